@@ -0,0 +1,311 @@
+use crate::error::AppError;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Metadata file stored next to each backed-up file
+const META_FILE: &str = "backup.json";
+
+/// How many backups to keep
+#[derive(Debug, Clone, Copy)]
+pub struct RetentionPolicy {
+    /// Backups kept per original file, newest first
+    pub max_per_file: usize,
+    /// Backups older than this are dropped (the newest one per file is always kept)
+    pub max_age_days: i64,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy {
+            max_per_file: 10,
+            max_age_days: 30,
+        }
+    }
+}
+
+/// One snapshot of a file that was about to be overwritten
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BackupEntry {
+    pub id: String,
+    pub original_path: String,
+    pub file_name: String,
+    /// What replaced the file, e.g. "save_bindings", "unbind_profile", "deploy_character"
+    pub reason: String,
+    /// RFC 3339 timestamp
+    pub created: String,
+    pub size: u64,
+}
+
+/// Write a file through a temp file in the same folder and rename it into place,
+/// so a crash never leaves a half-written file behind
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), AppError> {
+    let invalid = || AppError::InvalidInput(format!("Invalid target path: {}", path.display()));
+    let dir = path.parent().ok_or_else(invalid)?;
+    let file_name = path.file_name().ok_or_else(invalid)?.to_string_lossy();
+    let temp_path = dir.join(format!(".{}.tmp", file_name));
+
+    let result = (|| -> std::io::Result<()> {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp_path, path)
+    })();
+
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(AppError::io("Failed to write", path, e));
+    }
+
+    Ok(())
+}
+
+/// Copy `target` into the backup area if it exists
+/// Returns None when there was nothing to back up
+pub fn backup_existing(
+    backup_root: &Path,
+    target: &Path,
+    reason: &str,
+    policy: RetentionPolicy,
+) -> Result<Option<BackupEntry>, AppError> {
+    snapshot(backup_root, target, reason, policy, None)
+}
+
+/// `backup_existing`, with retention never removing the backup `keep`
+fn snapshot(
+    backup_root: &Path,
+    target: &Path,
+    reason: &str,
+    policy: RetentionPolicy,
+    keep: Option<&str>,
+) -> Result<Option<BackupEntry>, AppError> {
+    if !target.is_file() {
+        return Ok(None);
+    }
+
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!("Invalid target path: {}", target.display()))
+        })?
+        .to_string_lossy()
+        .to_string();
+
+    let now = chrono::Local::now();
+    let stamp = now.format("%Y%m%d-%H%M%S%3f").to_string();
+
+    // Unique folder per snapshot; add a counter if two land in the same millisecond
+    let mut id = stamp.clone();
+    let mut counter = 1;
+    while backup_root.join(&id).exists() {
+        id = format!("{}-{}", stamp, counter);
+        counter += 1;
+    }
+
+    let backup_dir = backup_root.join(&id);
+    fs::create_dir_all(&backup_dir)
+        .map_err(|e| AppError::io("Failed to create backup directory", &backup_dir, e))?;
+
+    let size = fs::copy(target, backup_dir.join(&file_name))
+        .map_err(|e| AppError::io("Failed to back up", target, e))?;
+
+    let entry = BackupEntry {
+        id,
+        original_path: target.to_string_lossy().to_string(),
+        file_name,
+        reason: reason.to_string(),
+        created: now.to_rfc3339(),
+        size,
+    };
+
+    let meta = serde_json::to_string_pretty(&entry)?;
+    write_atomic(&backup_dir.join(META_FILE), meta.as_bytes())?;
+
+    info!("Backed up {} to {}", target.display(), backup_dir.display());
+
+    if let Err(e) = apply_retention(backup_root, &entry.original_path, policy, keep) {
+        warn!("Failed to apply backup retention: {}", e);
+    }
+
+    Ok(Some(entry))
+}
+
+/// Back up whatever is at `target`, then write the new contents atomically
+pub fn write_with_backup(
+    backup_root: &Path,
+    target: &Path,
+    contents: &[u8],
+    reason: &str,
+) -> Result<Option<BackupEntry>, AppError> {
+    let entry = backup_existing(backup_root, target, reason, RetentionPolicy::default())?;
+    crate::watcher::note_own_write(target, contents);
+    write_atomic(target, contents)?;
+    Ok(entry)
+}
+
+/// All backups, newest first
+pub fn list_backups(backup_root: &Path) -> Result<Vec<BackupEntry>, AppError> {
+    let entries = match fs::read_dir(backup_root) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(AppError::io(
+                "Failed to read backup directory",
+                backup_root,
+                e,
+            ))
+        }
+    };
+
+    let mut backups: Vec<BackupEntry> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let meta = fs::read_to_string(entry.path().join(META_FILE)).ok()?;
+            serde_json::from_str(&meta).ok()
+        })
+        .collect();
+
+    backups.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(backups)
+}
+
+/// Put a backup back at its original location
+/// The file currently there is backed up first, so a restore can be undone
+pub fn restore_backup(backup_root: &Path, id: &str) -> Result<BackupEntry, AppError> {
+    // Ids are folder names we generated; refuse anything that could leave the backup area
+    if id.is_empty() || id.contains(['/', '\\']) || id.contains("..") {
+        return Err(AppError::InvalidInput(format!("Invalid backup id: {}", id)));
+    }
+
+    let backup_dir = backup_root.join(id);
+    let meta_path = backup_dir.join(META_FILE);
+    let meta = fs::read_to_string(&meta_path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => AppError::NotFound(format!("Backup {} not found", id)),
+        _ => AppError::io("Failed to read backup metadata", &meta_path, e),
+    })?;
+    let entry: BackupEntry = serde_json::from_str(&meta)?;
+
+    let backup_file = backup_dir.join(&entry.file_name);
+    let contents = fs::read(&backup_file)
+        .map_err(|e| AppError::io("Failed to read backup file", &backup_file, e))?;
+
+    let target = PathBuf::from(&entry.original_path);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io("Failed to create target directory", parent, e))?;
+    }
+
+    // The restored backup may be past the age limit; retention must not take it with it
+    snapshot(
+        backup_root,
+        &target,
+        "before_restore",
+        RetentionPolicy::default(),
+        Some(id),
+    )?;
+    crate::watcher::note_own_write(&target, &contents);
+    write_atomic(&target, &contents)?;
+
+    info!("Restored backup {} to {}", id, target.display());
+    Ok(entry)
+}
+
+/// Drop old backups of one original file according to the policy, except `keep`
+fn apply_retention(
+    backup_root: &Path,
+    original_path: &str,
+    policy: RetentionPolicy,
+    keep: Option<&str>,
+) -> Result<(), AppError> {
+    let cutoff = chrono::Local::now() - chrono::Duration::days(policy.max_age_days);
+
+    let backups: Vec<BackupEntry> = list_backups(backup_root)?
+        .into_iter()
+        .filter(|b| b.original_path == original_path)
+        .collect();
+
+    for (index, backup) in backups.iter().enumerate() {
+        // Always keep the newest backup
+        if index == 0 || keep == Some(backup.id.as_str()) {
+            continue;
+        }
+
+        let too_old = chrono::DateTime::parse_from_rfc3339(&backup.created)
+            .map(|created| created < cutoff)
+            .unwrap_or(false);
+
+        if index >= policy.max_per_file || too_old {
+            match fs::remove_dir_all(backup_root.join(&backup.id)) {
+                Ok(_) => info!("Removed old backup {}", backup.id),
+                Err(e) => warn!("Failed to remove old backup {}: {}", backup.id, e),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restoring_an_expired_backup_keeps_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup_root = dir.path().join("backups");
+        let target = dir.path().join("layout.xml");
+
+        fs::write(&target, "old").unwrap();
+        let old = backup_existing(&backup_root, &target, "save_bindings", Default::default())
+            .unwrap()
+            .unwrap();
+        fs::write(&target, "new").unwrap();
+
+        // Age the backup past the retention limit
+        let meta_path = backup_root.join(&old.id).join(META_FILE);
+        let aged = BackupEntry {
+            created: (chrono::Local::now() - chrono::Duration::days(90)).to_rfc3339(),
+            ..old.clone()
+        };
+        fs::write(&meta_path, serde_json::to_string(&aged).unwrap()).unwrap();
+
+        restore_backup(&backup_root, &old.id).unwrap();
+
+        assert_eq!(fs::read_to_string(&target).unwrap(), "old");
+        let ids: Vec<String> = list_backups(&backup_root)
+            .unwrap()
+            .into_iter()
+            .map(|b| b.id)
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&old.id));
+    }
+
+    #[test]
+    fn retention_drops_expired_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup_root = dir.path().join("backups");
+        let target = dir.path().join("layout.xml");
+
+        fs::write(&target, "v1").unwrap();
+        let old = backup_existing(&backup_root, &target, "save_bindings", Default::default())
+            .unwrap()
+            .unwrap();
+        let meta_path = backup_root.join(&old.id).join(META_FILE);
+        let aged = BackupEntry {
+            created: (chrono::Local::now() - chrono::Duration::days(90)).to_rfc3339(),
+            ..old.clone()
+        };
+        fs::write(&meta_path, serde_json::to_string(&aged).unwrap()).unwrap();
+
+        fs::write(&target, "v2").unwrap();
+        write_with_backup(&backup_root, &target, b"v3", "save_bindings").unwrap();
+
+        let backups = list_backups(&backup_root).unwrap();
+        assert_eq!(backups.len(), 1);
+        assert_ne!(backups[0].id, old.id);
+    }
+}
@@ -0,0 +1,242 @@
+use crate::backup;
+use crate::error::AppError;
+use crate::sc_install::{self, ScInstallation};
+use log::info;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How a character file in an installation compares to the library copy
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CopyStatus {
+    Identical,
+    /// Contents differ from the library (or the library has no copy)
+    Different,
+    Missing,
+}
+
+/// One installation's copy of a character
+#[derive(Debug, Serialize, Clone)]
+pub struct CharacterCopy {
+    pub installation: String,
+    pub path: String,
+    pub status: CopyStatus,
+    pub hash: Option<String>,
+    /// Unix timestamp in seconds
+    pub modified: Option<u64>,
+}
+
+/// A character file across the library and every installation
+#[derive(Debug, Serialize, Clone)]
+pub struct CharacterReport {
+    pub name: String,
+    /// None when the character only exists in installations
+    pub library_hash: Option<String>,
+    pub copies: Vec<CharacterCopy>,
+}
+
+/// Outcome of copying one character file
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferStatus {
+    Copied,
+    /// The target already had the same contents
+    Unchanged,
+    /// The target has different contents and overwriting wasn't allowed; nothing was written
+    Conflict,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CharacterTransfer {
+    pub name: String,
+    pub source_path: String,
+    pub target_path: String,
+    pub status: TransferStatus,
+    pub error: Option<String>,
+}
+
+/// SHA-256 of a file's contents as lowercase hex
+pub fn file_hash(path: &Path) -> Result<String, AppError> {
+    let contents = fs::read(path).map_err(|e| AppError::io("Failed to read", path, e))?;
+    Ok(hash_bytes(&contents))
+}
+
+fn hash_bytes(contents: &[u8]) -> String {
+    Sha256::digest(contents)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn modified_secs(path: &Path) -> Option<u64> {
+    fs::metadata(path)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+/// `.chf` files in a folder by name; a missing folder has none
+fn character_files(dir: &Path) -> BTreeMap<String, PathBuf> {
+    let mut files = BTreeMap::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_file() && path.extension().is_some_and(|e| e == "chf") {
+            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+                files.insert(name.to_string(), path.clone());
+            }
+        }
+    }
+    files
+}
+
+/// Compare every character in the library and the installations by content
+pub fn character_report(
+    library: Option<&Path>,
+    installations: &[ScInstallation],
+) -> Vec<CharacterReport> {
+    let library_files = library.map(character_files).unwrap_or_default();
+    let install_files: Vec<(&ScInstallation, BTreeMap<String, PathBuf>)> = installations
+        .iter()
+        .map(|install| {
+            let dir = sc_install::custom_characters_dir(Path::new(&install.path));
+            (install, character_files(&dir))
+        })
+        .collect();
+
+    let mut names: Vec<&String> = library_files.keys().collect();
+    for (_, files) in &install_files {
+        names.extend(files.keys());
+    }
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .map(|name| {
+            let library_hash = library_files.get(name).and_then(|p| file_hash(p).ok());
+
+            let copies = install_files
+                .iter()
+                .map(|(install, files)| {
+                    let path = files.get(name).cloned().unwrap_or_else(|| {
+                        sc_install::custom_characters_dir(Path::new(&install.path)).join(name)
+                    });
+                    let hash = files.get(name).and_then(|p| file_hash(p).ok());
+                    let status = match (&hash, &library_hash) {
+                        (None, _) => CopyStatus::Missing,
+                        (Some(h), Some(lib)) if h == lib => CopyStatus::Identical,
+                        _ => CopyStatus::Different,
+                    };
+                    CharacterCopy {
+                        installation: install.name.clone(),
+                        path: path.to_string_lossy().to_string(),
+                        status,
+                        modified: hash.as_ref().and_then(|_| modified_secs(&path)),
+                        hash,
+                    }
+                })
+                .collect();
+
+            CharacterReport {
+                name: name.clone(),
+                library_hash,
+                copies,
+            }
+        })
+        .collect()
+}
+
+/// Copy a character file unless the target already holds different contents and
+/// `overwrite` is false. Replaced files are backed up first.
+pub fn copy_character(
+    source: &Path,
+    target: &Path,
+    overwrite: bool,
+    backup_root: &Path,
+    reason: &str,
+) -> Result<TransferStatus, AppError> {
+    let contents =
+        fs::read(source).map_err(|e| AppError::io("Failed to read character file", source, e))?;
+
+    if target.is_file() {
+        let existing = file_hash(target)?;
+        if existing == hash_bytes(&contents) {
+            return Ok(TransferStatus::Unchanged);
+        }
+        if !overwrite {
+            return Ok(TransferStatus::Conflict);
+        }
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| AppError::io("Failed to create target directory", parent, e))?;
+    }
+    backup::write_with_backup(backup_root, target, &contents, reason)?;
+
+    info!(
+        "Copied character {} to {}",
+        source.display(),
+        target.display()
+    );
+    Ok(TransferStatus::Copied)
+}
+
+fn transfer(
+    name: &str,
+    source: &Path,
+    target: &Path,
+    overwrite: bool,
+    backup_root: &Path,
+    reason: &str,
+) -> CharacterTransfer {
+    let (status, error) = match copy_character(source, target, overwrite, backup_root, reason) {
+        Ok(status) => (status, None),
+        Err(e) => (TransferStatus::Failed, Some(e.to_string())),
+    };
+    CharacterTransfer {
+        name: name.to_string(),
+        source_path: source.to_string_lossy().to_string(),
+        target_path: target.to_string_lossy().to_string(),
+        status,
+        error,
+    }
+}
+
+/// Push every library character to every installation
+/// Different copies are reported as conflicts unless `overwrite` is set
+pub fn sync_library(
+    library: &Path,
+    installations: &[ScInstallation],
+    overwrite: bool,
+    backup_root: &Path,
+) -> Vec<CharacterTransfer> {
+    let library_files = character_files(library);
+    let mut results = Vec::new();
+
+    for install in installations {
+        let target_dir = sc_install::custom_characters_dir(Path::new(&install.path));
+        for (name, source) in &library_files {
+            results.push(transfer(
+                name,
+                source,
+                &target_dir.join(name),
+                overwrite,
+                backup_root,
+                "sync_characters",
+            ));
+        }
+    }
+
+    results
+}
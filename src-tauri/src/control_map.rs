@@ -0,0 +1,403 @@
+use crate::keybindings::ActionMaps;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Correspondence table between the controls of two sticks
+/// Keys and values are Star Citizen control names without the instance prefix,
+/// e.g. "button3" -> "button5", "hat1_up" -> "hat1_up", "rotz" -> "rotz"
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ControlMap {
+    pub entries: HashMap<String, String>,
+}
+
+/// A control map generated from two template pages, plus the buttons that had no counterpart
+#[derive(Debug, Serialize, Clone)]
+pub struct GeneratedControlMap {
+    pub control_map: ControlMap,
+    pub unmatched_buttons: Vec<String>,
+}
+
+/// How joystick rebinds are carried across
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MirrorMode {
+    /// Rebinds on the source stick are rewritten onto the target stick
+    /// (SC keeps one joystick rebind per action, so the source binding is replaced;
+    /// the report lists every binding that moved off the source stick)
+    Copy,
+    /// Both sticks are translated across at once: source -> target through the table,
+    /// target -> source through the inverse table (left/right swap)
+    Mirror,
+}
+
+/// A rebind the transform had to leave untouched
+#[derive(Debug, Serialize, Clone)]
+pub struct UnmappedBinding {
+    pub action_map: String,
+    pub action: String,
+    pub input: String,
+    pub reason: String,
+}
+
+/// A source stick rebind that a copy moved onto the target stick
+#[derive(Debug, Serialize, Clone)]
+pub struct DisplacedBinding {
+    pub action_map: String,
+    pub action: String,
+    /// The source stick input the action no longer has
+    pub input: String,
+    pub replaced_by: String,
+}
+
+/// Result of a mirror transform
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct MirrorReport {
+    pub mapped_count: usize,
+    pub unmapped: Vec<UnmappedBinding>,
+    /// Copy only: source bindings replaced by their target stick counterpart
+    pub displaced: Vec<DisplacedBinding>,
+}
+
+impl ControlMap {
+    /// Build the inverse table (target -> source); the first entry wins on duplicates
+    pub fn inverse(&self) -> ControlMap {
+        let mut sorted: Vec<(&String, &String)> = self.entries.iter().collect();
+        sorted.sort();
+
+        let mut entries = HashMap::new();
+        for (source, target) in sorted {
+            entries
+                .entry(target.clone())
+                .or_insert_with(|| source.clone());
+        }
+        ControlMap { entries }
+    }
+
+    /// Generate a control map from two template pages by matching buttons by name
+    /// and pairing up their inputs (main/up/down/left/right/push)
+    pub fn from_template_pages(
+        source_page: &serde_json::Value,
+        target_page: &serde_json::Value,
+    ) -> Result<GeneratedControlMap, String> {
+        let source_buttons = page_buttons(source_page)?;
+        let target_buttons = page_buttons(target_page)?;
+
+        let mut entries = HashMap::new();
+        let mut unmatched_buttons = Vec::new();
+
+        for source_button in source_buttons {
+            let name = button_name(source_button);
+            let Some(target_button) = target_buttons
+                .iter()
+                .find(|b| button_name(b).eq_ignore_ascii_case(&name))
+            else {
+                unmatched_buttons.push(name);
+                continue;
+            };
+
+            let (Some(source_inputs), Some(target_inputs)) = (
+                source_button.get("inputs").and_then(|i| i.as_object()),
+                target_button.get("inputs").and_then(|i| i.as_object()),
+            ) else {
+                continue;
+            };
+
+            for (slot, source_input) in source_inputs {
+                let (Some(source_input), Some(target_input)) = (
+                    source_input.as_str(),
+                    target_inputs.get(slot).and_then(|v| v.as_str()),
+                ) else {
+                    continue;
+                };
+
+                let source_control = strip_instance(source_input);
+                let target_control = strip_instance(target_input);
+                if source_control.is_empty() || target_control.is_empty() {
+                    continue;
+                }
+
+                entries.insert(source_control.to_string(), target_control.to_string());
+            }
+        }
+
+        Ok(GeneratedControlMap {
+            control_map: ControlMap { entries },
+            unmatched_buttons,
+        })
+    }
+}
+
+/// Find a page in a template JSON document
+/// Matches a page `id` or `name` in the paged format, or "left"/"right" for the old
+/// leftStick/rightStick format
+pub fn find_template_page<'a>(
+    template: &'a serde_json::Value,
+    page: &str,
+) -> Option<&'a serde_json::Value> {
+    if let Some(pages) = template.get("pages").and_then(|p| p.as_array()) {
+        let found = pages.iter().find(|p| {
+            p.get("id").and_then(|v| v.as_str()) == Some(page)
+                || p.get("name")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|n| n.eq_ignore_ascii_case(page))
+        });
+        if found.is_some() {
+            return found;
+        }
+    }
+
+    match page.to_lowercase().as_str() {
+        "left" | "leftstick" => template.get("leftStick"),
+        "right" | "rightstick" => template.get("rightStick"),
+        _ => None,
+    }
+}
+
+fn page_buttons(page: &serde_json::Value) -> Result<&Vec<serde_json::Value>, String> {
+    page.get("buttons")
+        .and_then(|b| b.as_array())
+        .ok_or_else(|| "Template page has no buttons".to_string())
+}
+
+fn button_name(button: &serde_json::Value) -> String {
+    button
+        .get("name")
+        .and_then(|n| n.as_str())
+        .unwrap_or("")
+        .trim()
+        .to_string()
+}
+
+/// Strip a "jsN_" instance prefix: "js2_button11" -> "button11", "button11" -> "button11"
+pub fn strip_instance(input: &str) -> &str {
+    let input = input.trim();
+    if let Some(rest) = input.strip_prefix("js") {
+        if let Some((instance, control)) = rest.split_once('_') {
+            if !instance.is_empty() && instance.chars().all(|c| c.is_ascii_digit()) {
+                return control.trim();
+            }
+        }
+    }
+    input
+}
+
+/// Split "js1_button3" into (1, "button3"); returns None for non-joystick parts
+fn split_joystick_part(part: &str) -> Option<(u8, &str)> {
+    let rest = part.trim().strip_prefix("js")?;
+    let (instance, control) = rest.split_once('_')?;
+    Some((instance.parse().ok()?, control))
+}
+
+/// Translate one rebind input. Modifier parts ("lalt", "kb1_lshift") pass through,
+/// joystick parts on the swapped instances go through the relevant table.
+/// Returns Ok(None) if the input doesn't touch any of the swapped instances.
+fn translate_input(
+    input: &str,
+    tables: &[(u8, u8, &ControlMap)],
+) -> Result<Option<String>, String> {
+    let mut touched = false;
+    let mut parts = Vec::new();
+
+    for part in input.split('+') {
+        let Some((instance, control)) = split_joystick_part(part) else {
+            parts.push(part.to_string());
+            continue;
+        };
+        let Some((_, to_instance, table)) = tables.iter().find(|(from, _, _)| *from == instance)
+        else {
+            parts.push(part.to_string());
+            continue;
+        };
+
+        touched = true;
+        let control = control.trim();
+        if control.is_empty() {
+            // Cleared binding ("js1_ ") - carry the explicit unbind across as-is
+            parts.push(format!("js{}_ ", to_instance));
+            continue;
+        }
+
+        match table.entries.get(control) {
+            Some(mapped) => parts.push(format!("js{}_{}", to_instance, mapped)),
+            None => return Err(format!("No counterpart for '{}' in control map", control)),
+        }
+    }
+
+    Ok(touched.then(|| parts.join("+")))
+}
+
+/// Copy or mirror every joystick rebind from one stick instance to another
+pub fn mirror_bindings(
+    bindings: &mut ActionMaps,
+    source_instance: u8,
+    target_instance: u8,
+    control_map: &ControlMap,
+    mode: MirrorMode,
+) -> Result<MirrorReport, String> {
+    if source_instance == target_instance {
+        return Err("Source and target instance must differ".to_string());
+    }
+
+    let inverse = control_map.inverse();
+    let mut tables = vec![(source_instance, target_instance, control_map)];
+    if mode == MirrorMode::Mirror {
+        tables.push((target_instance, source_instance, &inverse));
+    }
+
+    let mut report = MirrorReport::default();
+
+    for action_map in &mut bindings.action_maps {
+        for action in &mut action_map.actions {
+            for rebind in &mut action.rebinds {
+                match translate_input(&rebind.input, &tables) {
+                    Ok(Some(translated)) => {
+                        if mode == MirrorMode::Copy {
+                            report.displaced.push(DisplacedBinding {
+                                action_map: action_map.name.clone(),
+                                action: action.name.clone(),
+                                input: rebind.input.clone(),
+                                replaced_by: translated.clone(),
+                            });
+                        }
+                        rebind.input = translated;
+                        report.mapped_count += 1;
+                    }
+                    Ok(None) => {}
+                    Err(reason) => report.unmapped.push(UnmappedBinding {
+                        action_map: action_map.name.clone(),
+                        action: action.name.clone(),
+                        input: rebind.input.clone(),
+                        reason,
+                    }),
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(pairs: &[(&str, &str)]) -> ControlMap {
+        ControlMap {
+            entries: pairs
+                .iter()
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .collect(),
+        }
+    }
+
+    fn bindings(rebinds: &[(&str, &str)]) -> ActionMaps {
+        let actions: String = rebinds
+            .iter()
+            .map(|(action, input)| {
+                format!(
+                    "<action name=\"{}\"><rebind input=\"{}\"/></action>",
+                    action, input
+                )
+            })
+            .collect();
+        let xml = format!(
+            "<ActionMaps profileName=\"test\"><actionmap name=\"spaceship_weapons\">{}</actionmap></ActionMaps>",
+            actions
+        );
+        ActionMaps::from_xml(&xml).unwrap()
+    }
+
+    fn inputs(bindings: &ActionMaps) -> Vec<(&str, &str)> {
+        bindings.action_maps[0]
+            .actions
+            .iter()
+            .flat_map(|action| {
+                action
+                    .rebinds
+                    .iter()
+                    .map(|rebind| (action.name.as_str(), rebind.input.as_str()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn translate_input_passes_modifiers_through() {
+        let map = table(&[("button3", "button5"), ("button4", "button6")]);
+        let tables = [(1, 2, &map)];
+
+        assert_eq!(
+            translate_input("lalt+js1_button3", &tables),
+            Ok(Some("lalt+js2_button5".to_string()))
+        );
+        assert_eq!(
+            translate_input("js1_button4+js1_button3", &tables),
+            Ok(Some("js2_button6+js2_button5".to_string()))
+        );
+        assert_eq!(translate_input("kb1_lshift+a", &tables), Ok(None));
+        assert_eq!(translate_input("js3_button3", &tables), Ok(None));
+    }
+
+    #[test]
+    fn translate_input_carries_cleared_bindings() {
+        let map = table(&[]);
+        assert_eq!(
+            translate_input("js1_ ", &[(1, 2, &map)]),
+            Ok(Some("js2_ ".to_string()))
+        );
+    }
+
+    #[test]
+    fn translate_input_rejects_unmapped_controls() {
+        let map = table(&[("button3", "button5")]);
+        assert!(translate_input("js1_button9", &[(1, 2, &map)]).is_err());
+    }
+
+    #[test]
+    fn copy_moves_source_rebinds_and_reports_them() {
+        let mut maps = bindings(&[
+            ("v_attack1", "js1_button3"),
+            ("v_attack2", "js2_button7"),
+            ("v_attack3", "js1_button9"),
+        ]);
+        let map = table(&[("button3", "button5")]);
+
+        let report = mirror_bindings(&mut maps, 1, 2, &map, MirrorMode::Copy).unwrap();
+
+        assert_eq!(
+            inputs(&maps),
+            [
+                ("v_attack1", "js2_button5"),
+                ("v_attack2", "js2_button7"),
+                ("v_attack3", "js1_button9"),
+            ]
+        );
+        assert_eq!(report.mapped_count, 1);
+        assert_eq!(report.displaced.len(), 1);
+        assert_eq!(report.displaced[0].input, "js1_button3");
+        assert_eq!(report.displaced[0].replaced_by, "js2_button5");
+        assert_eq!(report.unmapped.len(), 1);
+        assert_eq!(report.unmapped[0].input, "js1_button9");
+    }
+
+    #[test]
+    fn mirror_swaps_both_sticks() {
+        let mut maps = bindings(&[("v_attack1", "js1_button3"), ("v_attack2", "js2_button5")]);
+        let map = table(&[("button3", "button5"), ("button5", "button3")]);
+
+        let report = mirror_bindings(&mut maps, 1, 2, &map, MirrorMode::Mirror).unwrap();
+
+        assert_eq!(
+            inputs(&maps),
+            [("v_attack1", "js2_button5"), ("v_attack2", "js1_button3")]
+        );
+        assert_eq!(report.mapped_count, 2);
+        assert!(report.displaced.is_empty());
+        assert!(report.unmapped.is_empty());
+    }
+
+    #[test]
+    fn mirror_rejects_same_instance() {
+        let mut maps = bindings(&[("v_attack1", "js1_button3")]);
+        assert!(mirror_bindings(&mut maps, 1, 1, &table(&[]), MirrorMode::Copy).is_err());
+    }
+}
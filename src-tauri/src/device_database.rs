@@ -0,0 +1,232 @@
+use crate::error::AppError;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+/// File name of both the bundled database and the user override in app data
+pub const DATABASE_FILE: &str = "device-database.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceKind {
+    Joystick,
+    Throttle,
+    Pedals,
+    Gamepad,
+}
+
+/// A known device
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeviceEntry {
+    /// Hex, e.g. "0x231d"
+    pub vendor_id: String,
+    pub product_id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: DeviceKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub axis_profile: Option<String>,
+}
+
+/// Logical axis name -> raw axis index
+pub type AxisProfile = BTreeMap<String, u32>;
+
+/// Layout of `device-database.json` (bundled and user override alike)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DatabaseFile {
+    /// Vendor id -> manufacturer name, for devices without an entry
+    #[serde(default)]
+    pub vendors: BTreeMap<String, String>,
+    #[serde(default)]
+    pub devices: Vec<DeviceEntry>,
+    #[serde(default)]
+    pub axis_profiles: BTreeMap<String, AxisProfile>,
+}
+
+/// The bundled database with the user's overrides applied
+#[derive(Debug, Default)]
+pub struct DeviceDatabase {
+    devices: HashMap<(u16, u16), DeviceEntry>,
+    vendors: HashMap<u16, String>,
+    axis_profiles: BTreeMap<String, AxisProfile>,
+    bundled: DatabaseFile,
+    user: DatabaseFile,
+    user_path: Option<PathBuf>,
+}
+
+static DATABASE: OnceLock<RwLock<DeviceDatabase>> = OnceLock::new();
+
+fn database() -> &'static RwLock<DeviceDatabase> {
+    DATABASE.get_or_init(|| RwLock::new(DeviceDatabase::default()))
+}
+
+/// "0x231d" or "231d"
+pub fn parse_hex_id(id: &str) -> Option<u16> {
+    let id = id.trim();
+    let digits = id
+        .strip_prefix("0x")
+        .or_else(|| id.strip_prefix("0X"))
+        .unwrap_or(id);
+    u16::from_str_radix(digits, 16).ok()
+}
+
+fn read_file(path: &Path) -> Result<DatabaseFile, String> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+}
+
+impl DeviceDatabase {
+    fn merge(&mut self, file: &DatabaseFile) {
+        for (vendor, name) in &file.vendors {
+            match parse_hex_id(vendor) {
+                Some(id) => {
+                    self.vendors.insert(id, name.clone());
+                }
+                None => warn!("Ignoring device database vendor with bad id '{}'", vendor),
+            }
+        }
+        for entry in &file.devices {
+            match (
+                parse_hex_id(&entry.vendor_id),
+                parse_hex_id(&entry.product_id),
+            ) {
+                (Some(vendor), Some(product)) => {
+                    self.devices.insert((vendor, product), entry.clone());
+                }
+                _ => warn!(
+                    "Ignoring device database entry '{}' with bad ids {}:{}",
+                    entry.name, entry.vendor_id, entry.product_id
+                ),
+            }
+        }
+        self.axis_profiles.extend(
+            file.axis_profiles
+                .iter()
+                .map(|(name, profile)| (name.clone(), profile.clone())),
+        );
+    }
+
+    /// Rebuild the lookup tables: bundled entries first, user entries on top
+    fn rebuild(&mut self) {
+        self.devices.clear();
+        self.vendors.clear();
+        self.axis_profiles.clear();
+        let (bundled, user) = (
+            std::mem::take(&mut self.bundled),
+            std::mem::take(&mut self.user),
+        );
+        self.merge(&bundled);
+        self.merge(&user);
+        self.bundled = bundled;
+        self.user = user;
+    }
+
+    fn load(bundled: &Path, user_path: PathBuf) -> Self {
+        let mut db = DeviceDatabase::default();
+        match read_file(bundled) {
+            Ok(file) => db.bundled = file,
+            Err(e) => warn!("Device database not loaded: {}", e),
+        }
+        if user_path.exists() {
+            match read_file(&user_path) {
+                Ok(file) => db.user = file,
+                Err(e) => warn!("Ignoring user device database: {}", e),
+            }
+        }
+        db.user_path = Some(user_path);
+        db.rebuild();
+        db
+    }
+
+    fn save_user(&self) -> Result<(), AppError> {
+        let Some(path) = &self.user_path else {
+            return Err(AppError::Other(
+                "Device database not initialized".to_string(),
+            ));
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::io("Failed to create app data directory", parent, e))?;
+        }
+        let json = serde_json::to_string_pretty(&self.user)?;
+        crate::backup::write_atomic(path, json.as_bytes())
+    }
+}
+
+/// Load the bundled database and the user override; a missing or broken file only logs
+pub fn init(bundled: &Path, user_path: PathBuf) {
+    let db = DeviceDatabase::load(bundled, user_path);
+    info!(
+        "Device database: {} devices, {} axis profiles",
+        db.devices.len(),
+        db.axis_profiles.len()
+    );
+    *database().write().unwrap() = db;
+}
+
+pub fn lookup(vendor_id: u16, product_id: u16) -> Option<DeviceEntry> {
+    database()
+        .read()
+        .unwrap()
+        .devices
+        .get(&(vendor_id, product_id))
+        .cloned()
+}
+
+pub fn vendor_name(vendor_id: u16) -> Option<String> {
+    database().read().unwrap().vendors.get(&vendor_id).cloned()
+}
+
+/// Every known device (overrides applied), sorted by name, plus the axis profiles
+pub fn snapshot() -> DatabaseFile {
+    let db = database().read().unwrap();
+    let mut devices: Vec<DeviceEntry> = db.devices.values().cloned().collect();
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    DatabaseFile {
+        vendors: db
+            .vendors
+            .iter()
+            .map(|(id, name)| (format!("0x{:04x}", id), name.clone()))
+            .collect(),
+        devices,
+        axis_profiles: db.axis_profiles.clone(),
+    }
+}
+
+/// Add or replace a device in the user override file
+pub fn set_user_device(entry: DeviceEntry) -> Result<(), AppError> {
+    let (Some(vendor), Some(product)) = (
+        parse_hex_id(&entry.vendor_id),
+        parse_hex_id(&entry.product_id),
+    ) else {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid device id {}:{}",
+            entry.vendor_id, entry.product_id
+        )));
+    };
+
+    let mut db = database().write().unwrap();
+    db.user.devices.retain(|e| !same_device(e, vendor, product));
+    db.user.devices.push(entry);
+    db.rebuild();
+    db.save_user()
+}
+
+/// Drop a device from the user override file; the bundled entry (if any) applies again
+pub fn remove_user_device(vendor_id: u16, product_id: u16) -> Result<(), AppError> {
+    let mut db = database().write().unwrap();
+    db.user
+        .devices
+        .retain(|e| !same_device(e, vendor_id, product_id));
+    db.rebuild();
+    db.save_user()
+}
+
+fn same_device(entry: &DeviceEntry, vendor_id: u16, product_id: u16) -> bool {
+    parse_hex_id(&entry.vendor_id) == Some(vendor_id)
+        && parse_hex_id(&entry.product_id) == Some(product_id)
+}
@@ -1,7 +1,13 @@
+use crate::device_database;
+#[cfg(target_os = "linux")]
+use crate::evdev_input;
 use crate::hid_reader;
-use rusty_xinput::{XInputHandle, XInputState};
-use serde::Serialize;
+use crate::hid_service::HidService;
+use crate::input_source::{self, GamepadState, InputSample, InputSource, SourceInfo, XInput};
+use crate::sc_keys;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 use std::thread;
 use std::time::{Duration, Instant};
 use tauri::Emitter;
@@ -10,8 +16,33 @@ use tauri::Emitter;
 const AXIS_TRIGGER_THRESHOLD: f32 = 0.5;
 const AXIS_RESET_THRESHOLD: f32 = 0.3;
 const MOVEMENT_THRESHOLD: f32 = 0.3;
+// HID Generic Desktop usage for hat switches; hats share it in HidFullReport::axis_values
+const HID_USAGE_HAT_SWITCH: u32 = 0x39;
+
+/// XInput button mask -> gpN_buttonN number
+pub const XINPUT_BUTTONS: [(u16, u32); 14] = [
+    (0x1000, 1),  // A
+    (0x2000, 2),  // B
+    (0x4000, 3),  // X
+    (0x8000, 4),  // Y
+    (0x0100, 5),  // LB
+    (0x0200, 6),  // RB
+    (0x0010, 7),  // Back
+    (0x0020, 8),  // Start
+    (0x0040, 9),  // LS
+    (0x0080, 10), // RS
+    (0x0001, 11), // DPad Up
+    (0x0002, 12), // DPad Down
+    (0x0004, 13), // DPad Left
+    (0x0008, 14), // DPad Right
+];
+
+fn is_gamepad(vendor_id: u16, product_id: u16, name: &str) -> bool {
+    // Known devices are classified by the device database, not by name
+    if let Some(known) = device_database::lookup(vendor_id, product_id) {
+        return known.kind == device_database::DeviceKind::Gamepad;
+    }
 
-fn is_gamepad(name: &str) -> bool {
     let name_lower = name.to_lowercase();
 
     eprintln!("is_gamepad: Checking device: '{}'", name);
@@ -114,21 +145,97 @@ pub struct AxisMovement {
     pub value: f32,
 }
 
-// Stub for non-Windows platforms
-#[cfg(not(windows))]
+// Get currently pressed modifiers from the keyboards' evdev nodes
+#[cfg(target_os = "linux")]
+fn get_active_modifiers() -> Vec<String> {
+    evdev_input::pressed_keys()
+        .into_iter()
+        .filter(|key| sc_keys::MODIFIER_KEYS.contains(key))
+        .map(|key| key.to_uppercase())
+        .collect()
+}
+
+// Stub for other platforms
+#[cfg(not(any(windows, target_os = "linux")))]
 fn get_active_modifiers() -> Vec<String> {
     Vec::new()
 }
 
+// Keyboard keys and mouse buttons held right now: (SC prefix, key code, SC name)
+// Mouse buttons only count while another app is in front; clicks on our own window are UI
+#[cfg(windows)]
+fn pressed_keyboard_mouse_inputs() -> Vec<(&'static str, u16, &'static str)> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::GetAsyncKeyState;
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    let is_down = |vk: u16| vk != 0 && unsafe { GetAsyncKeyState(vk as i32) } as u16 & 0x8000 != 0;
+
+    let mut pressed: Vec<_> = sc_keys::KEYBOARD_KEYS
+        .iter()
+        .filter(|(_, vk, ..)| is_down(*vk))
+        .map(|&(_, vk, _, name, _)| ("kb1", vk, name))
+        .collect();
+
+    let mut foreground_pid = 0;
+    unsafe {
+        GetWindowThreadProcessId(GetForegroundWindow(), Some(&mut foreground_pid));
+    }
+    if foreground_pid != std::process::id() {
+        pressed.extend(
+            sc_keys::MOUSE_INPUTS
+                .iter()
+                .filter(|(_, vk, ..)| is_down(*vk))
+                .map(|&(_, vk, name, _)| ("mouse1", vk, name)),
+        );
+    }
+
+    pressed
+}
+
+// Keyboard keys held right now, read from evdev; the key code is the evdev one
+// Mice aren't read: their nodes usually aren't accessible and we can't tell clicks on our own window
+#[cfg(target_os = "linux")]
+fn pressed_keyboard_mouse_inputs() -> Vec<(&'static str, u16, &'static str)> {
+    let held = evdev_input::pressed_keys();
+    sc_keys::KEYBOARD_KEYS
+        .iter()
+        .filter(|(.., name, _)| held.contains(name))
+        .map(|&(_, _, code, name, _)| ("kb1", code, name))
+        .collect()
+}
+
+// Stub for other platforms
+#[cfg(not(any(windows, target_os = "linux")))]
+fn pressed_keyboard_mouse_inputs() -> Vec<(&'static str, u16, &'static str)> {
+    Vec::new()
+}
+
+/// Whether keyboard keys can be detected here; the binding modal falls back to the
+/// webview's own key events when they can't
+#[cfg(windows)]
+pub fn keyboard_available() -> bool {
+    true
+}
+
+#[cfg(target_os = "linux")]
+pub fn keyboard_available() -> bool {
+    evdev_input::keyboard_available()
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn keyboard_available() -> bool {
+    false
+}
+
 #[derive(Serialize, Clone, Debug)]
 pub struct DetectedInput {
     pub input_string: String, // Star Citizen format like "js1_button3", "js1_hat1_up", or "js1_axis1_positive"
     pub display_name: String,
     pub device_type: String,
     pub axis_value: Option<f32>,     // Raw axis value if applicable
-    pub modifiers: Vec<String>,      // Active modifiers: LALT, RALT, LCTRL, RCTRL, LSHIFT, RSHIFT
-    pub is_modifier: bool,           // True if this input itself is a modifier key
-    pub session_id: String, // Session ID to track which detection session this input belongs to
+    pub modifiers: Vec<String>, // Active modifiers: LALT, RALT, LCTRL, RCTRL, LSHIFT, RSHIFT, plus held declared modifiers like "js1_button5"
+    pub is_modifier: bool,      // True if this input itself is a modifier key
+    pub session_id: String,     // Session ID to track which detection session this input belongs to
     pub device_uuid: Option<String>, // Unique device identifier for persistent mapping
 
     // Extended debug information
@@ -146,7 +253,7 @@ pub struct DetectionComplete {
     pub session_id: String,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize)]
 pub struct JoystickInfo {
     pub id: usize,
     pub name: String,
@@ -157,6 +264,10 @@ pub struct JoystickInfo {
     pub hat_count: usize,
     pub device_type: String,
     pub uuid: Option<String>, // Hardware UUID (vendor_id:product_id format)
+    #[serde(default)]
+    pub axis_profile: Option<String>, // From the device database, when the device is known
+    #[serde(default)]
+    pub capabilities: Option<hid_reader::DeviceCapabilities>, // None when the descriptor couldn't be read
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -168,9 +279,19 @@ pub struct DeviceInfo {
     pub hat_count: usize,
     pub device_type: String,
     pub is_connected: bool,
+    pub axis_profile: Option<String>,
+    pub capabilities: Option<hid_reader::DeviceCapabilities>,
 }
 
-fn resolve_xinput_uuid(controller_id: u32) -> String {
+// Name for a device that has no product string and no database entry
+fn unknown_device_name(vendor_id: u16) -> String {
+    match device_database::vendor_name(vendor_id) {
+        Some(vendor) => format!("{} Device", vendor),
+        None => "Unknown HID Device".to_string(),
+    }
+}
+
+pub fn resolve_xinput_uuid(controller_id: u32) -> String {
     // Create a consistent UUID for XInput controllers based on their slot
     format!("xinput_{}", controller_id)
 }
@@ -184,542 +305,456 @@ struct AxisState {
 /// Handles the state and polling logic for input detection
 struct InputDetector {
     session_id: String,
-    xinput: Option<XInputHandle>,
-    xinput_prev_states: [Option<XInputState>; 4],
-    xinput_axis_states: HashMap<(u32, u32), AxisState>,
-    hid_devices: Vec<hid_reader::HidDeviceListItem>,
-    device_descriptors: HashMap<String, Vec<u8>>,
-    device_instances: HashMap<String, usize>,
-    device_hid_to_axis_maps: HashMap<String, HashMap<u32, u32>>,
-    prev_hid_reports: HashMap<String, hid_reader::HidFullReport>,
-    opened_devices: HashMap<String, hid_reader::OpenedHidDevice>,
+    sources: Vec<Box<dyn InputSource>>,
+    /// Last report per (jsN instance, report ID); devices with several input reports
+    /// carry different buttons and axes in each
+    prev_hid_reports: HashMap<(usize, u8), hid_reader::HidFullReport>,
+    /// Last state per gpN instance
+    prev_gamepad_states: HashMap<usize, GamepadState>,
+    gamepad_axis_states: HashMap<(usize, u32), AxisState>,
+    /// Inputs the profile declares as modifiers, e.g. "js1_button5"
+    declared_modifiers: Vec<String>,
+    /// Whether keyboard and mouse are polled; off when replaying a recording
+    keyboard_mouse: bool,
+    /// Keyboard keys and mouse buttons held at the last poll
+    prev_keyboard_mouse_inputs: Vec<(&'static str, u16, &'static str)>,
+}
+
+/// Chord of the held modifiers and the input, e.g. "js1_button5+js1_button3"
+fn chord_input(held_modifiers: &[String], input: &str) -> String {
+    held_modifiers
+        .iter()
+        .map(String::as_str)
+        .chain([input])
+        .collect::<Vec<_>>()
+        .join("+")
 }
 
 impl InputDetector {
-    fn new(session_id: String) -> Self {
-        eprintln!("InputDetector: Initializing...");
+    fn new(
+        session_id: String,
+        sources: Vec<Box<dyn InputSource>>,
+        declared_modifiers: Vec<String>,
+        keyboard_mouse: bool,
+    ) -> Self {
+        eprintln!("InputDetector: Monitoring {} input sources", sources.len());
 
-        // Initialize XInput
-        let xinput = XInputHandle::load_default().ok();
-        if xinput.is_none() {
-            eprintln!("InputDetector: Failed to load XInput (or not on Windows)");
+        Self {
+            session_id,
+            sources,
+            prev_hid_reports: HashMap::new(),
+            prev_gamepad_states: HashMap::new(),
+            gamepad_axis_states: HashMap::new(),
+            declared_modifiers,
+            keyboard_mouse,
+            // Keys held when detection starts (e.g. the one that opened it) aren't new presses
+            prev_keyboard_mouse_inputs: if keyboard_mouse {
+                pressed_keyboard_mouse_inputs()
+            } else {
+                Vec::new()
+            },
         }
+    }
 
-        let mut xinput_prev_states = [None, None, None, None];
-        let mut xinput_axis_states = HashMap::new();
-
-        if let Some(ref xinput) = xinput {
-            for i in 0..4 {
-                if let Ok(state) = xinput.get_state(i) {
-                    xinput_prev_states[i as usize] = Some(state);
-                    eprintln!("InputDetector: XInput controller {} initialized", i);
-
-                    // Initialize axis states for this controller
-                    let left_x = (state.raw.Gamepad.sThumbLX as f32) / 32768.0;
-                    let left_y = (state.raw.Gamepad.sThumbLY as f32) / 32768.0;
-                    let right_x = (state.raw.Gamepad.sThumbRX as f32) / 32768.0;
-                    let right_y = (state.raw.Gamepad.sThumbRY as f32) / 32768.0;
-                    let left_trigger = (state.raw.Gamepad.bLeftTrigger as f32) / 255.0;
-                    let right_trigger = (state.raw.Gamepad.bRightTrigger as f32) / 255.0;
-
-                    for (axis_idx, value) in [
-                        (1, left_x),
-                        (2, left_y),
-                        (3, right_x),
-                        (4, right_y),
-                        (5, left_trigger * 2.0 - 1.0),
-                        (6, right_trigger * 2.0 - 1.0),
-                    ] {
-                        xinput_axis_states.insert(
-                            (i, axis_idx),
-                            AxisState {
-                                last_value: value,
-                                last_triggered_direction: None,
-                            },
-                        );
+    /// Keyboard modifiers currently held; none when keyboard and mouse aren't polled
+    fn keyboard_modifiers(&self) -> Vec<String> {
+        if self.keyboard_mouse {
+            get_active_modifiers()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Declared modifier buttons already held on an XInput controller
+    fn held_gamepad_modifiers(&self, instance: usize) -> Vec<String> {
+        let Some(prev_state) = self.prev_gamepad_states.get(&instance) else {
+            return Vec::new();
+        };
+        XINPUT_BUTTONS
+            .iter()
+            .filter(|(mask, _)| prev_state.buttons & mask != 0)
+            .map(|(_, btn)| format!("gp{}_button{}", instance, btn))
+            .filter(|input| self.declared_modifiers.contains(input))
+            .collect()
+    }
+
+    /// Declared modifier buttons already held on a HID device, across its input reports
+    fn held_hid_modifiers(&self, instance: usize) -> Vec<String> {
+        let mut held: Vec<String> = self
+            .prev_hid_reports
+            .iter()
+            .filter(|((report_instance, _), _)| *report_instance == instance)
+            .flat_map(|(_, report)| &report.pressed_buttons)
+            .map(|btn| format!("js{}_button{}", instance, btn))
+            .filter(|input| self.declared_modifiers.contains(input))
+            .collect();
+        held.sort();
+        held.dedup();
+        held
+    }
+
+    fn poll(&mut self) -> Vec<DetectedInput> {
+        let mut detected_inputs = Vec::new();
+
+        // Poll keyboard and mouse
+        if self.keyboard_mouse {
+            self.detect_keyboard_mouse(&mut detected_inputs);
+        }
+
+        // Poll joysticks and gamepads
+        let mut sources = std::mem::take(&mut self.sources);
+        for source in &mut sources {
+            for sample in source.poll() {
+                match sample {
+                    InputSample::Joystick(report) => {
+                        self.detect_hid(source.info(), report, &mut detected_inputs)
+                    }
+                    InputSample::Gamepad(state) => {
+                        self.detect_gamepad(source.info(), state, &mut detected_inputs)
                     }
                 }
             }
         }
+        self.sources = sources;
 
-        // Initialize HID devices
-        let hid_devices = hid_reader::list_hid_game_controllers().unwrap_or_default();
-        let mut device_descriptors = HashMap::new();
-        let mut device_instances = HashMap::new();
-        let mut device_hid_to_axis_maps = HashMap::new();
-        let mut opened_devices = HashMap::new();
-
-        for (idx, device) in hid_devices.iter().enumerate() {
-            // Try to open the device persistently
-            match hid_reader::OpenedHidDevice::open(&device.path) {
-                Ok(opened_dev) => {
-                    opened_devices.insert(device.path.clone(), opened_dev);
-                }
-                Err(e) => {
-                    eprintln!(
-                        "InputDetector: Failed to open device {}: {}",
-                        device.path, e
-                    );
-                    continue;
-                }
-            }
+        detected_inputs
+    }
 
-            if let Ok(descriptor) = hid_reader::get_hid_descriptor_bytes(&device.path) {
-                device_descriptors.insert(device.path.clone(), descriptor);
-                device_instances.insert(device.path.clone(), idx + 1);
+    /// Whether every source has run out (only recordings do)
+    fn is_exhausted(&self) -> bool {
+        self.sources.iter().all(|source| source.is_exhausted())
+    }
 
-                // Get the DirectInput-to-HID mapping and invert it (HID usage ID -> DirectInput index)
-                if let Ok(di_to_hid) = hid_reader::get_directinput_to_hid_axis_mapping(&device.path)
-                {
-                    let mut hid_to_axis: HashMap<u32, u32> = HashMap::new();
-                    for (axis_idx, hid_usage_id) in di_to_hid {
-                        hid_to_axis.insert(hid_usage_id, axis_idx);
-                    }
-                    device_hid_to_axis_maps.insert(device.path.clone(), hid_to_axis);
-                }
+    fn detect_keyboard_mouse(&mut self, detected_inputs: &mut Vec<DetectedInput>) {
+        let keyboard_mouse_inputs = pressed_keyboard_mouse_inputs();
+        for &(prefix, key_code, name) in keyboard_mouse_inputs
+            .iter()
+            .filter(|input| !self.prev_keyboard_mouse_inputs.contains(input))
+        {
+            let (device_type, device_name) = if prefix == "mouse1" {
+                ("Mouse", "Mouse")
+            } else {
+                ("Keyboard", "Keyboard")
+            };
+            // A modifier key isn't its own modifier
+            let modifiers = get_active_modifiers()
+                .into_iter()
+                .filter(|m| !m.eq_ignore_ascii_case(name))
+                .collect();
+
+            detected_inputs.push(DetectedInput {
+                input_string: format!("{}_{}", prefix, name),
+                display_name: format!("{} - {}", device_name, sc_keys::label(name).unwrap_or(name)),
+                device_type: device_type.to_string(),
+                axis_value: None,
+                modifiers,
+                is_modifier: sc_keys::MODIFIER_KEYS.contains(&name),
+                session_id: self.session_id.clone(),
+                device_uuid: None,
+                raw_button_code: Some(format!("Key 0x{:02X}", key_code)),
+                raw_code_index: Some(key_code as u32),
+                device_name: Some(device_name.to_string()),
+                hid_usage_id: None,
+                hid_axis_name: None,
+            });
+        }
+        self.prev_keyboard_mouse_inputs = keyboard_mouse_inputs;
+    }
+
+    fn detect_gamepad(
+        &mut self,
+        info: &SourceInfo,
+        state: GamepadState,
+        detected_inputs: &mut Vec<DetectedInput>,
+    ) {
+        let sc_instance = info.instance;
+
+        // The first state only establishes the baseline
+        let Some(prev_state) = self.prev_gamepad_states.get(&sc_instance).copied() else {
+            for &(axis_index, value, _) in &state.axes {
+                self.gamepad_axis_states.insert(
+                    (sc_instance, axis_index),
+                    AxisState {
+                        last_value: value,
+                        last_triggered_direction: None,
+                    },
+                );
+            }
+            self.prev_gamepad_states.insert(sc_instance, state);
+            return;
+        };
+
+        // Check buttons
+        let buttons_pressed = state.buttons & !prev_state.buttons;
+
+        if buttons_pressed != 0 {
+            let held_modifiers = self.held_gamepad_modifiers(sc_instance);
+
+            // Every button that went down, not just the first
+            for &(mask, btn) in XINPUT_BUTTONS
+                .iter()
+                .filter(|(mask, _)| buttons_pressed & mask != 0)
+            {
+                let input = format!("gp{}_button{}", sc_instance, btn);
+                let mut modifiers = self.keyboard_modifiers();
+                modifiers.extend(held_modifiers.iter().cloned());
+                detected_inputs.push(DetectedInput {
+                    input_string: chord_input(&held_modifiers, &input),
+                    display_name: format!("Gamepad {} - Button {}", sc_instance, btn),
+                    device_type: "Gamepad".to_string(),
+                    axis_value: None,
+                    modifiers,
+                    is_modifier: self.declared_modifiers.contains(&input),
+                    session_id: self.session_id.clone(),
+                    device_uuid: Some(info.uuid.clone()),
+                    raw_button_code: Some(format!("XInput 0x{:04X}", mask)),
+                    raw_code_index: Some(btn),
+                    device_name: Some(info.name.clone()),
+                    hid_usage_id: None,
+                    hid_axis_name: None,
+                });
             }
         }
 
-        eprintln!(
-            "InputDetector: Monitoring {} HID devices and 4 XInput slots",
-            hid_devices.len()
-        );
+        // Check axes
+        let keyboard_modifiers = self.keyboard_modifiers();
+        for (axis_index, value, axis_name) in state.axes.iter() {
+            let state_entry = self
+                .gamepad_axis_states
+                .entry((sc_instance, *axis_index))
+                .or_insert(AxisState {
+                    last_value: *value,
+                    last_triggered_direction: None,
+                });
 
-        Self {
-            session_id,
-            xinput,
-            xinput_prev_states,
-            xinput_axis_states,
-            hid_devices,
-            device_descriptors,
-            device_instances,
-            device_hid_to_axis_maps,
-            prev_hid_reports: HashMap::new(),
-            opened_devices,
+            let movement_delta = (value - state_entry.last_value).abs();
+            let is_positive = *value > AXIS_TRIGGER_THRESHOLD;
+            let is_negative = *value < -AXIS_TRIGGER_THRESHOLD;
+            let is_centered = value.abs() < AXIS_RESET_THRESHOLD;
+            let has_moved_enough = movement_delta > MOVEMENT_THRESHOLD;
+
+            if is_centered {
+                state_entry.last_triggered_direction = None;
+                state_entry.last_value = *value;
+            }
+
+            let should_trigger = (is_positive
+                && has_moved_enough
+                && state_entry.last_triggered_direction != Some(true))
+                || (is_negative
+                    && has_moved_enough
+                    && state_entry.last_triggered_direction != Some(false));
+
+            if should_trigger {
+                let direction = if is_positive { "positive" } else { "negative" };
+                let direction_symbol = if is_positive { "+" } else { "-" };
+                state_entry.last_triggered_direction = Some(is_positive);
+                state_entry.last_value = *value;
+
+                detected_inputs.push(DetectedInput {
+                    input_string: format!("gp{}_axis{}_{}", sc_instance, axis_index, direction),
+                    display_name: format!(
+                        "Gamepad {} - {} {} (Axis {})",
+                        sc_instance, axis_name, direction_symbol, axis_index
+                    ),
+                    device_type: "Gamepad".to_string(),
+                    axis_value: Some(*value),
+                    modifiers: keyboard_modifiers.clone(),
+                    is_modifier: false,
+                    session_id: self.session_id.clone(),
+                    device_uuid: Some(info.uuid.clone()),
+                    raw_button_code: None,
+                    raw_code_index: Some(*axis_index),
+                    device_name: Some(info.name.clone()),
+                    hid_usage_id: None,
+                    hid_axis_name: None,
+                });
+            }
         }
+        self.prev_gamepad_states.insert(sc_instance, state);
     }
 
-    fn poll(&mut self) -> Vec<DetectedInput> {
-        let mut detected_inputs = Vec::new();
+    fn detect_hid(
+        &mut self,
+        info: &SourceInfo,
+        current_report: hid_reader::HidFullReport,
+        detected_inputs: &mut Vec<DetectedInput>,
+    ) {
+        let device_instance = info.instance;
+        let report_key = (device_instance, current_report.report_id);
+
+        // Skip detection on the very first report from this device (baseline establishment)
+        let Some(prev_report) = self.prev_hid_reports.get(&report_key) else {
+            self.prev_hid_reports.insert(report_key, current_report);
+            return;
+        };
 
-        // Poll XInput
-        if let Some(ref xinput) = self.xinput {
-            for controller_id in 0..4 {
-                if let Ok(state) = xinput.get_state(controller_id) {
-                    if let Some(prev_state) = &self.xinput_prev_states[controller_id as usize] {
-                        // Check buttons
-                        let buttons_pressed =
-                            state.raw.Gamepad.wButtons & !prev_state.raw.Gamepad.wButtons;
-
-                        if buttons_pressed != 0 {
-                            let button_num = match buttons_pressed {
-                                0x1000 => Some(1),  // A
-                                0x2000 => Some(2),  // B
-                                0x4000 => Some(3),  // X
-                                0x8000 => Some(4),  // Y
-                                0x0100 => Some(5),  // LB
-                                0x0200 => Some(6),  // RB
-                                0x0010 => Some(7),  // Back
-                                0x0020 => Some(8),  // Start
-                                0x0040 => Some(9),  // LS
-                                0x0080 => Some(10), // RS
-                                0x0001 => Some(11), // DPad Up
-                                0x0002 => Some(12), // DPad Down
-                                0x0004 => Some(13), // DPad Left
-                                0x0008 => Some(14), // DPad Right
-                                _ => None,
-                            };
-
-                            if let Some(btn) = button_num {
-                                let sc_instance = controller_id as usize + 1;
-                                detected_inputs.push(DetectedInput {
-                                    input_string: format!("gp{}_button{}", sc_instance, btn),
-                                    display_name: format!(
-                                        "Gamepad {} - Button {}",
-                                        sc_instance, btn
-                                    ),
-                                    device_type: "Gamepad".to_string(),
-                                    axis_value: None,
-                                    modifiers: get_active_modifiers(),
-                                    is_modifier: false,
-                                    session_id: self.session_id.clone(),
-                                    device_uuid: Some(resolve_xinput_uuid(controller_id)),
-                                    raw_button_code: Some(format!(
-                                        "XInput 0x{:04X}",
-                                        buttons_pressed
-                                    )),
-                                    raw_code_index: Some(btn),
-                                    device_name: Some(format!(
-                                        "Xbox Controller (XInput {})",
-                                        controller_id
-                                    )),
-                                    hid_usage_id: None,
-                                    hid_axis_name: None,
-                                });
-                            }
-                        }
+        // Check buttons - only detect NEW button presses (not held buttons)
+        let prev_buttons = &prev_report.pressed_buttons;
+        let newly_pressed: Vec<u32> = current_report
+            .pressed_buttons
+            .iter()
+            .filter(|&&btn| !prev_buttons.contains(&btn))
+            .copied()
+            .collect();
+
+        // Debug logging for button detection
+        if !current_report.pressed_buttons.is_empty() || !prev_buttons.is_empty() {
+            eprintln!(
+                "Device {}: Current buttons: {:?}, Prev buttons: {:?}, Newly pressed: {:?}",
+                device_instance, current_report.pressed_buttons, prev_buttons, newly_pressed
+            );
+        }
 
-                        // Check axes
-                        let axes = [
-                            (
-                                1,
-                                (state.raw.Gamepad.sThumbLX as f32) / 32768.0,
-                                "Left Stick X",
-                            ),
-                            (
-                                2,
-                                (state.raw.Gamepad.sThumbLY as f32) / 32768.0,
-                                "Left Stick Y",
-                            ),
-                            (
-                                3,
-                                (state.raw.Gamepad.sThumbRX as f32) / 32768.0,
-                                "Right Stick X",
-                            ),
-                            (
-                                4,
-                                (state.raw.Gamepad.sThumbRY as f32) / 32768.0,
-                                "Right Stick Y",
-                            ),
-                            (
-                                5,
-                                (state.raw.Gamepad.bLeftTrigger as f32) / 255.0 * 2.0 - 1.0,
-                                "Left Trigger",
-                            ),
-                            (
-                                6,
-                                (state.raw.Gamepad.bRightTrigger as f32) / 255.0 * 2.0 - 1.0,
-                                "Right Trigger",
-                            ),
-                        ];
-
-                        for (axis_index, value, axis_name) in axes.iter() {
-                            let axis_key = (controller_id, *axis_index);
-                            let state_entry =
-                                self.xinput_axis_states
-                                    .entry(axis_key)
-                                    .or_insert(AxisState {
-                                        last_value: *value,
-                                        last_triggered_direction: None,
-                                    });
-
-                            let movement_delta = (value - state_entry.last_value).abs();
-                            let is_positive = *value > AXIS_TRIGGER_THRESHOLD;
-                            let is_negative = *value < -AXIS_TRIGGER_THRESHOLD;
-                            let is_centered = value.abs() < AXIS_RESET_THRESHOLD;
-                            let has_moved_enough = movement_delta > MOVEMENT_THRESHOLD;
-
-                            if is_centered {
-                                state_entry.last_triggered_direction = None;
-                                state_entry.last_value = *value;
-                            }
+        // Every button that went down in this report, each chorded with the
+        // declared modifiers that were already held
+        let held_modifiers = self.held_hid_modifiers(device_instance);
+        for &button_num in &newly_pressed {
+            let input = format!("js{}_button{}", device_instance, button_num);
+            let mut modifiers = self.keyboard_modifiers();
+            modifiers.extend(held_modifiers.iter().cloned());
+
+            detected_inputs.push(DetectedInput {
+                input_string: chord_input(&held_modifiers, &input),
+                display_name: format!("Joystick {} - Button {}", device_instance, button_num),
+                device_type: "Joystick".to_string(),
+                axis_value: None,
+                modifiers,
+                is_modifier: self.declared_modifiers.contains(&input),
+                session_id: self.session_id.clone(),
+                device_uuid: Some(info.uuid.clone()),
+                raw_button_code: Some(format!("HID Button {}", button_num)),
+                raw_code_index: Some(button_num),
+                device_name: Some(info.name.clone()),
+                hid_usage_id: None,
+                hid_axis_name: None,
+            });
+        }
 
-                            let should_trigger = (is_positive
-                                && has_moved_enough
-                                && state_entry.last_triggered_direction != Some(true))
-                                || (is_negative
-                                    && has_moved_enough
-                                    && state_entry.last_triggered_direction != Some(false));
-
-                            if should_trigger {
-                                let direction = if is_positive { "positive" } else { "negative" };
-                                let direction_symbol = if is_positive { "+" } else { "-" };
-                                state_entry.last_triggered_direction = Some(is_positive);
-                                state_entry.last_value = *value;
-
-                                let sc_instance = controller_id as usize + 1;
-                                detected_inputs.push(DetectedInput {
-                                    input_string: format!(
-                                        "gp{}_axis{}_{}",
-                                        sc_instance, axis_index, direction
-                                    ),
-                                    display_name: format!(
-                                        "Gamepad {} - {} {} (Axis {})",
-                                        sc_instance, axis_name, direction_symbol, axis_index
-                                    ),
-                                    device_type: "Gamepad".to_string(),
-                                    axis_value: Some(*value),
-                                    modifiers: get_active_modifiers(),
-                                    is_modifier: false,
-                                    session_id: self.session_id.clone(),
-                                    device_uuid: Some(resolve_xinput_uuid(controller_id)),
-                                    raw_button_code: None,
-                                    raw_code_index: Some(*axis_index),
-                                    device_name: Some(format!(
-                                        "Xbox Controller (XInput {})",
-                                        controller_id
-                                    )),
-                                    hid_usage_id: None,
-                                    hid_axis_name: None,
-                                });
-                            }
-                        }
-                    }
-                    self.xinput_prev_states[controller_id as usize] = Some(state);
-                }
+        // Check hats - each hat field separately, only directions that just engaged
+        // (moving up -> up-right emits only "right")
+        for hat in &current_report.hats {
+            let prev_directions = prev_report
+                .hats
+                .iter()
+                .find(|h| h.hat == hat.hat)
+                .map(|h| h.directions())
+                .unwrap_or(&[]);
+
+            for direction in hat
+                .directions()
+                .iter()
+                .filter(|d| !prev_directions.contains(d))
+            {
+                let input = format!("js{}_hat{}_{}", device_instance, hat.hat, direction);
+                let mut modifiers = self.keyboard_modifiers();
+                modifiers.extend(held_modifiers.iter().cloned());
+                detected_inputs.push(DetectedInput {
+                    input_string: chord_input(&held_modifiers, &input),
+                    display_name: format!(
+                        "Joystick {} - Hat {} {}",
+                        device_instance,
+                        hat.hat,
+                        direction.to_uppercase()
+                    ),
+                    device_type: "Joystick".to_string(),
+                    axis_value: None,
+                    modifiers,
+                    is_modifier: self.declared_modifiers.contains(&input),
+                    session_id: self.session_id.clone(),
+                    device_uuid: Some(info.uuid.clone()),
+                    raw_button_code: Some(format!("HID Hat {} = {}", hat.hat, hat.value)),
+                    raw_code_index: Some(hat.hat),
+                    device_name: Some(info.name.clone()),
+                    hid_usage_id: Some(HID_USAGE_HAT_SWITCH),
+                    hid_axis_name: Some("Hat switch".to_string()),
+                });
             }
         }
 
-        // Poll HID devices
-        for device in &self.hid_devices {
-            let Some(descriptor) = self.device_descriptors.get(&device.path) else {
-                continue;
-            };
-            let Some(opened_device) = self.opened_devices.get(&device.path) else {
+        // Check axes
+        for (&axis_id, &current_value) in &current_report.axis_values {
+            // Hats are handled above
+            if axis_id == HID_USAGE_HAT_SWITCH {
                 continue;
-            };
-            let device_instance = self.device_instances[&device.path];
+            }
 
-            // Drain the HID queue (up to 10 reports per poll to avoid blocking)
-            let mut reports_processed = 0;
-            loop {
-                if reports_processed >= 10 {
-                    break;
-                }
+            let prev_value = prev_report.axis_values.get(&axis_id).copied().unwrap_or(0);
 
-                // Use 0 timeout for non-blocking read
-                let report_bytes = match opened_device.read(0) {
-                    Ok(bytes) if !bytes.is_empty() => bytes,
-                    _ => break, // No more reports or error
-                };
+            let (logical_min, logical_max) = current_report
+                .axis_ranges
+                .get(&axis_id)
+                .copied()
+                .unwrap_or((0, 65535));
 
-                reports_processed += 1;
-
-                let current_report =
-                    match hid_reader::parse_hid_full_report(&report_bytes, descriptor) {
-                        Ok(report) => report,
-                        Err(_) => continue,
-                    };
-
-                // Check buttons - only detect NEW button presses (not held buttons)
-                // Skip detection on the very first poll for this device (baseline establishment)
-                let prev_buttons = self
-                    .prev_hid_reports
-                    .get(&device.path)
-                    .map(|r| &r.pressed_buttons)
-                    .cloned();
-
-                if let Some(prev_buttons) = prev_buttons {
-                    // We have a previous state, so detect new button presses
-                    let newly_pressed: Vec<u32> = current_report
-                        .pressed_buttons
-                        .iter()
-                        .filter(|&&btn| !prev_buttons.contains(&btn))
-                        .copied()
-                        .collect();
+            let range = (logical_max - logical_min) as f32;
+            if range <= 0.0 {
+                continue;
+            }
 
-                    // Debug logging for button detection
-                    if !current_report.pressed_buttons.is_empty() || !prev_buttons.is_empty() {
-                        eprintln!(
-                        "Device {}: Current buttons: {:?}, Prev buttons: {:?}, Newly pressed: {:?}",
-                        device_instance,
-                        current_report.pressed_buttons,
-                        prev_buttons,
-                        newly_pressed
-                    );
-                    }
+            let change_abs = (current_value as i32 - prev_value as i32).abs() as f32;
 
-                    if !newly_pressed.is_empty() {
-                        let button_num = newly_pressed[0];
-                        let device_name = device.product.as_deref().unwrap_or("Unknown Device");
-
-                        detected_inputs.push(DetectedInput {
-                            input_string: format!("js{}_button{}", device_instance, button_num),
-                            display_name: format!(
-                                "Joystick {} - Button {}",
-                                device_instance, button_num
-                            ),
-                            device_type: "Joystick".to_string(),
-                            axis_value: None,
-                            modifiers: get_active_modifiers(),
-                            is_modifier: false,
-                            session_id: self.session_id.clone(),
-                            device_uuid: Some(format!(
-                                "{:04x}:{:04x}",
-                                device.vendor_id, device.product_id
-                            )),
-                            raw_button_code: Some(format!("HID Button {}", button_num)),
-                            raw_code_index: Some(button_num),
-                            device_name: Some(device_name.to_string()),
-                            hid_usage_id: None,
-                            hid_axis_name: None,
-                        });
-                    }
-                } // else: First poll for this device - just establish baseline, don't detect anything
-
-                // Check axes
-                if let Some(prev_report) = self.prev_hid_reports.get(&device.path) {
-                    for (&axis_id, &current_value) in &current_report.axis_values {
-                        let prev_value =
-                            prev_report.axis_values.get(&axis_id).copied().unwrap_or(0);
-
-                        let (logical_min, logical_max) = current_report
-                            .axis_ranges
-                            .get(&axis_id)
-                            .copied()
-                            .unwrap_or((0, 65535));
-
-                        let range = (logical_max - logical_min) as f32;
-                        if range <= 0.0 {
-                            continue;
-                        }
+            const AXIS_CHANGE_THRESHOLD: f32 = 50.0; // Absolute value change needed
 
-                        let change_abs = (current_value as i32 - prev_value as i32).abs() as f32;
-
-                        // Hat switch check (HID Usage ID 0x39 = 57)
-                        // Hat switches need special handling - they should detect any value change
-                        let is_hat_switch = axis_id == 0x39;
-
-                        // For hat switches, trigger on any non-zero change
-                        // For regular axes, use absolute threshold
-                        const AXIS_CHANGE_THRESHOLD: f32 = 50.0; // Absolute value change needed
-
-                        let should_detect = if is_hat_switch {
-                            change_abs > 0.0 // Any change triggers detection for hat switches
-                        } else {
-                            change_abs >= AXIS_CHANGE_THRESHOLD
-                        };
-
-                        if should_detect {
-                            // Normalize to -1.0 to 1.0 for all checks
-                            let normalized =
-                                ((current_value as i32 - logical_min) as f32 / range * 2.0) - 1.0;
-
-                            // Hat switch check (HID Usage ID 0x39 = 57)
-                            if is_hat_switch {
-                                let axis_name = current_report
-                                    .axis_names
-                                    .get(&axis_id)
-                                    .map(|s| s.as_str())
-                                    .unwrap_or("Unknown");
-                                let device_name =
-                                    device.product.as_deref().unwrap_or("Unknown Device");
-
-                                let axis_index = self
-                                    .device_hid_to_axis_maps
-                                    .get(&device.path)
-                                    .and_then(|map| map.get(&axis_id).copied())
-                                    .unwrap_or_else(|| {
-                                        let mut sorted_axes: Vec<_> =
-                                            current_report.axis_values.keys().copied().collect();
-                                        sorted_axes.sort();
-                                        sorted_axes
-                                            .iter()
-                                            .position(|&id| id == axis_id)
-                                            .map(|pos| pos as u32 + 1)
-                                            .unwrap_or(1)
-                                    });
-
-                                let hat_direction = match current_value {
-                                    0 => Some("up"),
-                                    1 => Some("up"),
-                                    2 => Some("right"),
-                                    3 => Some("right"),
-                                    4 => Some("down"),
-                                    5 => Some("down"),
-                                    6 => Some("left"),
-                                    7 => Some("left"),
-                                    8 | 15 => None, // Centered
-                                    _ => None,
-                                };
-
-                                if let Some(direction) = hat_direction {
-                                    detected_inputs.push(DetectedInput {
-                                        input_string: format!(
-                                            "js{}_hat1_{}",
-                                            device_instance, direction
-                                        ),
-                                        display_name: format!(
-                                            "Joystick {} - Hat 1 {}",
-                                            device_instance,
-                                            direction.to_uppercase()
-                                        ),
-                                        device_type: "Joystick".to_string(),
-                                        axis_value: Some(normalized),
-                                        modifiers: get_active_modifiers(),
-                                        is_modifier: false,
-                                        session_id: self.session_id.clone(),
-                                        device_uuid: Some(format!(
-                                            "{:04x}:{:04x}",
-                                            device.vendor_id, device.product_id
-                                        )),
-                                        raw_button_code: None,
-                                        raw_code_index: Some(axis_index),
-                                        device_name: Some(device_name.to_string()),
-                                        hid_usage_id: Some(axis_id),
-                                        hid_axis_name: Some(axis_name.to_string()),
-                                    });
-                                }
-                                continue;
-                            }
+            if change_abs >= AXIS_CHANGE_THRESHOLD {
+                // Normalize to -1.0 to 1.0 for all checks
+                let normalized = ((current_value as i32 - logical_min) as f32 / range * 2.0) - 1.0;
 
-                            // Regular axis - detect any movement past threshold regardless of position
-                            let direction = if normalized > 0.0 {
-                                "positive"
-                            } else {
-                                "negative"
-                            };
-                            let direction_symbol = if normalized > 0.0 { "+" } else { "-" };
-                            let axis_name = current_report
-                                .axis_names
-                                .get(&axis_id)
-                                .map(|s| s.as_str())
-                                .unwrap_or("Unknown");
-                            let device_name = device.product.as_deref().unwrap_or("Unknown Device");
-
-                            let axis_index = self
-                                .device_hid_to_axis_maps
-                                .get(&device.path)
-                                .and_then(|map| map.get(&axis_id).copied())
-                                .unwrap_or_else(|| {
-                                    let mut sorted_axes: Vec<_> =
-                                        current_report.axis_values.keys().copied().collect();
-                                    sorted_axes.sort();
-                                    sorted_axes
-                                        .iter()
-                                        .position(|&id| id == axis_id)
-                                        .map(|pos| pos as u32 + 1)
-                                        .unwrap_or(1)
-                                });
-
-                            detected_inputs.push(DetectedInput {
-                                input_string: format!(
-                                    "js{}_axis{}_{}",
-                                    device_instance, axis_index, direction
-                                ),
-                                display_name: format!(
-                                    "Joystick {} - {} {} (Axis {})",
-                                    device_instance, axis_name, direction_symbol, axis_index
-                                ),
-                                device_type: "Joystick".to_string(),
-                                axis_value: Some(normalized),
-                                modifiers: get_active_modifiers(),
-                                is_modifier: false,
-                                session_id: self.session_id.clone(),
-                                device_uuid: Some(format!(
-                                    "{:04x}:{:04x}",
-                                    device.vendor_id, device.product_id
-                                )),
-                                raw_button_code: None,
-                                raw_code_index: Some(axis_index),
-                                device_name: Some(device_name.to_string()),
-                                hid_usage_id: Some(axis_id),
-                                hid_axis_name: Some(axis_name.to_string()),
-                            });
-                        }
-                    }
-                }
-                self.prev_hid_reports
-                    .insert(device.path.clone(), current_report);
+                // Regular axis - detect any movement past threshold regardless of position
+                let direction = if normalized > 0.0 {
+                    "positive"
+                } else {
+                    "negative"
+                };
+                let direction_symbol = if normalized > 0.0 { "+" } else { "-" };
+                let axis_name = current_report
+                    .axis_names
+                    .get(&axis_id)
+                    .map(|s| s.as_str())
+                    .unwrap_or("Unknown");
+
+                let axis_index = info.axis_indices.get(&axis_id).copied().unwrap_or_else(|| {
+                    let mut sorted_axes: Vec<_> =
+                        current_report.axis_values.keys().copied().collect();
+                    sorted_axes.sort();
+                    sorted_axes
+                        .iter()
+                        .position(|&id| id == axis_id)
+                        .map(|pos| pos as u32 + 1)
+                        .unwrap_or(1)
+                });
+
+                detected_inputs.push(DetectedInput {
+                    input_string: format!("js{}_axis{}_{}", device_instance, axis_index, direction),
+                    display_name: format!(
+                        "Joystick {} - {} {} (Axis {})",
+                        device_instance, axis_name, direction_symbol, axis_index
+                    ),
+                    device_type: "Joystick".to_string(),
+                    axis_value: Some(normalized),
+                    modifiers: self.keyboard_modifiers(),
+                    is_modifier: false,
+                    session_id: self.session_id.clone(),
+                    device_uuid: Some(info.uuid.clone()),
+                    raw_button_code: None,
+                    raw_code_index: Some(axis_index),
+                    device_name: Some(info.name.clone()),
+                    hid_usage_id: Some(axis_id),
+                    hid_axis_name: Some(axis_name.to_string()),
+                });
             }
         }
-
-        detected_inputs
+        self.prev_hid_reports.insert(report_key, current_report);
     }
 }
 
 /// Wait for input from any game controller (hybrid approach)
+///
+/// Modifier presses on their own are skipped; the input pressed while one is held comes
+/// back as a chord.
 pub fn wait_for_input(
+    hid: &HidService,
+    declared_modifiers: Vec<String>,
     session_id: String,
     timeout_secs: u64,
 ) -> Result<Option<DetectedInput>, String> {
@@ -731,14 +766,19 @@ pub fn wait_for_input(
         timeout_secs
     );
 
-    let mut detector = InputDetector::new(session_id);
+    let mut detector = InputDetector::new(
+        session_id,
+        input_source::live_sources(hid),
+        declared_modifiers,
+        true,
+    );
 
     while start.elapsed() < timeout {
         let inputs = detector.poll();
-        if let Some(input) = inputs.into_iter().next() {
+        if let Some(input) = inputs.into_iter().find(|input| !input.is_modifier) {
             return Ok(Some(input));
         }
-        thread::sleep(Duration::from_millis(5));
+        thread::sleep(input_source::POLL_INTERVAL);
     }
 
     Ok(None)
@@ -746,6 +786,8 @@ pub fn wait_for_input(
 
 /// Wait for joystick inputs and emit events in real-time
 pub fn wait_for_inputs_with_events(
+    hid: &HidService,
+    declared_modifiers: Vec<String>,
     window: tauri::Window,
     session_id: String,
     initial_timeout_secs: u64,
@@ -753,7 +795,12 @@ pub fn wait_for_inputs_with_events(
 ) -> Result<(), String> {
     eprintln!("wait_for_inputs_with_events: Starting hybrid input detection");
 
-    let mut detector = InputDetector::new(session_id.clone());
+    let mut detector = InputDetector::new(
+        session_id.clone(),
+        input_source::live_sources(hid),
+        declared_modifiers,
+        true,
+    );
 
     let start = Instant::now();
     let initial_timeout = Duration::from_secs(initial_timeout_secs);
@@ -790,30 +837,85 @@ pub fn wait_for_inputs_with_events(
             }
         }
 
-        thread::sleep(Duration::from_millis(5));
+        thread::sleep(input_source::POLL_INTERVAL);
     }
 
     Ok(())
 }
 
+/// Record a HID device's descriptor and reports to a file for [`replay_recording`];
+/// returns the number of reports recorded
+pub fn record_input(
+    hid: &HidService,
+    target_uuid: &str,
+    duration_secs: u64,
+    path: &Path,
+) -> Result<usize, String> {
+    let hid_devices = hid.devices().unwrap_or_default();
+    let device = hid_devices
+        .iter()
+        .find(|d| format!("{:04x}:{:04x}", d.vendor_id, d.product_id) == target_uuid)
+        .ok_or_else(|| format!("Device not found: {}", target_uuid))?;
+
+    eprintln!(
+        "record_input: Recording {} for {} seconds",
+        target_uuid, duration_secs
+    );
+    let recording =
+        input_source::Recording::capture(hid, device, Duration::from_secs(duration_secs))?;
+    recording.save(path)?;
+    Ok(recording.reports.len())
+}
+
+/// Run input detection over a recording, as js1, and return everything it detects
+///
+/// Keyboard and mouse aren't polled, so the result only depends on the recording.
+pub fn replay_recording(
+    path: &Path,
+    declared_modifiers: Vec<String>,
+) -> Result<Vec<DetectedInput>, String> {
+    let recording = input_source::Recording::load(path)?;
+    let source = input_source::ReplaySource::new(recording, 1)?;
+    let mut detector = InputDetector::new(
+        "replay".to_string(),
+        vec![Box::new(source)],
+        declared_modifiers,
+        false,
+    );
+
+    let mut detected_inputs = Vec::new();
+    while !detector.is_exhausted() {
+        detected_inputs.extend(detector.poll());
+    }
+    Ok(detected_inputs)
+}
+
 /// Get list of available joysticks using hybrid approach (HID + XInput)
-pub fn detect_joysticks() -> Result<Vec<JoystickInfo>, String> {
+pub fn detect_joysticks(hid: &HidService) -> Result<Vec<JoystickInfo>, String> {
     let mut joysticks = Vec::new();
 
     eprintln!("=== Hybrid Device Detection (HID + XInput) ===");
 
     // First, list HID game controllers (joysticks/HOTAS)
-    match hid_reader::list_hid_game_controllers() {
+    match hid.devices() {
         Ok(hid_devices) => {
             eprintln!("Found {} HID game controllers", hid_devices.len());
 
             for (idx, device) in hid_devices.iter().enumerate() {
-                let device_name = device.product.as_deref().unwrap_or("Unknown HID Device");
+                let known = device_database::lookup(device.vendor_id, device.product_id);
+                let device_name = match &known {
+                    Some(entry) => entry.name.clone(),
+                    None => device
+                        .product
+                        .clone()
+                        .unwrap_or_else(|| unknown_device_name(device.vendor_id)),
+                };
                 let manufacturer = device.manufacturer.as_deref().unwrap_or("");
-                let full_name = if !manufacturer.is_empty() {
+                // Database names already include the manufacturer
+                let full_name = if known.is_none() && !manufacturer.is_empty() {
                     format!("{} {}", manufacturer, device_name)
                 } else {
-                    device_name.to_string()
+                    device_name.clone()
                 };
 
                 // Skip Xbox controllers as they'll be added via XInput for better support
@@ -838,22 +940,30 @@ pub fn detect_joysticks() -> Result<Vec<JoystickInfo>, String> {
                     device.product_id
                 );
 
-                // Try to get axis count from descriptor
-                let (button_count, axis_count, hat_count) =
-                    match hid_reader::get_axis_names_from_descriptor(&device.path) {
-                        Ok(axis_names) => {
-                            let axes = axis_names.len();
-                            eprintln!("  Detected {} axes from HID descriptor", axes);
-                            (32, axes, 1)
-                        }
-                        Err(e) => {
-                            eprintln!("  Could not read descriptor: {}", e);
-                            (32, 6, 1) // Defaults
-                        }
-                    };
+                // Read counts from the report descriptor
+                let capabilities = match hid
+                    .descriptor(&device.path)
+                    .and_then(|d| hid_reader::get_device_capabilities(&d))
+                {
+                    Ok(caps) => {
+                        eprintln!(
+                            "  Descriptor: {} buttons, {} axes, {} hats",
+                            caps.button_count, caps.axis_count, caps.hat_count
+                        );
+                        Some(caps)
+                    }
+                    Err(e) => {
+                        eprintln!("  Could not read descriptor: {}", e);
+                        None
+                    }
+                };
+                let (button_count, axis_count, hat_count) = match &capabilities {
+                    Some(caps) => (caps.button_count, caps.axis_count, caps.hat_count),
+                    None => (32, 6, 1), // Defaults
+                };
 
                 // Determine device type
-                let device_type = if is_gamepad(&full_name) {
+                let device_type = if is_gamepad(device.vendor_id, device.product_id, &full_name) {
                     "Gamepad"
                 } else {
                     "Joystick"
@@ -865,13 +975,16 @@ pub fn detect_joysticks() -> Result<Vec<JoystickInfo>, String> {
                 joysticks.push(JoystickInfo {
                     id: joysticks.len() + 1,
                     name: full_name,
-                    product_name: Some(device_name.to_string()),
+                    // The raw HID product string; SC matches it in actionmaps.xml `Product`
+                    product_name: Some(device.product.clone().unwrap_or(device_name)),
                     is_connected: true,
                     button_count,
                     axis_count,
                     hat_count,
                     device_type: device_type.to_string(),
                     uuid: Some(uuid),
+                    axis_profile: known.and_then(|entry| entry.axis_profile),
+                    capabilities,
                 });
             }
         }
@@ -881,9 +994,9 @@ pub fn detect_joysticks() -> Result<Vec<JoystickInfo>, String> {
     }
 
     // Then, check for XInput controllers (Xbox gamepads)
-    if let Ok(xinput) = XInputHandle::load_default() {
+    if let Ok(xinput) = XInput::load() {
         for controller_id in 0..4 {
-            if xinput.get_state(controller_id).is_ok() {
+            if xinput.state(controller_id).is_some() {
                 eprintln!("XInput slot {} active (Xbox Controller)", controller_id);
 
                 joysticks.push(JoystickInfo {
@@ -896,6 +1009,8 @@ pub fn detect_joysticks() -> Result<Vec<JoystickInfo>, String> {
                     hat_count: 1,
                     device_type: "Gamepad".to_string(),
                     uuid: None, // XInput devices don't have hardware UUIDs
+                    axis_profile: None,
+                    capabilities: None,
                 });
             }
         }
@@ -907,18 +1022,21 @@ pub fn detect_joysticks() -> Result<Vec<JoystickInfo>, String> {
 }
 
 /// Returns detailed information for all connected devices.
-pub fn list_connected_devices() -> Result<Vec<DeviceInfo>, String> {
+pub fn list_connected_devices(hid: &HidService) -> Result<Vec<DeviceInfo>, String> {
     let mut devices = Vec::new();
 
     // List HID game controllers (joysticks/HOTAS)
-    let hid_devices = hid_reader::list_hid_game_controllers().unwrap_or_default();
+    let hid_devices = hid.devices().unwrap_or_default();
 
     for device in hid_devices {
-        let name = device
-            .product
-            .as_deref()
-            .unwrap_or("Unknown Device")
-            .to_string();
+        let known = device_database::lookup(device.vendor_id, device.product_id);
+        let name = match &known {
+            Some(entry) => entry.name.clone(),
+            None => device
+                .product
+                .clone()
+                .unwrap_or_else(|| unknown_device_name(device.vendor_id)),
+        };
 
         // Skip Xbox controllers as they'll be added via XInput
         if cfg!(windows)
@@ -928,12 +1046,18 @@ pub fn list_connected_devices() -> Result<Vec<DeviceInfo>, String> {
         }
 
         let uuid = format!("{:04x}:{:04x}", device.vendor_id, device.product_id);
-        let is_gamepad_device = is_gamepad(&name);
-
-        let (button_count, axis_count, hat_count) = if is_gamepad_device {
-            (15, 6, 1)
-        } else {
-            (32, 7, 1)
+        let is_gamepad_device = is_gamepad(device.vendor_id, device.product_id, &name);
+
+        let capabilities = hid
+            .descriptor(&device.path)
+            .and_then(|d| hid_reader::get_device_capabilities(&d))
+            .map_err(|e| eprintln!("Could not read descriptor for {}: {}", name, e))
+            .ok();
+        let (button_count, axis_count, hat_count) = match &capabilities {
+            Some(caps) => (caps.button_count, caps.axis_count, caps.hat_count),
+            // Defaults when the descriptor can't be read
+            None if is_gamepad_device => (15, 6, 1),
+            None => (32, 7, 1),
         };
 
         devices.push(DeviceInfo {
@@ -949,13 +1073,15 @@ pub fn list_connected_devices() -> Result<Vec<DeviceInfo>, String> {
             }
             .to_string(),
             is_connected: true,
+            axis_profile: known.and_then(|entry| entry.axis_profile),
+            capabilities,
         });
     }
 
     // Add XInput devices explicitly
-    if let Ok(xinput) = XInputHandle::load_default() {
+    if let Ok(xinput) = XInput::load() {
         for i in 0..4 {
-            if xinput.get_state(i).is_ok() {
+            if xinput.state(i).is_some() {
                 let uuid = resolve_xinput_uuid(i);
 
                 if !devices.iter().any(|d| d.uuid == uuid) {
@@ -967,6 +1093,8 @@ pub fn list_connected_devices() -> Result<Vec<DeviceInfo>, String> {
                         hat_count: 1,
                         device_type: "Gamepad".to_string(),
                         is_connected: true,
+                        axis_profile: None,
+                        capabilities: None,
                     });
                 }
             }
@@ -978,6 +1106,7 @@ pub fn list_connected_devices() -> Result<Vec<DeviceInfo>, String> {
 
 /// Waits for the user to move an axis on the specified device and returns the raw axis index.
 pub fn detect_axis_movement_for_device(
+    hid: &HidService,
     target_uuid: &str,
     timeout_millis: u64,
 ) -> Result<Option<AxisMovement>, String> {
@@ -991,49 +1120,17 @@ pub fn detect_axis_movement_for_device(
             .and_then(|s| s.parse().ok())
             .ok_or("Invalid XInput UUID")?;
 
-        let xinput =
-            XInputHandle::load_default().map_err(|e| format!("Failed to load XInput: {:?}", e))?;
+        let xinput = XInput::load()?;
 
         let initial_state = xinput
-            .get_state(controller_id)
-            .map_err(|e| format!("XInput error: {:?}", e))?;
+            .state(controller_id)
+            .ok_or_else(|| format!("XInput controller {} not connected", controller_id))?;
 
         while start.elapsed() < timeout {
-            if let Ok(state) = xinput.get_state(controller_id) {
-                let axes = [
-                    (
-                        1,
-                        (state.raw.Gamepad.sThumbLX as f32) / 32768.0,
-                        (initial_state.raw.Gamepad.sThumbLX as f32) / 32768.0,
-                    ),
-                    (
-                        2,
-                        (state.raw.Gamepad.sThumbLY as f32) / 32768.0,
-                        (initial_state.raw.Gamepad.sThumbLY as f32) / 32768.0,
-                    ),
-                    (
-                        3,
-                        (state.raw.Gamepad.sThumbRX as f32) / 32768.0,
-                        (initial_state.raw.Gamepad.sThumbRX as f32) / 32768.0,
-                    ),
-                    (
-                        4,
-                        (state.raw.Gamepad.sThumbRY as f32) / 32768.0,
-                        (initial_state.raw.Gamepad.sThumbRY as f32) / 32768.0,
-                    ),
-                    (
-                        5,
-                        (state.raw.Gamepad.bLeftTrigger as f32) / 255.0 * 2.0 - 1.0,
-                        (initial_state.raw.Gamepad.bLeftTrigger as f32) / 255.0 * 2.0 - 1.0,
-                    ),
-                    (
-                        6,
-                        (state.raw.Gamepad.bRightTrigger as f32) / 255.0 * 2.0 - 1.0,
-                        (initial_state.raw.Gamepad.bRightTrigger as f32) / 255.0 * 2.0 - 1.0,
-                    ),
-                ];
-
-                for (axis_id, value, initial_value) in axes {
+            if let Some(state) = xinput.state(controller_id) {
+                for ((axis_id, value, _), (_, initial_value, _)) in
+                    state.axes.into_iter().zip(initial_state.axes)
+                {
                     let delta = (value - initial_value).abs();
                     if delta > 0.15 && value.abs() > 0.15 {
                         return Ok(Some(AxisMovement { axis_id, value }));
@@ -1046,7 +1143,7 @@ pub fn detect_axis_movement_for_device(
     }
 
     // Handle HID devices
-    let hid_devices = hid_reader::list_hid_game_controllers().unwrap_or_default();
+    let hid_devices = hid.devices().unwrap_or_default();
 
     let target_device = hid_devices.iter().find(|d| {
         let uuid = format!("{:04x}:{:04x}", d.vendor_id, d.product_id);
@@ -1057,17 +1154,20 @@ pub fn detect_axis_movement_for_device(
         return Err(format!("Device not found: {}", target_uuid));
     };
 
-    let descriptor = hid_reader::get_hid_descriptor_bytes(&device.path)
+    let parser = hid
+        .parser(&device.path)
         .map_err(|e| format!("Failed to get descriptor: {}", e))?;
 
-    let initial_bytes = hid_reader::read_hid_report(&device.path, 10)
+    let initial_bytes = hid
+        .read_report(&device.path, 10)
         .map_err(|e| format!("Failed to read initial report: {}", e))?;
 
-    let initial_report = hid_reader::parse_hid_full_report(&initial_bytes, &descriptor)
+    let initial_report = parser
+        .parse(&initial_bytes)
         .map_err(|e| format!("Failed to parse initial report: {}", e))?;
 
     while start.elapsed() < timeout {
-        let report_bytes = match hid_reader::read_hid_report(&device.path, 10) {
+        let report_bytes = match hid.read_report(&device.path, 10) {
             Ok(bytes) if !bytes.is_empty() => bytes,
             _ => {
                 thread::sleep(Duration::from_millis(5));
@@ -1075,7 +1175,7 @@ pub fn detect_axis_movement_for_device(
             }
         };
 
-        let current_report = match hid_reader::parse_hid_full_report(&report_bytes, &descriptor) {
+        let current_report = match parser.parse(&report_bytes) {
             Ok(report) => report,
             Err(_) => {
                 thread::sleep(Duration::from_millis(5));
@@ -1108,8 +1208,9 @@ pub fn detect_axis_movement_for_device(
                 let normalized = ((current_value as i32 - logical_min) as f32 / range * 2.0) - 1.0;
 
                 if normalized.abs() > 0.15 {
-                    if let Ok(di_to_hid) =
-                        hid_reader::get_directinput_to_hid_axis_mapping(&device.path)
+                    if let Ok(di_to_hid) = hid
+                        .descriptor(&device.path)
+                        .and_then(|d| hid_reader::get_directinput_to_hid_axis_mapping(&d))
                     {
                         for (axis_index, hid_usage_id) in di_to_hid {
                             if hid_usage_id == axis_id {
@@ -1129,3 +1230,35 @@ pub fn detect_axis_movement_for_device(
 
     Ok(None)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reports from a 64-button stick: button 5, button 3 while 5 is held, hat up, then X full right
+    const RECORDING: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/stick_recording.json"
+    );
+
+    #[test]
+    fn replay_detects_recorded_inputs() {
+        let detected =
+            replay_recording(Path::new(RECORDING), vec!["js1_button5".to_string()]).unwrap();
+        let inputs: Vec<&str> = detected.iter().map(|d| d.input_string.as_str()).collect();
+        assert_eq!(
+            inputs,
+            [
+                "js1_button5",
+                "js1_button5+js1_button3",
+                "js1_hat1_up",
+                "js1_axis1_positive",
+            ]
+        );
+        assert!(detected[0].is_modifier);
+        assert_eq!(detected[1].modifiers, ["js1_button5"]);
+        assert!(detected
+            .iter()
+            .all(|d| d.device_uuid.as_deref() == Some("1234:5678")));
+    }
+}
@@ -0,0 +1,255 @@
+use crate::file_access::FileAccessError;
+use crate::template::TemplateIssue;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+use std::path::Path;
+
+/// An XML document that couldn't be parsed, with the reader position it failed at
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmlError {
+    pub message: String,
+    /// 1-based
+    pub line: usize,
+    /// 1-based, in characters
+    pub column: usize,
+    /// Byte offset into the document
+    pub position: u64,
+}
+
+impl XmlError {
+    /// Build from a quick-xml reader position, counting lines in the source
+    pub fn at(xml: &str, position: u64, message: impl fmt::Display) -> Self {
+        let offset = (position as usize).min(xml.len());
+        // Positions can land inside a multi-byte character
+        let offset = (0..=offset)
+            .rev()
+            .find(|&i| xml.is_char_boundary(i))
+            .unwrap_or(0);
+        let before = &xml[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rfind('\n')
+            .map_or(before, |newline| &before[newline + 1..])
+            .chars()
+            .count()
+            + 1;
+
+        XmlError {
+            message: message.to_string(),
+            line,
+            column,
+            position,
+        }
+    }
+}
+
+impl fmt::Display for XmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "XML parsing error at line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl From<XmlError> for String {
+    fn from(e: XmlError) -> String {
+        e.to_string()
+    }
+}
+
+/// Error returned by every command
+/// Serialized as `{code, message, details}` so the frontend can branch on `code`
+#[derive(Debug, Clone)]
+pub enum AppError {
+    /// AllBinds.xml hasn't been loaded yet
+    AllBindsNotLoaded,
+    /// No keybindings are loaded in the editor
+    NoBindingsLoaded,
+    /// A named thing (action, profile, page, device...) doesn't exist
+    NotFound(String),
+    Io {
+        message: String,
+        path: Option<String>,
+        /// std::io::ErrorKind, e.g. "NotFound", "PermissionDenied"
+        kind: String,
+    },
+    Xml(XmlError),
+    Json {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// Reading from or talking to a HID device failed
+    Hid(String),
+    /// DirectInput / XInput device access failed
+    Input(String),
+    FileAccess(FileAccessError),
+    /// A template failed validation; holds the blocking issues
+    TemplateInvalid(Vec<TemplateIssue>),
+    InvalidInput(String),
+    Other(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::AllBindsNotLoaded => "all_binds_not_loaded",
+            AppError::NoBindingsLoaded => "no_bindings_loaded",
+            AppError::NotFound(_) => "not_found",
+            AppError::Io { .. } => "io",
+            AppError::Xml(_) => "xml_parse",
+            AppError::Json { .. } => "json_parse",
+            AppError::Hid(_) => "hid",
+            AppError::Input(_) => "input_device",
+            AppError::FileAccess(_) => "file_access",
+            AppError::TemplateInvalid(_) => "template_invalid",
+            AppError::InvalidInput(_) => "invalid_input",
+            AppError::Other(_) => "internal",
+        }
+    }
+
+    /// An IO failure on a known path
+    pub fn io(context: &str, path: &Path, e: std::io::Error) -> Self {
+        AppError::Io {
+            message: format!("{} {}: {}", context, path.display(), e),
+            path: Some(path.to_string_lossy().to_string()),
+            kind: format!("{:?}", e.kind()),
+        }
+    }
+
+    fn details(&self) -> serde_json::Value {
+        match self {
+            AppError::Io { path, kind, .. } => serde_json::json!({ "path": path, "kind": kind }),
+            AppError::Xml(e) => serde_json::json!({
+                "line": e.line,
+                "column": e.column,
+                "position": e.position,
+            }),
+            AppError::Json { line, column, .. } => {
+                serde_json::json!({ "line": line, "column": column })
+            }
+            AppError::FileAccess(e) => serde_json::to_value(e).unwrap_or_default(),
+            AppError::TemplateInvalid(issues) => serde_json::json!({ "issues": issues }),
+            _ => serde_json::Value::Null,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::AllBindsNotLoaded => {
+                write!(
+                    f,
+                    "AllBinds.xml not loaded. Please restart the application."
+                )
+            }
+            AppError::NoBindingsLoaded => write!(f, "No keybindings loaded"),
+            AppError::NotFound(what) => write!(f, "{}", what),
+            AppError::Io { message, .. } => write!(f, "{}", message),
+            AppError::Xml(e) => write!(f, "{}", e),
+            AppError::Json { message, .. } => write!(f, "{}", message),
+            AppError::Hid(message)
+            | AppError::Input(message)
+            | AppError::InvalidInput(message)
+            | AppError::Other(message) => write!(f, "{}", message),
+            AppError::FileAccess(e) => write!(f, "{}", e),
+            AppError::TemplateInvalid(issues) => {
+                let messages: Vec<String> = issues
+                    .iter()
+                    .map(|i| match &i.page {
+                        Some(page) => format!("{}: {}", page, i.message),
+                        None => i.message.clone(),
+                    })
+                    .collect();
+                write!(f, "Invalid template: {}", messages.join("; "))
+            }
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("AppError", 3)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.serialize_field("details", &self.details())?;
+        s.end()
+    }
+}
+
+// Helpers in the other modules still report plain strings
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other(message.to_string())
+    }
+}
+
+impl From<XmlError> for AppError {
+    fn from(e: XmlError) -> Self {
+        AppError::Xml(e)
+    }
+}
+
+impl From<FileAccessError> for AppError {
+    fn from(e: FileAccessError) -> Self {
+        AppError::FileAccess(e)
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Io {
+            message: e.to_string(),
+            path: None,
+            kind: format!("{:?}", e.kind()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        use serde_json::error::Category;
+        // Errors raised while serializing are classified as data errors too, but
+        // only errors from the parser carry a position (lines start at 1)
+        match e.classify() {
+            Category::Syntax | Category::Data | Category::Eof if e.line() > 0 => AppError::Json {
+                message: format!("JSON parsing error: {}", e),
+                line: e.line(),
+                column: e.column(),
+            },
+            _ => AppError::Other(format!("JSON serialization error: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn json_parse_errors_keep_their_position() {
+        let e: AppError = serde_json::from_str::<serde_json::Value>("{\n  \"a\": }")
+            .unwrap_err()
+            .into();
+        assert_eq!(e.code(), "json_parse");
+        assert!(matches!(e, AppError::Json { line: 2, .. }));
+    }
+
+    #[test]
+    fn json_serialize_errors_are_internal() {
+        // Non-string map keys cannot be written as JSON
+        let map: HashMap<(u8, u8), u8> = HashMap::from([((1, 2), 3)]);
+        let e: AppError = serde_json::to_string(&map).unwrap_err().into();
+        assert_eq!(e.code(), "internal");
+    }
+}
@@ -0,0 +1,502 @@
+use crate::hid_reader::{self, HatValue, HidFullReport};
+use crate::sc_keys;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Read;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const INPUT_DIR: &str = "/dev/input";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+const EV_KEY: u8 = 0x01;
+const EV_ABS: u8 = 0x03;
+const KEY_ENTER: u16 = 28;
+const KEY_A: u16 = 30;
+const KEY_SPACE: u16 = 57;
+const BTN_MISC: u16 = 0x100;
+const BTN_JOYSTICK: u16 = 0x120;
+const BTN_DIGI: u16 = 0x140;
+const BTN_TRIGGER_HAPPY: u16 = 0x2c0;
+const KEY_MAX: u16 = 0x2ff;
+const ABS_HAT0X: u16 = 0x10;
+const ABS_HAT3Y: u16 = 0x17;
+const ABS_MAX: u16 = 0x3f;
+
+/// Bytes in a key bitmask (KEY_MAX + 1 bits)
+const KEY_BYTES: usize = KEY_MAX as usize / 8 + 1;
+
+/// Axis codes and the HID Generic Desktop usages Linux maps them from
+const AXES: [(u16, u32, &str); 9] = [
+    (0x00, 0x30, "X"),
+    (0x01, 0x31, "Y"),
+    (0x02, 0x32, "Z"),
+    (0x03, 0x33, "Rx"),
+    (0x04, 0x34, "Ry"),
+    (0x05, 0x35, "Rz"),
+    (0x06, 0x36, "Slider"), // ABS_THROTTLE
+    (0x07, 0x37, "Dial"),   // ABS_RUDDER
+    (0x08, 0x38, "Wheel"),
+];
+
+/// `_IOC(_IOC_READ, 'E', nr, size)`
+fn eviocg(nr: u8, size: usize) -> libc::c_ulong {
+    (2 << 30)
+        | ((size as libc::c_ulong) << 16)
+        | ((b'E' as libc::c_ulong) << 8)
+        | nr as libc::c_ulong
+}
+
+fn ioctl_read(file: &File, nr: u8, buf: &mut [u8]) -> Result<(), String> {
+    // SAFETY: the request's size is `buf.len()`, so the kernel writes within `buf`
+    let result = unsafe {
+        libc::ioctl(
+            file.as_raw_fd(),
+            eviocg(nr, buf.len()) as _,
+            buf.as_mut_ptr(),
+        )
+    };
+    if result < 0 {
+        Err(std::io::Error::last_os_error().to_string())
+    } else {
+        Ok(())
+    }
+}
+
+fn bit_set(bits: &[u8], bit: u16) -> bool {
+    bits.get(bit as usize / 8)
+        .is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+}
+
+/// Identity and controls of an event node
+struct NodeInfo {
+    name: String,
+    vendor_id: u16,
+    product_id: u16,
+    keys: [u8; KEY_BYTES],
+    abs: [u8; ABS_MAX as usize / 8 + 1],
+}
+
+impl NodeInfo {
+    fn read(file: &File) -> Result<Self, String> {
+        let mut name = [0u8; 256];
+        ioctl_read(file, 0x06, &mut name)?; // EVIOCGNAME
+        let name_len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+
+        // struct input_id { bustype, vendor, product, version }
+        let mut id = [0u8; 8];
+        ioctl_read(file, 0x02, &mut id)?; // EVIOCGID
+
+        let mut keys = [0u8; KEY_BYTES];
+        ioctl_read(file, 0x20 + EV_KEY, &mut keys)?; // EVIOCGBIT(EV_KEY)
+        let mut abs = [0u8; ABS_MAX as usize / 8 + 1];
+        ioctl_read(file, 0x20 + EV_ABS, &mut abs)?; // EVIOCGBIT(EV_ABS)
+
+        Ok(NodeInfo {
+            name: String::from_utf8_lossy(&name[..name_len]).to_string(),
+            vendor_id: u16::from_ne_bytes([id[2], id[3]]),
+            product_id: u16::from_ne_bytes([id[4], id[5]]),
+            keys,
+            abs,
+        })
+    }
+
+    fn is_joystick(&self) -> bool {
+        (BTN_JOYSTICK..BTN_DIGI)
+            .chain(BTN_TRIGGER_HAPPY..=KEY_MAX)
+            .any(|code| bit_set(&self.keys, code))
+    }
+
+    fn is_keyboard(&self) -> bool {
+        [KEY_ENTER, KEY_A, KEY_SPACE]
+            .iter()
+            .all(|&code| bit_set(&self.keys, code))
+    }
+}
+
+/// Event nodes we may read, with their info, in eventN order
+fn event_nodes() -> Vec<(String, File, NodeInfo)> {
+    let Ok(entries) = fs::read_dir(INPUT_DIR) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<(u32, String)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let number = name.strip_prefix("event")?.parse().ok()?;
+            Some((number, entry.path().to_string_lossy().to_string()))
+        })
+        .collect();
+    paths.sort();
+
+    paths
+        .into_iter()
+        .filter_map(|(_, path)| {
+            // Unreadable nodes are skipped; joysticks are usually readable by the seat user,
+            // keyboards need membership of the "input" group
+            let file = OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(&path)
+                .ok()?;
+            let info = NodeInfo::read(&file).ok()?;
+            Some((path, file, info))
+        })
+        .collect()
+}
+
+/// A joystick read through its `/dev/input/event*` node
+///
+/// Its events are turned into [`HidFullReport`]s, so detection handles it like a HID device.
+/// Buttons are numbered in the order joydev uses: BTN_JOYSTICK and up, then BTN_MISC.
+pub struct EvdevJoystick {
+    pub path: String,
+    pub name: String,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    file: File,
+    /// Key code of each button; its position + 1 is the button number
+    buttons: Vec<u16>,
+    /// (axis code, HID usage, name, logical min, logical max)
+    axes: Vec<(u16, u32, &'static str, i32, i32)>,
+    /// Hat axis codes present, ABS_HAT0X..=ABS_HAT3Y
+    hat_codes: Vec<u16>,
+    /// Latest value of every axis and hat code
+    abs_values: HashMap<u16, i32>,
+    pressed: Vec<u32>,
+    /// The first read reports the state at open time, as the detection baseline
+    baseline_sent: bool,
+}
+
+impl EvdevJoystick {
+    fn open(path: String, file: File, info: NodeInfo) -> Self {
+        let buttons: Vec<u16> = (BTN_JOYSTICK..=KEY_MAX)
+            .chain(BTN_MISC..BTN_JOYSTICK)
+            .filter(|&code| bit_set(&info.keys, code))
+            .collect();
+
+        let mut abs_values = HashMap::new();
+        let mut axes = Vec::new();
+        let mut hat_codes = Vec::new();
+        for code in 0..=ABS_MAX {
+            if !bit_set(&info.abs, code) {
+                continue;
+            }
+            // struct input_absinfo { value, minimum, maximum, fuzz, flat, resolution }
+            let mut absinfo = [0u8; 24];
+            if ioctl_read(&file, 0x40 + code as u8, &mut absinfo).is_err() {
+                continue;
+            }
+            let field = |i: usize| {
+                i32::from_ne_bytes([
+                    absinfo[i * 4],
+                    absinfo[i * 4 + 1],
+                    absinfo[i * 4 + 2],
+                    absinfo[i * 4 + 3],
+                ])
+            };
+            abs_values.insert(code, field(0));
+
+            if (ABS_HAT0X..=ABS_HAT3Y).contains(&code) {
+                hat_codes.push(code);
+            } else if let Some(&(_, usage, name)) = AXES.iter().find(|(c, ..)| *c == code) {
+                axes.push((code, usage, name, field(1), field(2)));
+            }
+        }
+
+        // Buttons already held (EVIOCGKEY)
+        let mut key_state = [0u8; KEY_BYTES];
+        let pressed = match ioctl_read(&file, 0x18, &mut key_state) {
+            Ok(()) => buttons
+                .iter()
+                .enumerate()
+                .filter(|(_, &code)| bit_set(&key_state, code))
+                .map(|(i, _)| i as u32 + 1)
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        EvdevJoystick {
+            path,
+            name: info.name,
+            vendor_id: info.vendor_id,
+            product_id: info.product_id,
+            file,
+            buttons,
+            axes,
+            hat_codes,
+            abs_values,
+            pressed,
+            baseline_sent: false,
+        }
+    }
+
+    /// The device's state if anything changed since the last call (always on the first)
+    pub fn read_report(&mut self) -> Option<HidFullReport> {
+        let event_size = std::mem::size_of::<libc::input_event>();
+        let mut buf = vec![0u8; event_size * 64];
+        let mut changed = !self.baseline_sent;
+        self.baseline_sent = true;
+
+        loop {
+            let read = match self.file.read(&mut buf) {
+                Ok(read) if read > 0 => read,
+                _ => break,
+            };
+            for chunk in buf[..read].chunks_exact(event_size) {
+                // SAFETY: the chunk holds one whole input_event as written by the kernel
+                let event: libc::input_event =
+                    unsafe { std::ptr::read_unaligned(chunk.as_ptr() as *const _) };
+                match event.type_ as u8 {
+                    EV_KEY => {
+                        let Some(index) = self.buttons.iter().position(|&c| c == event.code) else {
+                            continue;
+                        };
+                        let number = index as u32 + 1;
+                        self.pressed.retain(|&n| n != number);
+                        // 1 = press, 2 = autorepeat
+                        if event.value != 0 {
+                            self.pressed.push(number);
+                        }
+                        changed = true;
+                    }
+                    EV_ABS => {
+                        self.abs_values.insert(event.code, event.value);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        changed.then(|| self.report())
+    }
+
+    fn report(&self) -> HidFullReport {
+        let mut report = HidFullReport {
+            axis_values: HashMap::new(),
+            axis_bit_depths: HashMap::new(),
+            axis_names: HashMap::new(),
+            axis_ranges: HashMap::new(),
+            pressed_buttons: self.pressed.clone(),
+            report_id: 0,
+            hats: Vec::new(),
+            timestamp_ms: hid_reader::current_time_ms(),
+            is_16bit: false,
+        };
+        report.pressed_buttons.sort_unstable();
+
+        for &(code, usage, name, min, max) in &self.axes {
+            let value = self.abs_values.get(&code).copied().unwrap_or(min);
+            let range = (max - min).max(0);
+            // HID reports carry unsigned values, so shift the range to start at 0
+            report
+                .axis_values
+                .insert(usage, (value - min).clamp(0, u16::MAX as i32) as u16);
+            report
+                .axis_ranges
+                .insert(usage, (0, range.min(u16::MAX as i32)));
+            report.axis_names.insert(usage, name.to_string());
+            let bits = (32 - range.leading_zeros()) as u8;
+            report.axis_bit_depths.insert(usage, bits);
+            report.is_16bit |= bits > 8;
+        }
+
+        // Hats are X/Y pairs of -1/0/1; report them as 8-way hats clockwise from up
+        for (hat, x_code) in (ABS_HAT0X..=ABS_HAT3Y).step_by(2).enumerate() {
+            let y_code = x_code + 1;
+            if !self.hat_codes.contains(&x_code) && !self.hat_codes.contains(&y_code) {
+                continue;
+            }
+            let x = self.abs_values.get(&x_code).copied().unwrap_or(0).signum();
+            let y = self.abs_values.get(&y_code).copied().unwrap_or(0).signum();
+            let value = match (x, y) {
+                (0, -1) => 0,
+                (1, -1) => 1,
+                (1, 0) => 2,
+                (1, 1) => 3,
+                (0, 1) => 4,
+                (-1, 1) => 5,
+                (-1, 0) => 6,
+                (-1, -1) => 7,
+                _ => 8, // centered
+            };
+            report.hats.push(HatValue {
+                hat: hat as u32 + 1,
+                value,
+                logical_min: 0,
+                logical_max: 7,
+            });
+        }
+
+        report
+    }
+}
+
+/// Joysticks with a readable event node, in eventN order
+pub fn joysticks() -> Vec<EvdevJoystick> {
+    event_nodes()
+        .into_iter()
+        .filter(|(_, _, info)| info.is_joystick())
+        .map(|(path, file, info)| EvdevJoystick::open(path, file, info))
+        .collect()
+}
+
+/// Readable keyboard nodes and when they were found; re-scanned to pick up hotplugs
+static KEYBOARDS: Mutex<Option<(Instant, Vec<File>)>> = Mutex::new(None);
+
+/// The readable keyboard nodes, re-scanned when the last scan is stale
+fn keyboards() -> std::sync::MutexGuard<'static, Option<(Instant, Vec<File>)>> {
+    let mut keyboards = KEYBOARDS.lock().unwrap();
+    if keyboards
+        .as_ref()
+        .is_none_or(|(found, _)| found.elapsed() >= REFRESH_INTERVAL)
+    {
+        let files = event_nodes()
+            .into_iter()
+            .filter(|(_, _, info)| info.is_keyboard())
+            .map(|(_, file, _)| file)
+            .collect();
+        *keyboards = Some((Instant::now(), files));
+    }
+    keyboards
+}
+
+/// Whether any keyboard node is readable; without "input" group membership none are
+pub fn keyboard_available() -> bool {
+    keyboards()
+        .as_ref()
+        .is_some_and(|(_, files)| !files.is_empty())
+}
+
+/// SC names of the keys held on any keyboard, e.g. "lalt", "np_1"
+pub fn pressed_keys() -> Vec<&'static str> {
+    let mut held = [0u8; KEY_BYTES];
+    if let Some((_, files)) = keyboards().as_ref() {
+        for file in files {
+            let mut state = [0u8; KEY_BYTES];
+            // EVIOCGKEY
+            if ioctl_read(file, 0x18, &mut state).is_ok() {
+                for (held, state) in held.iter_mut().zip(state) {
+                    *held |= state;
+                }
+            }
+        }
+    }
+
+    sc_keys::KEYBOARD_KEYS
+        .iter()
+        .filter(|(_, _, code, ..)| bit_set(&held, *code))
+        .map(|(.., name, _)| *name)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::thread;
+
+    const EV_SYN: u16 = 0x00;
+    const BTN_TRIGGER: u16 = BTN_JOYSTICK;
+    const UINPUT_NAME: &str = "SCBU uinput test stick";
+
+    // ioctl requests from linux/uinput.h
+    const UI_DEV_CREATE: libc::c_ulong = 0x5501; // _IO('U', 1)
+    const UI_DEV_DESTROY: libc::c_ulong = 0x5502; // _IO('U', 2)
+    const UI_SET_EVBIT: libc::c_ulong = 0x4004_5564; // _IOW('U', 100, int)
+    const UI_SET_KEYBIT: libc::c_ulong = 0x4004_5565; // _IOW('U', 101, int)
+
+    /// A virtual joystick with two buttons, removed on drop
+    struct VirtualStick(File);
+
+    impl VirtualStick {
+        fn create() -> Self {
+            let file = OpenOptions::new()
+                .write(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open("/dev/uinput")
+                .expect("open /dev/uinput");
+            let set = |request, value: u16| {
+                // SAFETY: the uinput setup requests take an int argument by value
+                let result =
+                    unsafe { libc::ioctl(file.as_raw_fd(), request as _, value as libc::c_int) };
+                assert!(result >= 0, "{}", std::io::Error::last_os_error());
+            };
+            set(UI_SET_EVBIT, EV_KEY as u16);
+            set(UI_SET_KEYBIT, BTN_TRIGGER);
+            set(UI_SET_KEYBIT, BTN_TRIGGER + 1);
+
+            // struct uinput_user_dev { name[80], input_id, ff_effects_max, abs{max,min,fuzz,flat}[64] }
+            let mut dev = vec![0u8; 80 + 8 + 4 + 4 * 64 * 4];
+            dev[..UINPUT_NAME.len()].copy_from_slice(UINPUT_NAME.as_bytes());
+            dev[80..82].copy_from_slice(&0x06u16.to_ne_bytes()); // BUS_VIRTUAL
+            dev[82..84].copy_from_slice(&0x1234u16.to_ne_bytes());
+            dev[84..86].copy_from_slice(&0x5678u16.to_ne_bytes());
+            (&file).write_all(&dev).expect("write uinput_user_dev");
+
+            // SAFETY: UI_DEV_CREATE takes no argument
+            let result = unsafe { libc::ioctl(file.as_raw_fd(), UI_DEV_CREATE as _) };
+            assert!(result >= 0, "{}", std::io::Error::last_os_error());
+            // Give udev time to create the event node
+            thread::sleep(Duration::from_millis(500));
+            VirtualStick(file)
+        }
+
+        fn emit(&self, type_: u16, code: u16, value: i32) {
+            // SAFETY: input_event is plain data, all-zero is a valid value
+            let mut event: libc::input_event = unsafe { std::mem::zeroed() };
+            event.type_ = type_;
+            event.code = code;
+            event.value = value;
+            // SAFETY: the slice covers exactly the event
+            let bytes = unsafe {
+                std::slice::from_raw_parts(
+                    &event as *const _ as *const u8,
+                    std::mem::size_of::<libc::input_event>(),
+                )
+            };
+            (&self.0).write_all(bytes).expect("write input_event");
+        }
+
+        fn button(&self, code: u16, pressed: bool) {
+            self.emit(EV_KEY as u16, code, pressed as i32);
+            self.emit(EV_SYN, 0, 0);
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    impl Drop for VirtualStick {
+        fn drop(&mut self) {
+            // SAFETY: UI_DEV_DESTROY takes no argument
+            unsafe { libc::ioctl(self.0.as_raw_fd(), UI_DEV_DESTROY as _) };
+        }
+    }
+
+    // Needs write access to /dev/uinput and read access to the new event node:
+    // cargo test -- --ignored uinput
+    #[test]
+    #[ignore]
+    fn uinput_joystick_buttons_are_reported() {
+        let stick = VirtualStick::create();
+        let mut joystick = joysticks()
+            .into_iter()
+            .find(|joystick| joystick.name == UINPUT_NAME)
+            .expect("virtual joystick not found");
+        assert_eq!((joystick.vendor_id, joystick.product_id), (0x1234, 0x5678));
+
+        let baseline = joystick.read_report().expect("baseline report");
+        assert!(baseline.pressed_buttons.is_empty());
+        assert!(joystick.read_report().is_none());
+
+        stick.button(BTN_TRIGGER + 1, true);
+        let pressed = joystick.read_report().expect("report after press");
+        assert_eq!(pressed.pressed_buttons, vec![2]);
+
+        stick.button(BTN_TRIGGER + 1, false);
+        let released = joystick.read_report().expect("report after release");
+        assert!(released.pressed_buttons.is_empty());
+    }
+}
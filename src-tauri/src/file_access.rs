@@ -0,0 +1,339 @@
+use crate::error::AppError;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+const CONFIG_FILE: &str = "file_access.json";
+/// The only app data subfolders the webview may touch; everything else in app data
+/// (this config, the session, the device database, backups) is app-owned state
+const WEBVIEW_FOLDERS: [&str; 2] = ["templates", "recordings"];
+
+/// Why a path coming from the webview was refused
+#[derive(Debug, Serialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FileAccessError {
+    /// The path contains `..` or the name isn't a plain file name
+    Traversal {
+        path: String,
+    },
+    /// Relative paths are ambiguous and never accepted
+    NotAbsolute {
+        path: String,
+    },
+    /// The path resolves outside every allowed root
+    OutsideRoots {
+        path: String,
+    },
+    ExtensionNotAllowed {
+        path: String,
+        allowed: Vec<String>,
+    },
+    /// The path (or its parent folder, for writes) couldn't be resolved
+    Unresolvable {
+        path: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for FileAccessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileAccessError::Traversal { path } => {
+                write!(f, "Path traversal is not allowed: {}", path)
+            }
+            FileAccessError::NotAbsolute { path } => write!(f, "Path must be absolute: {}", path),
+            FileAccessError::OutsideRoots { path } => {
+                write!(f, "Path is outside the allowed folders: {}", path)
+            }
+            FileAccessError::ExtensionNotAllowed { path, allowed } => write!(
+                f,
+                "File type not allowed: {} (expected {})",
+                path,
+                allowed.join(", ")
+            ),
+            FileAccessError::Unresolvable { path, reason } => {
+                write!(f, "Cannot resolve {}: {}", path, reason)
+            }
+        }
+    }
+}
+
+impl From<FileAccessError> for String {
+    fn from(e: FileAccessError) -> String {
+        e.to_string()
+    }
+}
+
+/// User-chosen roots, persisted in app data
+///
+/// Every root comes from a native dialog opened by the backend; the webview can't name one
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RootConfig {
+    /// The configured StarCitizen folder
+    pub sc_base: Option<PathBuf>,
+    /// The character library folder
+    #[serde(default)]
+    pub character_library: Option<PathBuf>,
+    /// Folders of files picked in an open/save dialog (templates, exports, ...)
+    #[serde(default)]
+    pub picked_folders: Vec<PathBuf>,
+}
+
+/// The folders file commands may touch: the webview folders in app data, bundled
+/// resources, the SC base folder, the character library and the folders of files the user picked
+#[derive(Debug, Default)]
+pub struct FileAccess {
+    app_data: Option<PathBuf>,
+    resources: Option<PathBuf>,
+    config: RootConfig,
+}
+
+impl FileAccess {
+    /// Set the fixed roots and load the user-chosen ones
+    pub fn init(&mut self, app_data: PathBuf, resources: Option<PathBuf>) {
+        match fs::read_to_string(app_data.join(CONFIG_FILE)) {
+            Ok(json) => match serde_json::from_str(&json) {
+                Ok(config) => self.config = config,
+                Err(e) => warn!("Ignoring unreadable {}: {}", CONFIG_FILE, e),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to read {}: {}", CONFIG_FILE, e),
+        }
+        for folder in WEBVIEW_FOLDERS {
+            if let Err(e) = fs::create_dir_all(app_data.join(folder)) {
+                warn!("Failed to create {}: {}", folder, e);
+            }
+        }
+        self.app_data = Some(app_data);
+        self.resources = resources;
+    }
+
+    pub fn config(&self) -> &RootConfig {
+        &self.config
+    }
+
+    /// The StarCitizen folder picked in the settings dialog
+    pub fn set_sc_base(&mut self, folder: PathBuf) -> Result<(), AppError> {
+        info!("Allowing file access to SC folder {}", folder.display());
+        self.config.sc_base = Some(folder);
+        self.save()
+    }
+
+    /// The character library folder picked in the character manager
+    pub fn set_character_library(&mut self, folder: PathBuf) -> Result<(), AppError> {
+        info!(
+            "Allowing file access to character library {}",
+            folder.display()
+        );
+        self.config.character_library = Some(folder);
+        self.save()
+    }
+
+    /// The folder of a file picked in an open/save dialog
+    pub fn add_picked_folder(&mut self, folder: PathBuf) -> Result<(), AppError> {
+        if !self.config.picked_folders.contains(&folder) {
+            info!("Allowing file access to {}", folder.display());
+            self.config.picked_folders.push(folder);
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), AppError> {
+        let Some(app_data) = &self.app_data else {
+            return Ok(());
+        };
+        fs::create_dir_all(app_data)
+            .map_err(|e| AppError::io("Failed to create app data directory", app_data, e))?;
+        let json = serde_json::to_string_pretty(&self.config)?;
+        crate::backup::write_atomic(&app_data.join(CONFIG_FILE), json.as_bytes())
+    }
+
+    fn webview_folders(&self) -> Vec<PathBuf> {
+        self.app_data
+            .iter()
+            .flat_map(|app_data| WEBVIEW_FOLDERS.iter().map(|folder| app_data.join(folder)))
+            .collect()
+    }
+
+    fn roots(&self) -> Vec<PathBuf> {
+        self.webview_folders()
+            .iter()
+            .chain(self.resources.iter())
+            .chain(self.config.sc_base.iter())
+            .chain(self.config.character_library.iter())
+            .chain(self.config.picked_folders.iter())
+            // Roots that don't exist (yet) can't contain anything
+            .filter_map(|root| fs::canonicalize(root).ok())
+            .collect()
+    }
+
+    /// Check a full path from the webview; returns it canonicalized
+    /// The file itself may not exist yet (writes), but its folder must
+    pub fn resolve(&self, path: &str, extensions: &[&str]) -> Result<PathBuf, FileAccessError> {
+        let raw = checked_absolute(path)?;
+
+        let ext_ok = raw.extension().and_then(|e| e.to_str()).is_some_and(|e| {
+            extensions
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(e))
+        });
+        if !ext_ok {
+            return Err(FileAccessError::ExtensionNotAllowed {
+                path: path.to_string(),
+                allowed: extensions.iter().map(|e| e.to_string()).collect(),
+            });
+        }
+
+        let unresolvable = |reason: String| FileAccessError::Unresolvable {
+            path: path.to_string(),
+            reason,
+        };
+        let resolved = if raw.exists() {
+            fs::canonicalize(raw).map_err(|e| unresolvable(e.to_string()))?
+        } else {
+            let parent = raw
+                .parent()
+                .ok_or_else(|| unresolvable("no parent folder".to_string()))?;
+            let file_name = raw
+                .file_name()
+                .ok_or_else(|| unresolvable("no file name".to_string()))?;
+            fs::canonicalize(parent)
+                .map_err(|e| unresolvable(e.to_string()))?
+                .join(file_name)
+        };
+
+        self.inside_roots(path, resolved)
+    }
+
+    /// Check an existing folder path from the webview (an installation, the character
+    /// library); returns it canonicalized
+    pub fn resolve_dir(&self, path: &str) -> Result<PathBuf, FileAccessError> {
+        let raw = checked_absolute(path)?;
+        let unresolvable = |reason: String| FileAccessError::Unresolvable {
+            path: path.to_string(),
+            reason,
+        };
+        let resolved = fs::canonicalize(raw).map_err(|e| unresolvable(e.to_string()))?;
+        if !resolved.is_dir() {
+            return Err(unresolvable("not a folder".to_string()));
+        }
+
+        self.inside_roots(path, resolved)
+    }
+
+    fn inside_roots(&self, path: &str, resolved: PathBuf) -> Result<PathBuf, FileAccessError> {
+        // App data is off limits outside the webview folders, even under a picked folder
+        let app_owned = self
+            .app_data
+            .iter()
+            .filter_map(|app_data| fs::canonicalize(app_data).ok())
+            .any(|app_data| resolved.starts_with(app_data))
+            && !self
+                .webview_folders()
+                .iter()
+                .filter_map(|folder| fs::canonicalize(folder).ok())
+                .any(|folder| resolved.starts_with(folder));
+        if app_owned || !self.roots().iter().any(|root| resolved.starts_with(root)) {
+            warn!("Refused access outside allowed folders: {}", path);
+            return Err(FileAccessError::OutsideRoots {
+                path: path.to_string(),
+            });
+        }
+        Ok(resolved)
+    }
+
+    /// Check a file name from the webview and resolve it inside `dir`
+    pub fn resolve_in(
+        &self,
+        dir: &Path,
+        name: &str,
+        extensions: &[&str],
+    ) -> Result<PathBuf, FileAccessError> {
+        check_file_name(name)?;
+        self.resolve(&dir.join(name).to_string_lossy(), extensions)
+    }
+}
+
+/// A bare file name: no folders, no `..`, no drive prefix
+pub fn check_file_name(name: &str) -> Result<(), FileAccessError> {
+    let mut components = Path::new(name).components();
+    let plain_name = matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    );
+    // Both separators are checked because `\` is a plain character on Unix
+    if !plain_name || name.contains(['/', '\\', ':']) {
+        return Err(FileAccessError::Traversal {
+            path: name.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn checked_absolute(path: &str) -> Result<&Path, FileAccessError> {
+    let raw = Path::new(path);
+    if raw.components().any(|c| matches!(c, Component::ParentDir)) {
+        return Err(FileAccessError::Traversal {
+            path: path.to_string(),
+        });
+    }
+    if !raw.is_absolute() {
+        return Err(FileAccessError::NotAbsolute {
+            path: path.to_string(),
+        });
+    }
+    Ok(raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(p: &Path) -> String {
+        p.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn app_owned_state_is_outside_the_roots() {
+        let app_data = tempfile::tempdir().unwrap();
+        let mut access = FileAccess::default();
+        access.init(app_data.path().to_path_buf(), None);
+        access.save().unwrap();
+
+        let templates = app_data.path().join("templates");
+        let recordings = app_data.path().join("recordings");
+        assert!(access
+            .resolve(&path(&templates.join("stick.json")), &["json"])
+            .is_ok());
+        assert!(access
+            .resolve(&path(&recordings.join("take1.json")), &["json"])
+            .is_ok());
+
+        for state_file in [CONFIG_FILE, "session.json", "device-database.json"] {
+            assert_eq!(
+                access.resolve(&path(&app_data.path().join(state_file)), &["json"]),
+                Err(FileAccessError::OutsideRoots {
+                    path: path(&app_data.path().join(state_file)),
+                })
+            );
+        }
+
+        // Picking a file in app data itself doesn't open it up either
+        fs::create_dir_all(app_data.path().join("backups")).unwrap();
+        access
+            .add_picked_folder(app_data.path().to_path_buf())
+            .unwrap();
+        assert!(access
+            .resolve(&path(&app_data.path().join(CONFIG_FILE)), &["json"])
+            .is_err());
+        assert!(access
+            .resolve(
+                &path(&app_data.path().join("backups").join("backup.json")),
+                &["json"]
+            )
+            .is_err());
+    }
+}
@@ -1,7 +1,7 @@
 use hidapi::HidApi;
-use hidreport::{Field, Report, ReportDescriptor};
+use hidreport::{ArrayField, Field, Report, ReportDescriptor, VariableField};
 use hut::Usage;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::ffi::CString;
 
@@ -10,8 +10,7 @@ pub struct OpenedHidDevice {
 }
 
 impl OpenedHidDevice {
-    pub fn open(device_path: &str) -> Result<Self, String> {
-        let api = HidApi::new().map_err(|e| format!("Failed to initialize HID API: {}", e))?;
+    pub fn open(api: &HidApi, device_path: &str) -> Result<Self, String> {
         let c_path =
             CString::new(device_path).map_err(|e| format!("Invalid device path: {}", e))?;
         let device = api
@@ -35,6 +34,20 @@ impl OpenedHidDevice {
             Ok(Vec::new())
         }
     }
+
+    /// The raw HID report descriptor
+    pub fn report_descriptor(&self) -> Result<Vec<u8>, String> {
+        // HID descriptors are at most 4096 bytes
+        let mut descriptor_buf = vec![0u8; 4096];
+        let descriptor_len = self
+            .device
+            .get_report_descriptor(&mut descriptor_buf)
+            .map_err(|e| format!("Failed to get report descriptor: {}", e))?;
+
+        eprintln!("[HID] Report descriptor length: {} bytes", descriptor_len);
+        descriptor_buf.truncate(descriptor_len);
+        Ok(descriptor_buf)
+    }
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -64,17 +77,60 @@ pub struct HidFullReport {
     pub axis_bit_depths: HashMap<u32, u8>,
     pub axis_names: HashMap<u32, String>,
     pub axis_ranges: HashMap<u32, (i32, i32)>,
+    /// DirectInput button numbers (see [`ButtonMap`]), sorted
     pub pressed_buttons: Vec<u32>,
+    /// Report ID this report was parsed from (0 when the device doesn't use IDs)
+    pub report_id: u8,
+    /// Every hat switch, in descriptor order (hats also appear in `axis_values` under
+    /// usage 0x39, where a second hat overwrites the first)
+    pub hats: Vec<HatValue>,
     pub timestamp_ms: u64,
     pub is_16bit: bool,
 }
 
-/// List all HID devices that appear to be game controllers
-pub fn list_hid_game_controllers() -> Result<Vec<HidDeviceListItem>, String> {
-    let api = HidApi::new().map_err(|e| format!("Failed to initialize HID API: {}", e))?;
+/// The raw position of one hat switch
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct HatValue {
+    /// 1-based position among the device's hats, which is SC's hatN numbering
+    pub hat: u32,
+    pub value: i32,
+    pub logical_min: i32,
+    pub logical_max: i32,
+}
+
+impl HatValue {
+    /// The SC directions held, two for a diagonal; empty when centered
+    /// 8-position hats go clockwise from up in 45 degree steps, 4-position ones in 90
+    pub fn directions(&self) -> &'static [&'static str] {
+        const EIGHT_WAY: [&[&str]; 8] = [
+            &["up"],
+            &["up", "right"],
+            &["right"],
+            &["down", "right"],
+            &["down"],
+            &["down", "left"],
+            &["left"],
+            &["up", "left"],
+        ];
+        const FOUR_WAY: [&[&str]; 4] = [&["up"], &["right"], &["down"], &["left"]];
+
+        let position = self.value - self.logical_min;
+        let positions = self.logical_max - self.logical_min + 1;
+        // Out-of-range values (the null state) mean centered
+        if position < 0 || position >= positions {
+            return &[];
+        }
+        match positions {
+            4 => FOUR_WAY[position as usize],
+            8 => EIGHT_WAY[position as usize],
+            _ => &[],
+        }
+    }
+}
 
-    let devices: Vec<HidDeviceListItem> = api
-        .device_list()
+/// The HID devices in `api`'s device list that appear to be game controllers
+pub fn game_controllers(api: &HidApi) -> Vec<HidDeviceListItem> {
+    api.device_list()
         .filter(|info| {
             // Filter for devices that are likely game controllers
             // HID Usage Page 0x01 = Generic Desktop Controls
@@ -94,50 +150,7 @@ pub fn list_hid_game_controllers() -> Result<Vec<HidDeviceListItem>, String> {
             path: info.path().to_string_lossy().to_string(),
             interface_number: info.interface_number(),
         })
-        .collect();
-
-    eprintln!("[HID] Found {} game controller devices", devices.len());
-
-    Ok(devices)
-}
-
-/// Open a HID device and read a single report
-pub fn read_hid_report(device_path: &str, timeout_ms: i32) -> Result<Vec<u8>, String> {
-    let api = HidApi::new().map_err(|e| format!("Failed to initialize HID API: {}", e))?;
-
-    // Convert Rust string to CString for hidapi
-    let c_path = CString::new(device_path)
-        .map_err(|e| format!("Invalid device path (contains null byte): {}", e))?;
-
-    let device = api
-        .open_path(&c_path)
-        .map_err(|e| format!("Failed to open HID device: {}", e))?;
-
-    let mut buf = [0u8; 256];
-    let len = device
-        .read_timeout(&mut buf, timeout_ms)
-        .map_err(|e| format!("Failed to read from HID device: {}", e))?;
-
-    if len > 0 {
-        eprintln!("[HID] Read {} bytes from device", len);
-        eprintln!("[HID] Raw report: {:?}", &buf[..len]);
-
-        // Print first 16 bytes with positions for easier analysis
-        if len >= 16 {
-            eprint!("[HID] Bytes 0-15:  ");
-            for byte in buf.iter().take(16) {
-                eprint!("{:02X} ", byte);
-            }
-            eprintln!();
-            eprint!("[HID] Positions:   ");
-            for i in 0..16 {
-                eprint!("{:2} ", i);
-            }
-            eprintln!();
-        }
-    }
-
-    Ok(buf[..len].to_vec())
+        .collect()
 }
 
 /// Parse a HID report using a pre-fetched descriptor (recommended)
@@ -146,95 +159,125 @@ pub fn parse_hid_axes_from_descriptor_bytes(
     report: &[u8],
     descriptor: &[u8],
 ) -> Result<HidAxisReport, String> {
-    let full_report = parse_hid_full_report(report, descriptor)?;
-
-    Ok(HidAxisReport {
-        axis_values: full_report.axis_values,
-        axis_bit_depths: full_report.axis_bit_depths,
-        axis_names: full_report.axis_names,
-        axis_ranges: full_report.axis_ranges,
-        timestamp_ms: full_report.timestamp_ms,
-        is_16bit: full_report.is_16bit,
-    })
+    parse_hid_full_report(report, descriptor).map(HidAxisReport::from)
+}
+
+impl From<HidFullReport> for HidAxisReport {
+    fn from(full_report: HidFullReport) -> Self {
+        HidAxisReport {
+            axis_values: full_report.axis_values,
+            axis_bit_depths: full_report.axis_bit_depths,
+            axis_names: full_report.axis_names,
+            axis_ranges: full_report.axis_ranges,
+            timestamp_ms: full_report.timestamp_ms,
+            is_16bit: full_report.is_16bit,
+        }
+    }
 }
 
 /// Parse a HID report to extract BOTH axes and buttons from descriptor
 /// This is the comprehensive version that returns all input data
+///
+/// Compiles the descriptor on every call; pollers should keep a [`ReportParser`]
+/// (see `HidService::parser`) instead
 pub fn parse_hid_full_report(report: &[u8], descriptor: &[u8]) -> Result<HidFullReport, String> {
-    // Parse the descriptor
-    let rdesc = ReportDescriptor::try_from(descriptor)
-        .map_err(|e| format!("Failed to parse report descriptor: {:?}", e))?;
+    ReportParser::new(descriptor)?.parse(report)
+}
 
-    // Find the matching input report
-    let input_report = rdesc
-        .find_input_report(report)
-        .ok_or("No matching input report found")?;
+/// A report descriptor compiled once per device: for every input report, where each
+/// button, hat and axis sits and how it is numbered. Parsing a report only slices bits.
+#[derive(Clone, Debug)]
+pub struct ReportParser {
+    /// Keyed by report ID (0 when the device doesn't use IDs)
+    reports: HashMap<u8, CompiledReport>,
+    uses_report_ids: bool,
+}
 
-    let mut axis_values = HashMap::new();
-    let mut axis_bit_depths = HashMap::new();
-    let mut axis_names = HashMap::new();
-    let mut axis_ranges: HashMap<u32, (i32, i32)> = HashMap::new();
-    let mut pressed_buttons = Vec::new();
-    let mut max_bits = 8;
-    let mut button_index: u32 = 1; // 1-based button numbering
-
-    // Extract values from each field
-    for field in input_report.fields() {
-        match field {
-            Field::Variable(var) => {
-                let usage_page = u16::from(var.usage.usage_page);
-
-                // Check if this is a button (Usage Page 0x09)
-                if usage_page == 0x09 {
-                    // This is a button
-                    match var.extract(report) {
-                        Ok(field_value) => {
-                            let value: i32 = field_value.into();
-                            if value != 0 {
-                                // Button is pressed
-                                pressed_buttons.push(button_index);
-                                eprintln!(
-                                    "[HID] Button {} pressed (value: {})",
-                                    button_index, value
-                                );
-                            }
-                            button_index += 1;
-                        }
-                        Err(e) => {
-                            eprintln!("[HID] Failed to extract button value: {:?}", e);
-                            button_index += 1;
-                        }
-                    }
-                    continue;
-                }
+#[derive(Clone, Debug)]
+struct CompiledReport {
+    fields: Vec<CompiledField>,
+    is_16bit: bool,
+}
 
-                // This is an axis
-                let bits = var.bits.end - var.bits.start;
-                max_bits = max_bits.max(bits);
+#[derive(Clone, Debug)]
+enum CompiledField {
+    Button {
+        field: VariableField,
+        number: u32,
+    },
+    /// `numbers[value - logical_min]` is the button pressed when a slot holds `value`
+    ButtonArray {
+        field: ArrayField,
+        logical_min: i32,
+        numbers: Vec<Option<u32>>,
+    },
+    Hat {
+        field: VariableField,
+        hat: u32,
+    },
+    Axis {
+        field: VariableField,
+        index: u32,
+        name: String,
+        effective_bits: u8,
+        range: (i32, i32),
+    },
+}
+
+impl ReportParser {
+    pub fn new(descriptor: &[u8]) -> Result<Self, String> {
+        let rdesc = ReportDescriptor::try_from(descriptor)
+            .map_err(|e| format!("Failed to parse report descriptor: {:?}", e))?;
+        Ok(Self::from_descriptor(&rdesc))
+    }
 
-                // Construct usage value
-                let usage_val: u32 =
-                    ((usage_page as u32) << 16) | (u16::from(var.usage.usage_id) as u32);
+    pub fn from_descriptor(rdesc: &ReportDescriptor) -> Self {
+        let buttons = ButtonMap::from_descriptor(rdesc);
+        let mut reports = HashMap::new();
+        let mut uses_report_ids = false;
+        // Hats are numbered across the whole descriptor
+        let mut hat_index: u32 = 0;
+
+        for report in rdesc.input_reports() {
+            uses_report_ids |= report.report_id().is_some();
+            let report_id = report.report_id().as_ref().map(u8::from).unwrap_or(0);
+            let mut fields = Vec::new();
+            let mut max_bits = 8;
+
+            for field in report.fields() {
+                match field {
+                    Field::Variable(var) => {
+                        let usage_page = u16::from(var.usage.usage_page);
+                        let usage_id = u16::from(var.usage.usage_id);
+
+                        if usage_page == USAGE_PAGE_BUTTON {
+                            if let Some(number) = buttons.number(report_id, usage_id) {
+                                fields.push(CompiledField::Button {
+                                    field: var.clone(),
+                                    number,
+                                });
+                            }
+                            continue;
+                        }
 
-                // Use the usage ID as the axis index for consistency
-                let axis_index = u16::from(var.usage.usage_id) as u32;
+                        if is_hat_field(field) {
+                            hat_index += 1;
+                            fields.push(CompiledField::Hat {
+                                field: var.clone(),
+                                hat: hat_index,
+                            });
+                        }
 
-                // Extract the value
-                match var.extract(report) {
-                    Ok(field_value) => {
-                        let value: i32 = field_value.into();
-                        let value_u16 = value.max(0).min(u16::MAX as i32) as u16;
+                        // Everything else (hats included) is an axis keyed by its usage ID
+                        let bits = var.bits.end - var.bits.start;
+                        max_bits = max_bits.max(bits);
 
-                        // Get axis name
-                        let axis_name = Usage::try_from(usage_val)
+                        let usage_val: u32 = ((usage_page as u32) << 16) | (usage_id as u32);
+                        let name = Usage::try_from(usage_val)
                             .ok()
                             .map(|u| u.name().to_string())
                             .unwrap_or_else(|| {
-                                format!(
-                                    "Usage {:04x}:{:04x}",
-                                    usage_page,
-                                    u16::from(var.usage.usage_id)
-                                )
+                                format!("Usage {:04x}:{:04x}", usage_page, usage_id)
                             });
 
                         let logical_min = i32::from(var.logical_minimum);
@@ -248,56 +291,169 @@ pub fn parse_hid_full_report(report: &[u8], descriptor: &[u8]) -> Result<HidFull
                             bits as u32
                         };
 
-                        axis_values.insert(axis_index, value_u16);
-                        axis_bit_depths.insert(axis_index, effective_bits as u8);
-                        axis_names.insert(axis_index, axis_name.clone());
-                        axis_ranges.insert(axis_index, (logical_min, logical_max));
+                        fields.push(CompiledField::Axis {
+                            field: var.clone(),
+                            index: usage_id as u32,
+                            name,
+                            effective_bits: effective_bits as u8,
+                            range: (logical_min, logical_max),
+                        });
                     }
-                    Err(e) => {
-                        eprintln!("[HID] Failed to extract axis value: {:?}", e);
+                    Field::Array(arr) => {
+                        // Each array slot holds the index of a pressed usage, counted from
+                        // the logical minimum; anything outside the range means "nothing"
+                        let numbers: Vec<Option<u32>> = arr
+                            .usages()
+                            .iter()
+                            .map(|u| {
+                                if u16::from(u.usage_page) == USAGE_PAGE_BUTTON {
+                                    buttons.number(report_id, u16::from(u.usage_id))
+                                } else {
+                                    None
+                                }
+                            })
+                            .collect();
+                        if numbers.iter().any(Option::is_some) {
+                            fields.push(CompiledField::ButtonArray {
+                                field: arr.clone(),
+                                logical_min: i32::from(arr.logical_minimum),
+                                numbers,
+                            });
+                        }
+                    }
+                    Field::Constant(_) => {
+                        // Padding, skip
                     }
                 }
             }
-            Field::Array(arr) => {
-                // Array fields for buttons - these typically encode multiple button states
-                // Each value in the array represents a button usage ID that is pressed
-                // Array fields are often used for keyboard-like button encoding
-                match arr.extract(report) {
+
+            reports.insert(
+                report_id,
+                CompiledReport {
+                    fields,
+                    is_16bit: max_bits > 8,
+                },
+            );
+        }
+
+        Self {
+            reports,
+            uses_report_ids,
+        }
+    }
+
+    /// Extract axes, buttons and hats from one input report
+    pub fn parse(&self, report: &[u8]) -> Result<HidFullReport, String> {
+        let report_id = if self.uses_report_ids {
+            *report.first().ok_or("Empty report")?
+        } else {
+            0
+        };
+        let compiled = self
+            .reports
+            .get(&report_id)
+            .ok_or("No matching input report found")?;
+
+        let mut axis_values = HashMap::new();
+        let mut axis_bit_depths = HashMap::new();
+        let mut axis_names = HashMap::new();
+        let mut axis_ranges: HashMap<u32, (i32, i32)> = HashMap::new();
+        let mut pressed_buttons = Vec::new();
+        let mut hats = Vec::new();
+
+        for field in &compiled.fields {
+            match field {
+                CompiledField::Button { field, number } => match field.extract(report) {
+                    Ok(field_value) => {
+                        let value: i32 = field_value.into();
+                        if value != 0 {
+                            pressed_buttons.push(*number);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("[HID] Failed to extract button value: {:?}", e);
+                    }
+                },
+                CompiledField::ButtonArray {
+                    field,
+                    logical_min,
+                    numbers,
+                } => match field.extract(report) {
                     Ok(values) => {
                         for field_value in values {
-                            let button_val: i32 = field_value.into();
-                            if button_val > 0 && button_val <= 255 {
-                                // Valid button press (usage ID)
-                                pressed_buttons.push(button_val as u32);
-                                eprintln!("[HID] Array button {} pressed", button_val);
+                            let index: i32 = field_value.into();
+                            if let Some(&Some(number)) = usize::try_from(index - logical_min)
+                                .ok()
+                                .and_then(|i| numbers.get(i))
+                            {
+                                pressed_buttons.push(number);
                             }
                         }
                     }
                     Err(e) => {
                         eprintln!("[HID] Failed to extract array button value: {:?}", e);
                     }
-                }
-            }
-            Field::Constant(_) => {
-                // Padding, skip
+                },
+                CompiledField::Hat { field, hat } => match field.extract(report) {
+                    Ok(field_value) => hats.push(HatValue {
+                        hat: *hat,
+                        value: field_value.into(),
+                        logical_min: i32::from(field.logical_minimum),
+                        logical_max: i32::from(field.logical_maximum),
+                    }),
+                    Err(e) => eprintln!("[HID] Failed to extract hat value: {:?}", e),
+                },
+                CompiledField::Axis {
+                    field,
+                    index,
+                    name,
+                    effective_bits,
+                    range,
+                } => match field.extract(report) {
+                    Ok(field_value) => {
+                        let value: i32 = field_value.into();
+                        let value_u16 = value.max(0).min(u16::MAX as i32) as u16;
+
+                        axis_values.insert(*index, value_u16);
+                        axis_bit_depths.insert(*index, *effective_bits);
+                        axis_names.insert(*index, name.clone());
+                        axis_ranges.insert(*index, *range);
+                    }
+                    Err(e) => {
+                        eprintln!("[HID] Failed to extract axis value: {:?}", e);
+                    }
+                },
             }
         }
+
+        pressed_buttons.sort_unstable();
+        pressed_buttons.dedup();
+
+        Ok(HidFullReport {
+            axis_values,
+            axis_bit_depths,
+            axis_names,
+            axis_ranges,
+            pressed_buttons,
+            report_id,
+            hats,
+            timestamp_ms: current_time_ms(),
+            is_16bit: compiled.is_16bit,
+        })
     }
+}
 
-    let is_16bit = max_bits > 8;
-
-    Ok(HidFullReport {
-        axis_values,
-        axis_bit_depths,
-        axis_names,
-        axis_ranges,
-        pressed_buttons,
-        timestamp_ms: current_time_ms(),
-        is_16bit,
-    })
+fn is_hat_field(field: &Field) -> bool {
+    match field {
+        Field::Variable(var) => {
+            u16::from(var.usage.usage_page) == USAGE_PAGE_GENERIC_DESKTOP
+                && u16::from(var.usage.usage_id) == USAGE_HAT_SWITCH
+        }
+        _ => false,
+    }
 }
 
-fn current_time_ms() -> u64 {
+pub fn current_time_ms() -> u64 {
     use std::time::{SystemTime, UNIX_EPOCH};
 
     SystemTime::now()
@@ -306,43 +462,193 @@ fn current_time_ms() -> u64 {
         .as_millis() as u64
 }
 
-/// Get the raw HID report descriptor bytes from a device
-/// This can be cached and reused for parsing multiple reports
-pub fn get_hid_descriptor_bytes(device_path: &str) -> Result<Vec<u8>, String> {
-    let api = HidApi::new().map_err(|e| format!("Failed to initialize HID API: {}", e))?;
+// HID usage pages and Generic Desktop usages used to classify fields
+const USAGE_PAGE_GENERIC_DESKTOP: u16 = 0x01;
+const USAGE_PAGE_SIMULATION: u16 = 0x02;
+const USAGE_PAGE_BUTTON: u16 = 0x09;
+const USAGE_HAT_SWITCH: u16 = 0x39;
+
+/// DirectInput-compatible button numbering for a whole device
+///
+/// A button is numbered by its Button-page usage ID, whichever input report carries it,
+/// so gaps in the usage ranges leave gaps in the numbering. A usage ID that shows up again
+/// in a later report (some devices restart at 1 per report) continues after the highest one.
+#[derive(Clone, Debug, Default)]
+pub struct ButtonMap {
+    numbers: HashMap<(u8, u16), u32>,
+    count: u32,
+}
+
+impl ButtonMap {
+    pub fn from_descriptor(rdesc: &ReportDescriptor) -> Self {
+        let mut usages: Vec<(u8, u16)> = Vec::new();
+        for report in rdesc.input_reports() {
+            let report_id = report.report_id().as_ref().map(u8::from).unwrap_or(0);
+            for field in report.fields() {
+                match field {
+                    Field::Variable(var)
+                        if u16::from(var.usage.usage_page) == USAGE_PAGE_BUTTON =>
+                    {
+                        usages.push((report_id, u16::from(var.usage.usage_id)));
+                    }
+                    Field::Array(arr) => usages.extend(
+                        arr.usages()
+                            .iter()
+                            .filter(|u| u16::from(u.usage_page) == USAGE_PAGE_BUTTON)
+                            .map(|u| (report_id, u16::from(u.usage_id))),
+                    ),
+                    _ => {}
+                }
+            }
+        }
+
+        let highest = usages
+            .iter()
+            .map(|&(_, usage)| usage as u32)
+            .max()
+            .unwrap_or(0);
+        let mut map = ButtonMap {
+            numbers: HashMap::new(),
+            count: highest,
+        };
+        let mut seen = std::collections::HashSet::new();
+        for (report_id, usage) in usages {
+            if map.numbers.contains_key(&(report_id, usage)) {
+                continue;
+            }
+            let number = if seen.insert(usage) {
+                usage as u32
+            } else {
+                map.count += 1;
+                map.count
+            };
+            map.numbers.insert((report_id, usage), number);
+        }
+        map
+    }
+
+    /// The 1-based button number of a usage in a report, if it is a button
+    pub fn number(&self, report_id: u8, usage_id: u16) -> Option<u32> {
+        self.numbers.get(&(report_id, usage_id)).copied()
+    }
+
+    /// Highest button number
+    pub fn count(&self) -> usize {
+        self.count as usize
+    }
+}
+
+/// One axis input as declared in the descriptor
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AxisCapability {
+    pub usage_page: u16,
+    pub usage_id: u16,
+    pub name: String,
+    pub bits: usize,
+    pub logical_min: i32,
+    pub logical_max: i32,
+}
+
+/// What a device can do, read from its report descriptor
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DeviceCapabilities {
+    /// Highest button number (see [`ButtonMap`]); gaps in the usages still count
+    pub button_count: usize,
+    pub axis_count: usize,
+    /// Axes in descriptor order
+    pub axes: Vec<AxisCapability>,
+    pub hat_count: usize,
+    pub input_report_count: usize,
+    /// Report IDs (0 when the device doesn't use IDs)
+    pub output_report_ids: Vec<u8>,
+    pub feature_report_ids: Vec<u8>,
+}
+
+fn report_ids<'a>(reports: impl Iterator<Item = &'a Option<hidreport::ReportId>>) -> Vec<u8> {
+    reports
+        .map(|id| id.as_ref().map(u8::from).unwrap_or(0))
+        .collect()
+}
 
-    let c_path = CString::new(device_path).map_err(|e| format!("Invalid device path: {}", e))?;
+impl DeviceCapabilities {
+    pub fn from_descriptor(rdesc: &ReportDescriptor) -> Self {
+        let mut caps = DeviceCapabilities {
+            input_report_count: rdesc.input_reports().len(),
+            output_report_ids: report_ids(rdesc.output_reports().iter().map(|r| r.report_id())),
+            feature_report_ids: report_ids(rdesc.feature_reports().iter().map(|r| r.report_id())),
+            ..Default::default()
+        };
 
-    let device = api
-        .open_path(&c_path)
-        .map_err(|e| format!("Failed to open device: {}", e))?;
+        for field in rdesc.input_reports().iter().flat_map(|r| r.fields()) {
+            match field {
+                Field::Variable(var) => {
+                    let usage_page = u16::from(var.usage.usage_page);
+                    let usage_id = u16::from(var.usage.usage_id);
+                    match (usage_page, usage_id) {
+                        // Counted by ButtonMap below
+                        (USAGE_PAGE_BUTTON, _) => {}
+                        (USAGE_PAGE_GENERIC_DESKTOP, USAGE_HAT_SWITCH) => caps.hat_count += 1,
+                        (USAGE_PAGE_GENERIC_DESKTOP | USAGE_PAGE_SIMULATION, _) => {
+                            let usage_val = ((usage_page as u32) << 16) | usage_id as u32;
+                            caps.axes.push(AxisCapability {
+                                usage_page,
+                                usage_id,
+                                name: Usage::try_from(usage_val)
+                                    .ok()
+                                    .map(|u| u.name().to_string())
+                                    .unwrap_or_else(|| {
+                                        format!("Usage {:04x}:{:04x}", usage_page, usage_id)
+                                    }),
+                                bits: var.bits.end - var.bits.start,
+                                logical_min: i32::from(var.logical_minimum),
+                                logical_max: i32::from(var.logical_maximum),
+                            });
+                        }
+                        // Vendor-defined and other pages aren't inputs SC can bind
+                        _ => {}
+                    }
+                }
+                Field::Array(arr) => {
+                    let usage_page = u16::from(arr.usage_range().minimum().usage_page());
+                    if usage_page == USAGE_PAGE_GENERIC_DESKTOP
+                        && arr
+                            .usages()
+                            .iter()
+                            .any(|u| u16::from(u.usage_id) == USAGE_HAT_SWITCH)
+                    {
+                        caps.hat_count += 1;
+                    }
+                }
+                Field::Constant(_) => {}
+            }
+        }
 
-    // Get the report descriptor (max 4096 bytes for HID descriptors)
-    let mut descriptor_buf = vec![0u8; 4096];
-    let descriptor_len = device
-        .get_report_descriptor(&mut descriptor_buf)
-        .map_err(|e| format!("Failed to get report descriptor: {}", e))?;
+        caps.button_count = ButtonMap::from_descriptor(rdesc).count();
+        caps.axis_count = caps.axes.len();
+        caps
+    }
+}
 
-    eprintln!("[HID] Report descriptor length: {} bytes", descriptor_len);
-    Ok(descriptor_buf[..descriptor_len].to_vec())
+/// Parse a device's descriptor into its capabilities
+pub fn get_device_capabilities(descriptor: &[u8]) -> Result<DeviceCapabilities, String> {
+    let rdesc = ReportDescriptor::try_from(descriptor)
+        .map_err(|e| format!("Failed to parse report descriptor: {:?}", e))?;
+    Ok(DeviceCapabilities::from_descriptor(&rdesc))
 }
 
 /// Get HID axis names from the device's report descriptor using proper HID parsing libraries
 /// Returns a mapping of axis index -> axis name (e.g., "X", "Y", "Rz", "Slider")
-pub fn get_axis_names_from_descriptor(device_path: &str) -> Result<HashMap<u32, String>, String> {
-    let descriptor = get_hid_descriptor_bytes(device_path)?;
+pub fn get_axis_names_from_descriptor(descriptor: &[u8]) -> Result<HashMap<u32, String>, String> {
     // Parse the descriptor using the hidreport crate
-    parse_hid_descriptor_with_library(&descriptor)
+    parse_hid_descriptor_with_library(descriptor)
 }
 
 /// Get a mapping from DirectInput axis indices (1-based sequential) to HID usage IDs
 /// This is needed because DirectInput returns axes in sequential order (1, 2, 3...)
 /// but HID uses usage IDs (48=X, 49=Y, 50=Z, etc.)
-pub fn get_directinput_to_hid_axis_mapping(device_path: &str) -> Result<HashMap<u32, u32>, String> {
-    let descriptor = get_hid_descriptor_bytes(device_path)?;
-
+pub fn get_directinput_to_hid_axis_mapping(descriptor: &[u8]) -> Result<HashMap<u32, u32>, String> {
     // Parse the report descriptor
-    let rdesc = ReportDescriptor::try_from(descriptor.as_slice())
+    let rdesc = ReportDescriptor::try_from(descriptor)
         .map_err(|e| format!("Failed to parse report descriptor: {:?}", e))?;
 
     let mut mapping = HashMap::new();
@@ -463,3 +769,114 @@ fn parse_hid_descriptor_with_library(descriptor: &[u8]) -> Result<HashMap<u32, S
 
     Ok(axis_names)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// A HOTAS-sized stick: report ID 1, eight 16-bit axes, an 8-way hat and 64 buttons
+    const STICK_DESCRIPTOR: &[u8] = &[
+        0x05, 0x01, // Usage Page (Generic Desktop)
+        0x09, 0x04, // Usage (Joystick)
+        0xa1, 0x01, // Collection (Application)
+        0x85, 0x01, //   Report ID (1)
+        0x09, 0x30, 0x09, 0x31, 0x09, 0x32, 0x09, 0x33, // X, Y, Z, Rx
+        0x09, 0x34, 0x09, 0x35, 0x09, 0x36, 0x09, 0x37, // Ry, Rz, Slider, Dial
+        0x15, 0x00, //   Logical Minimum (0)
+        0x27, 0xff, 0xff, 0x00, 0x00, //   Logical Maximum (65535)
+        0x75, 0x10, //   Report Size (16)
+        0x95, 0x08, //   Report Count (8)
+        0x81, 0x02, //   Input (Data, Var, Abs)
+        0x09, 0x39, //   Usage (Hat switch)
+        0x25, 0x07, //   Logical Maximum (7)
+        0x35, 0x00, //   Physical Minimum (0)
+        0x46, 0x3b, 0x01, //   Physical Maximum (315)
+        0x65, 0x14, //   Unit (Degrees)
+        0x75, 0x04, //   Report Size (4)
+        0x95, 0x01, //   Report Count (1)
+        0x81, 0x42, //   Input (Data, Var, Abs, Null)
+        0x65, 0x00, //   Unit (None)
+        0x81, 0x03, //   Input (Const) - 4 bits padding
+        0x05, 0x09, //   Usage Page (Button)
+        0x19, 0x01, //   Usage Minimum (1)
+        0x29, 0x40, //   Usage Maximum (64)
+        0x25, 0x01, //   Logical Maximum (1)
+        0x45, 0x01, //   Physical Maximum (1)
+        0x75, 0x01, //   Report Size (1)
+        0x95, 0x40, //   Report Count (64)
+        0x81, 0x02, //   Input (Data, Var, Abs)
+        0xc0, // End Collection
+    ];
+
+    /// A report for [`STICK_DESCRIPTOR`]; `hat` 8 is centered
+    fn stick_report(axis: u16, hat: u8, buttons: &[u32]) -> Vec<u8> {
+        let mut report = vec![0x01];
+        for _ in 0..8 {
+            report.extend_from_slice(&axis.to_le_bytes());
+        }
+        report.push(hat & 0x0f);
+        let mut bits = 0u64;
+        for button in buttons {
+            bits |= 1 << (button - 1);
+        }
+        report.extend_from_slice(&bits.to_le_bytes());
+        report
+    }
+
+    #[test]
+    fn report_parser_matches_one_shot_parse() {
+        let parser = ReportParser::new(STICK_DESCRIPTOR).unwrap();
+
+        for report in [
+            stick_report(0, 8, &[]),
+            stick_report(0x8000, 2, &[1]),
+            stick_report(0xffff, 7, &[5, 33, 64]),
+        ] {
+            let compiled = parser.parse(&report).unwrap();
+            let one_shot = parse_hid_full_report(&report, STICK_DESCRIPTOR).unwrap();
+            assert_eq!(compiled.pressed_buttons, one_shot.pressed_buttons);
+            assert_eq!(compiled.axis_values, one_shot.axis_values);
+            assert_eq!(compiled.hats, one_shot.hats);
+        }
+
+        let parsed = parser.parse(&stick_report(0x1234, 6, &[5, 33])).unwrap();
+        assert_eq!(parsed.pressed_buttons, vec![5, 33]);
+        assert_eq!(parsed.axis_values.get(&0x30), Some(&0x1234));
+        assert_eq!(parsed.hats[0].directions(), &["left"]);
+        assert!(parsed.is_16bit);
+    }
+
+    // Timing, not a check: wall-clock numbers vary too much between machines to assert on
+    // cargo test --release report_parser_timing -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn report_parser_timing() {
+        const REPORTS: usize = 2000;
+        let report = stick_report(0x8000, 8, &[3, 17]);
+
+        let start = Instant::now();
+        for _ in 0..REPORTS {
+            parse_hid_full_report(&report, STICK_DESCRIPTOR).unwrap();
+        }
+        let one_shot = start.elapsed();
+
+        let start = Instant::now();
+        let parser = ReportParser::new(STICK_DESCRIPTOR).unwrap();
+        for _ in 0..REPORTS {
+            parser.parse(&report).unwrap();
+        }
+        let compiled = start.elapsed();
+
+        println!(
+            "{} reports: parse_hid_full_report {:?}, ReportParser {:?}",
+            REPORTS, one_shot, compiled
+        );
+        println!(
+            "per report: {:?} vs {:?} ({:.1}x)",
+            one_shot / REPORTS as u32,
+            compiled / REPORTS as u32,
+            one_shot.as_secs_f64() / compiled.as_secs_f64()
+        );
+    }
+}
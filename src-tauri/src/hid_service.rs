@@ -0,0 +1,216 @@
+use crate::hid_reader::{self, HidDeviceListItem, OpenedHidDevice, ReportParser};
+use hidapi::HidApi;
+use log::{info, warn};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::Emitter;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A device opened by the service; hidapi handles can't be used from two threads at once
+pub type SharedDevice = Arc<Mutex<OpenedHidDevice>>;
+
+#[derive(Default)]
+struct ServiceState {
+    api: Option<HidApi>,
+    /// Game controllers as of the last refresh
+    devices: Vec<HidDeviceListItem>,
+    opened: HashMap<String, SharedDevice>,
+    descriptors: HashMap<String, Vec<u8>>,
+    parsers: HashMap<String, Arc<ReportParser>>,
+}
+
+impl ServiceState {
+    fn api(&mut self) -> Result<&mut HidApi, String> {
+        if self.api.is_none() {
+            let api = HidApi::new().map_err(|e| format!("Failed to initialize HID API: {}", e))?;
+            self.api = Some(api);
+        }
+        Ok(self.api.as_mut().expect("HID API initialized above"))
+    }
+
+    /// Drop everything held for a device, e.g. after it was unplugged
+    fn forget(&mut self, device_path: &str) {
+        self.opened.remove(device_path);
+        self.descriptors.remove(device_path);
+        self.parsers.remove(device_path);
+    }
+}
+
+/// The app's single HID context: one API handle, each device opened once and shared,
+/// descriptors and compiled parsers cached per device
+///
+/// Cloning gives another handle to the same service.
+#[derive(Clone, Default)]
+pub struct HidService {
+    state: Arc<Mutex<ServiceState>>,
+}
+
+impl HidService {
+    /// Refresh the device list in the background, emitting "device-connected" and
+    /// "device-disconnected" with the device as payload
+    ///
+    /// Devices attached before the first pass are the starting list, not hotplug events.
+    pub fn start(&self, app_handle: tauri::AppHandle) {
+        let service = self.clone();
+        thread::spawn(move || loop {
+            let seeded = service.state.lock().unwrap().api.is_some();
+            if !seeded {
+                match service.refresh() {
+                    Ok((attached, _)) => {
+                        info!("HID: {} game controller(s) attached", attached.len())
+                    }
+                    Err(e) => warn!("Failed to refresh HID devices: {}", e),
+                }
+                thread::sleep(REFRESH_INTERVAL);
+                continue;
+            }
+
+            match service.refresh() {
+                Ok((connected, disconnected)) => {
+                    for (event, devices) in [
+                        ("device-connected", connected),
+                        ("device-disconnected", disconnected),
+                    ] {
+                        for device in devices {
+                            info!(
+                                "HID {}: {} ({:04x}:{:04x})",
+                                event,
+                                device.product.as_deref().unwrap_or("Unknown Device"),
+                                device.vendor_id,
+                                device.product_id
+                            );
+                            if let Err(e) = app_handle.emit(event, &device) {
+                                warn!("Failed to emit {} event: {}", event, e);
+                            }
+                        }
+                    }
+                }
+                Err(e) => warn!("Failed to refresh HID devices: {}", e),
+            }
+            thread::sleep(REFRESH_INTERVAL);
+        });
+    }
+
+    /// Re-enumerate devices; returns the ones that appeared and the ones that went away
+    pub fn refresh(&self) -> Result<(Vec<HidDeviceListItem>, Vec<HidDeviceListItem>), String> {
+        let mut state = self.state.lock().unwrap();
+        let api = state.api()?;
+        api.refresh_devices()
+            .map_err(|e| format!("Failed to enumerate HID devices: {}", e))?;
+        let current = hid_reader::game_controllers(api);
+
+        let connected: Vec<HidDeviceListItem> = current
+            .iter()
+            .filter(|d| !state.devices.iter().any(|p| p.path == d.path))
+            .cloned()
+            .collect();
+        let disconnected: Vec<HidDeviceListItem> = state
+            .devices
+            .iter()
+            .filter(|p| !current.iter().any(|d| d.path == p.path))
+            .cloned()
+            .collect();
+
+        for device in &disconnected {
+            state.forget(&device.path);
+        }
+        state.devices = current;
+        Ok((connected, disconnected))
+    }
+
+    /// Game controllers as of the last refresh
+    pub fn devices(&self) -> Result<Vec<HidDeviceListItem>, String> {
+        if self.state.lock().unwrap().api.is_none() {
+            self.refresh()?;
+        }
+        Ok(self.state.lock().unwrap().devices.clone())
+    }
+
+    /// The shared handle for a device, opening it on first use
+    pub fn open(&self, device_path: &str) -> Result<SharedDevice, String> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(device) = state.opened.get(device_path) {
+            return Ok(device.clone());
+        }
+        let device = Arc::new(Mutex::new(OpenedHidDevice::open(
+            state.api()?,
+            device_path,
+        )?));
+        state.opened.insert(device_path.to_string(), device.clone());
+        Ok(device)
+    }
+
+    /// The device's raw report descriptor, read once
+    pub fn descriptor(&self, device_path: &str) -> Result<Vec<u8>, String> {
+        if let Some(descriptor) = self.state.lock().unwrap().descriptors.get(device_path) {
+            return Ok(descriptor.clone());
+        }
+        let descriptor = self
+            .open(device_path)?
+            .lock()
+            .unwrap()
+            .report_descriptor()?;
+        self.state
+            .lock()
+            .unwrap()
+            .descriptors
+            .insert(device_path.to_string(), descriptor.clone());
+        Ok(descriptor)
+    }
+
+    /// The device's compiled report parser, built once
+    pub fn parser(&self, device_path: &str) -> Result<Arc<ReportParser>, String> {
+        if let Some(parser) = self.state.lock().unwrap().parsers.get(device_path) {
+            return Ok(parser.clone());
+        }
+        let parser = Arc::new(ReportParser::new(&self.descriptor(device_path)?)?);
+        self.state
+            .lock()
+            .unwrap()
+            .parsers
+            .insert(device_path.to_string(), parser.clone());
+        Ok(parser)
+    }
+
+    /// The newest report from a device: whatever is queued is skipped, and if nothing is
+    /// queued this waits up to `timeout_ms` for the next one
+    pub fn read_report(&self, device_path: &str, timeout_ms: i32) -> Result<Vec<u8>, String> {
+        let device = self.open(device_path)?;
+        let result = {
+            let device = device.lock().unwrap();
+            let mut latest = Vec::new();
+            loop {
+                match device.read(0) {
+                    Ok(bytes) if !bytes.is_empty() => latest = bytes,
+                    Ok(_) => break,
+                    Err(e) => return Err(self.read_failed(device_path, e)),
+                }
+            }
+            if latest.is_empty() {
+                device.read(timeout_ms)
+            } else {
+                Ok(latest)
+            }
+        };
+
+        match result {
+            Ok(report) => {
+                if !report.is_empty() {
+                    eprintln!("[HID] Read {} bytes from device", report.len());
+                    eprintln!("[HID] Raw report: {:?}", report);
+                }
+                Ok(report)
+            }
+            Err(e) => Err(self.read_failed(device_path, e)),
+        }
+    }
+
+    /// A failed read usually means the device is gone; reopen it next time
+    fn read_failed(&self, device_path: &str, error: String) -> String {
+        self.state.lock().unwrap().forget(device_path);
+        error
+    }
+}
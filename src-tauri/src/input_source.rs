@@ -0,0 +1,454 @@
+#[cfg(target_os = "linux")]
+use crate::evdev_input::{self, EvdevJoystick};
+use crate::hid_reader::{self, HidDeviceListItem, HidFullReport, ReportParser};
+use crate::hid_service::{HidService, SharedDevice};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often detection polls its sources
+pub const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Who a source's samples come from, as detection reports it
+#[derive(Debug, Clone)]
+pub struct SourceInfo {
+    /// The N in jsN / gpN
+    pub instance: usize,
+    pub name: String,
+    /// Persistent device identifier, e.g. "231d:0200" or "xinput_0"
+    pub uuid: String,
+    /// HID usage ID -> DirectInput axis index, from the descriptor (joystick sources)
+    pub axis_indices: HashMap<u32, u32>,
+}
+
+/// State of an XInput gamepad
+#[derive(Debug, Clone, Copy)]
+pub struct GamepadState {
+    /// XInput button mask, see [`crate::directinput::XINPUT_BUTTONS`]
+    pub buttons: u16,
+    /// gpN_axisN number, position in -1.0..=1.0 and name of each axis
+    pub axes: [(u32, f32, &'static str); 6],
+}
+
+#[cfg(windows)]
+impl From<&rusty_xinput::XInputState> for GamepadState {
+    fn from(state: &rusty_xinput::XInputState) -> Self {
+        let pad = &state.raw.Gamepad;
+        GamepadState {
+            buttons: pad.wButtons,
+            axes: [
+                (1, (pad.sThumbLX as f32) / 32768.0, "Left Stick X"),
+                (2, (pad.sThumbLY as f32) / 32768.0, "Left Stick Y"),
+                (3, (pad.sThumbRX as f32) / 32768.0, "Right Stick X"),
+                (4, (pad.sThumbRY as f32) / 32768.0, "Right Stick Y"),
+                (
+                    5,
+                    (pad.bLeftTrigger as f32) / 255.0 * 2.0 - 1.0,
+                    "Left Trigger",
+                ),
+                (
+                    6,
+                    (pad.bRightTrigger as f32) / 255.0 * 2.0 - 1.0,
+                    "Right Trigger",
+                ),
+            ],
+        }
+    }
+}
+
+/// The XInput API; it only exists on Windows, elsewhere it never loads
+#[derive(Clone)]
+pub struct XInput {
+    #[cfg(windows)]
+    handle: rusty_xinput::XInputHandle,
+}
+
+impl XInput {
+    #[cfg(windows)]
+    pub fn load() -> Result<Self, String> {
+        rusty_xinput::XInputHandle::load_default()
+            .map(|handle| XInput { handle })
+            .map_err(|e| format!("Failed to load XInput: {:?}", e))
+    }
+
+    #[cfg(not(windows))]
+    pub fn load() -> Result<Self, String> {
+        Err("XInput is only available on Windows".to_string())
+    }
+
+    /// State of the controller in `slot` (0-3), if one is connected
+    #[cfg(windows)]
+    pub fn state(&self, slot: u32) -> Option<GamepadState> {
+        self.handle
+            .get_state(slot)
+            .ok()
+            .map(|state| GamepadState::from(&state))
+    }
+
+    #[cfg(not(windows))]
+    pub fn state(&self, _slot: u32) -> Option<GamepadState> {
+        None
+    }
+}
+
+/// One reading from a source
+pub enum InputSample {
+    /// A parsed report from a joystick (jsN)
+    Joystick(HidFullReport),
+    /// A gamepad's current state (gpN)
+    Gamepad(GamepadState),
+}
+
+/// Something input detection reads controls from: a live device or a recording
+///
+/// Sources only deliver readings; baselines, thresholds and hat decoding stay in the
+/// detector, so a replayed recording goes through the same logic as a live device.
+pub trait InputSource {
+    fn info(&self) -> &SourceInfo;
+
+    /// Readings since the last poll, oldest first
+    fn poll(&mut self) -> Vec<InputSample>;
+
+    /// True once a finite source (a recording) has nothing left to deliver
+    fn is_exhausted(&self) -> bool {
+        false
+    }
+}
+
+fn hid_source_info(
+    device: &HidDeviceListItem,
+    instance: usize,
+    descriptor: Option<&[u8]>,
+) -> SourceInfo {
+    // Invert the DirectInput-to-HID mapping (HID usage ID -> DirectInput index)
+    let axis_indices = descriptor
+        .and_then(|d| hid_reader::get_directinput_to_hid_axis_mapping(d).ok())
+        .map(|di_to_hid| {
+            di_to_hid
+                .into_iter()
+                .map(|(axis_idx, hid_usage_id)| (hid_usage_id, axis_idx))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    SourceInfo {
+        instance,
+        name: device
+            .product
+            .clone()
+            .unwrap_or_else(|| "Unknown Device".to_string()),
+        uuid: format!("{:04x}:{:04x}", device.vendor_id, device.product_id),
+        axis_indices,
+    }
+}
+
+/// A live HID joystick, read through the [`HidService`]'s shared handle
+pub struct HidSource {
+    info: SourceInfo,
+    device: SharedDevice,
+    parser: Arc<ReportParser>,
+}
+
+impl HidSource {
+    pub fn open(
+        hid: &HidService,
+        device: &HidDeviceListItem,
+        instance: usize,
+    ) -> Result<Self, String> {
+        // The service keeps devices open between detections
+        let opened = hid.open(&device.path)?;
+        // Skip reports queued since the last detection; they'd look like fresh presses
+        {
+            let opened = opened.lock().unwrap();
+            for _ in 0..256 {
+                if !matches!(opened.read(0), Ok(bytes) if !bytes.is_empty()) {
+                    break;
+                }
+            }
+        }
+        let parser = hid.parser(&device.path)?;
+        let descriptor = hid.descriptor(&device.path).ok();
+
+        Ok(HidSource {
+            info: hid_source_info(device, instance, descriptor.as_deref()),
+            device: opened,
+            parser,
+        })
+    }
+}
+
+impl InputSource for HidSource {
+    fn info(&self) -> &SourceInfo {
+        &self.info
+    }
+
+    /// Drains the HID queue, up to 10 reports per poll to avoid blocking
+    fn poll(&mut self) -> Vec<InputSample> {
+        let mut samples = Vec::new();
+        for _ in 0..10 {
+            // Use 0 timeout for non-blocking read
+            let report_bytes = match self.device.lock().unwrap().read(0) {
+                Ok(bytes) if !bytes.is_empty() => bytes,
+                _ => break, // No more reports or error
+            };
+            if let Ok(report) = self.parser.parse(&report_bytes) {
+                samples.push(InputSample::Joystick(report));
+            }
+        }
+        samples
+    }
+}
+
+/// A live XInput controller slot; delivers nothing while the slot is empty
+pub struct XInputSource {
+    info: SourceInfo,
+    xinput: XInput,
+    slot: u32,
+}
+
+impl XInputSource {
+    pub fn new(xinput: XInput, slot: u32) -> Self {
+        XInputSource {
+            info: SourceInfo {
+                instance: slot as usize + 1,
+                name: format!("Xbox Controller (XInput {})", slot),
+                uuid: crate::directinput::resolve_xinput_uuid(slot),
+                axis_indices: HashMap::new(),
+            },
+            xinput,
+            slot,
+        }
+    }
+}
+
+impl InputSource for XInputSource {
+    fn info(&self) -> &SourceInfo {
+        &self.info
+    }
+
+    fn poll(&mut self) -> Vec<InputSample> {
+        self.xinput
+            .state(self.slot)
+            .map(InputSample::Gamepad)
+            .into_iter()
+            .collect()
+    }
+}
+
+/// A joystick read through its evdev node, for when hidraw isn't accessible
+#[cfg(target_os = "linux")]
+pub struct EvdevSource {
+    info: SourceInfo,
+    joystick: EvdevJoystick,
+}
+
+#[cfg(target_os = "linux")]
+impl InputSource for EvdevSource {
+    fn info(&self) -> &SourceInfo {
+        &self.info
+    }
+
+    fn poll(&mut self) -> Vec<InputSample> {
+        self.joystick
+            .read_report()
+            .map(InputSample::Joystick)
+            .into_iter()
+            .collect()
+    }
+}
+
+/// Every live controller: HID joysticks in device list order (js1, js2, ...), then the four
+/// XInput slots (gp1-gp4)
+///
+/// On Linux, a joystick hidraw can't open (no udev rule) is read through its evdev node
+/// instead, and evdev-only joysticks (e.g. uinput virtual devices) are numbered after the
+/// HID ones.
+pub fn live_sources(hid: &HidService) -> Vec<Box<dyn InputSource>> {
+    let devices = hid.devices().unwrap_or_default();
+    let mut sources: Vec<Box<dyn InputSource>> = Vec::new();
+    #[cfg(target_os = "linux")]
+    let mut evdev_joysticks = evdev_input::joysticks();
+
+    for (idx, device) in devices.iter().enumerate() {
+        #[cfg(target_os = "linux")]
+        let evdev_joystick = evdev_joysticks
+            .iter()
+            .position(|j| j.vendor_id == device.vendor_id && j.product_id == device.product_id)
+            .map(|pos| evdev_joysticks.remove(pos));
+
+        match HidSource::open(hid, device, idx + 1) {
+            Ok(source) => sources.push(Box::new(source)),
+            Err(e) => {
+                eprintln!(
+                    "InputDetector: Failed to open device {}: {}",
+                    device.path, e
+                );
+                #[cfg(target_os = "linux")]
+                if let Some(joystick) = evdev_joystick {
+                    eprintln!(
+                        "InputDetector: Reading {} through {}",
+                        device.path, joystick.path
+                    );
+                    sources.push(Box::new(EvdevSource {
+                        info: hid_source_info(device, idx + 1, None),
+                        joystick,
+                    }));
+                }
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    for (offset, joystick) in evdev_joysticks.into_iter().enumerate() {
+        eprintln!(
+            "InputDetector: evdev-only joystick {} ({})",
+            joystick.name, joystick.path
+        );
+        sources.push(Box::new(EvdevSource {
+            info: SourceInfo {
+                instance: devices.len() + offset + 1,
+                name: joystick.name.clone(),
+                uuid: format!("{:04x}:{:04x}", joystick.vendor_id, joystick.product_id),
+                axis_indices: HashMap::new(),
+            },
+            joystick,
+        }));
+    }
+
+    match XInput::load() {
+        Ok(xinput) => {
+            for slot in 0..4 {
+                sources.push(Box::new(XInputSource::new(xinput.clone(), slot)));
+            }
+        }
+        Err(e) => eprintln!("InputDetector: {}", e),
+    }
+
+    sources
+}
+
+/// A device's report descriptor and the raw reports it sent, as saved to a recording file
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Recording {
+    pub name: String,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub descriptor: Vec<u8>,
+    pub reports: Vec<RecordedReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedReport {
+    /// Milliseconds since the recording started
+    pub time_ms: u64,
+    pub data: Vec<u8>,
+}
+
+impl Recording {
+    /// Record a HID device's reports for `duration`
+    pub fn capture(
+        hid: &HidService,
+        device: &HidDeviceListItem,
+        duration: Duration,
+    ) -> Result<Self, String> {
+        let descriptor = hid.descriptor(&device.path)?;
+        let opened = hid.open(&device.path)?;
+
+        let start = Instant::now();
+        let mut reports = Vec::new();
+        while start.elapsed() < duration {
+            let data = opened.lock().unwrap().read(10)?;
+            if !data.is_empty() {
+                reports.push(RecordedReport {
+                    time_ms: start.elapsed().as_millis() as u64,
+                    data,
+                });
+            }
+        }
+
+        Ok(Recording {
+            name: device
+                .product
+                .clone()
+                .unwrap_or_else(|| "Unknown Device".to_string()),
+            vendor_id: device.vendor_id,
+            product_id: device.product_id,
+            descriptor,
+            reports,
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read recording {}: {}", path.display(), e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid recording: {}", e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string(self)
+            .map_err(|e| format!("Failed to serialize recording: {}", e))?;
+        fs::write(path, json)
+            .map_err(|e| format!("Failed to write recording {}: {}", path.display(), e))
+    }
+}
+
+/// Plays a [`Recording`] back as a joystick
+///
+/// Time is virtual: each poll advances it by one [`POLL_INTERVAL`] and delivers the reports
+/// recorded up to then, so detection sees the same batches on every run and on any machine.
+pub struct ReplaySource {
+    info: SourceInfo,
+    parser: ReportParser,
+    reports: VecDeque<RecordedReport>,
+    clock_ms: u64,
+}
+
+impl ReplaySource {
+    pub fn new(recording: Recording, instance: usize) -> Result<Self, String> {
+        let device = HidDeviceListItem {
+            vendor_id: recording.vendor_id,
+            product_id: recording.product_id,
+            serial_number: None,
+            manufacturer: None,
+            product: Some(recording.name),
+            path: String::new(),
+            interface_number: -1,
+        };
+
+        Ok(ReplaySource {
+            info: hid_source_info(&device, instance, Some(&recording.descriptor)),
+            parser: ReportParser::new(&recording.descriptor)?,
+            reports: recording.reports.into(),
+            clock_ms: 0,
+        })
+    }
+}
+
+impl InputSource for ReplaySource {
+    fn info(&self) -> &SourceInfo {
+        &self.info
+    }
+
+    fn poll(&mut self) -> Vec<InputSample> {
+        let mut samples = Vec::new();
+        while self
+            .reports
+            .front()
+            .is_some_and(|report| report.time_ms <= self.clock_ms)
+        {
+            let report = self.reports.pop_front().unwrap();
+            if let Ok(parsed) = self.parser.parse(&report.data) {
+                samples.push(InputSample::Joystick(parsed));
+            }
+        }
+        self.clock_ms += POLL_INTERVAL.as_millis() as u64;
+        samples
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.reports.is_empty()
+    }
+}
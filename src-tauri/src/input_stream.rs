@@ -0,0 +1,239 @@
+use crate::directinput::{self, XINPUT_BUTTONS};
+use crate::hid_reader::HidFullReport;
+use crate::hid_service::HidService;
+use crate::input_source::XInput;
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::Emitter;
+
+pub const DEFAULT_RATE_HZ: u32 = 30;
+const MAX_RATE_HZ: u32 = 120;
+
+/// Live state of one device, using SC input names
+#[derive(Debug, Serialize, Clone)]
+pub struct DeviceInputState {
+    /// SC prefix, e.g. "js1" or "gp1"
+    pub prefix: String,
+    pub device_name: String,
+    pub device_uuid: String,
+    /// Buttons and hat directions held right now, e.g. "js1_button3", "js1_hat1_up"
+    pub active: Vec<String>,
+    /// Axis input -> position in -1.0..=1.0, e.g. "js2_x"
+    pub axes: BTreeMap<String, f32>,
+}
+
+/// Payload of the "input-state" event
+#[derive(Debug, Serialize, Clone)]
+pub struct InputStateEvent {
+    pub devices: Vec<DeviceInputState>,
+}
+
+/// SC name of a Generic Desktop axis usage
+fn sc_axis_name(usage_id: u32) -> Option<&'static str> {
+    match usage_id {
+        0x30 => Some("x"),
+        0x31 => Some("y"),
+        0x32 => Some("z"),
+        0x33 => Some("rotx"),
+        0x34 => Some("roty"),
+        0x35 => Some("rotz"),
+        0x36 => Some("slider1"),
+        0x37 => Some("slider2"),
+        _ => None,
+    }
+}
+
+/// Combine the latest report of each report ID into one device state
+fn hid_device_state<'a>(
+    prefix: String,
+    device_name: String,
+    device_uuid: String,
+    reports: impl Iterator<Item = &'a HidFullReport>,
+) -> DeviceInputState {
+    let mut active = Vec::new();
+    let mut axes = BTreeMap::new();
+
+    for report in reports {
+        active.extend(
+            report
+                .pressed_buttons
+                .iter()
+                .map(|b| format!("{}_button{}", prefix, b)),
+        );
+        for hat in &report.hats {
+            active.extend(
+                hat.directions()
+                    .iter()
+                    .map(|d| format!("{}_hat{}_{}", prefix, hat.hat, d)),
+            );
+        }
+        for (&usage_id, &value) in &report.axis_values {
+            let Some(name) = sc_axis_name(usage_id) else {
+                continue;
+            };
+            let (min, max) = report
+                .axis_ranges
+                .get(&usage_id)
+                .copied()
+                .unwrap_or((0, 65535));
+            if max <= min {
+                continue;
+            }
+            let normalized = (value as i32 - min) as f32 / (max - min) as f32 * 2.0 - 1.0;
+            axes.insert(format!("{}_{}", prefix, name), normalized.clamp(-1.0, 1.0));
+        }
+    }
+
+    active.sort();
+    active.dedup();
+    DeviceInputState {
+        prefix,
+        device_name,
+        device_uuid,
+        active,
+        axes,
+    }
+}
+
+fn xinput_states(xinput: &XInput) -> Vec<DeviceInputState> {
+    (0..4)
+        .filter_map(|controller_id| {
+            let state = xinput.state(controller_id)?;
+            let prefix = format!("gp{}", controller_id + 1);
+            Some(DeviceInputState {
+                active: XINPUT_BUTTONS
+                    .iter()
+                    .filter(|(mask, _)| state.buttons & mask != 0)
+                    .map(|(_, btn)| format!("{}_button{}", prefix, btn))
+                    .collect(),
+                axes: state
+                    .axes
+                    .iter()
+                    .map(|(index, value, _)| (format!("{}_axis{}", prefix, index), *value))
+                    .collect(),
+                device_name: format!("Xbox Controller (XInput {})", controller_id),
+                device_uuid: directinput::resolve_xinput_uuid(controller_id),
+                prefix,
+            })
+        })
+        .collect()
+}
+
+/// Background poller that emits "input-state" with every device's live state
+///
+/// It reads the HID service's shared device handles, so reports it consumes aren't seen
+/// by input detection. Detection holds a [`StreamPause`] while it runs; the stream leaves
+/// the devices alone until every pause is dropped.
+#[derive(Default)]
+pub struct InputStream {
+    stop: Option<Arc<AtomicBool>>,
+    /// Live [`StreamPause`] guards
+    pauses: Arc<AtomicUsize>,
+}
+
+/// Keeps the input stream off the devices while held
+pub struct StreamPause(Arc<AtomicUsize>);
+
+impl Drop for StreamPause {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl InputStream {
+    /// Pause reading devices until the returned guard is dropped
+    pub fn pause(&self) -> StreamPause {
+        self.pauses.fetch_add(1, Ordering::Relaxed);
+        StreamPause(self.pauses.clone())
+    }
+
+    /// Start streaming (replacing any previous stream) at `rate_hz` events per second
+    pub fn start(&mut self, app_handle: tauri::AppHandle, hid: HidService, rate_hz: u32) {
+        self.stop();
+
+        let rate_hz = rate_hz.clamp(1, MAX_RATE_HZ);
+        info!("Starting input stream at {} Hz", rate_hz);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        self.stop = Some(stop.clone());
+        let pauses = self.pauses.clone();
+
+        thread::spawn(move || {
+            let interval = Duration::from_secs_f64(1.0 / rate_hz as f64);
+            let xinput = XInput::load().ok();
+            // Latest report per (device path, report ID)
+            let mut reports: HashMap<(String, u8), HidFullReport> = HashMap::new();
+
+            while !stop.load(Ordering::Relaxed) {
+                if pauses.load(Ordering::Relaxed) > 0 {
+                    // Detection is reading the devices; stale reports would show held inputs
+                    reports.clear();
+                    thread::sleep(interval);
+                    continue;
+                }
+
+                let devices = hid.devices().unwrap_or_default();
+                reports.retain(|(path, _), _| devices.iter().any(|d| &d.path == path));
+
+                let mut states = Vec::new();
+                // Same jsN numbering as input detection
+                for (idx, device) in devices.iter().enumerate() {
+                    let (Ok(opened), Ok(parser)) =
+                        (hid.open(&device.path), hid.parser(&device.path))
+                    else {
+                        continue;
+                    };
+                    {
+                        let opened = opened.lock().unwrap();
+                        // Everything queued since the last tick, bounded for chatty devices
+                        for _ in 0..64 {
+                            let bytes = match opened.read(0) {
+                                Ok(bytes) if !bytes.is_empty() => bytes,
+                                _ => break,
+                            };
+                            if let Ok(report) = parser.parse(&bytes) {
+                                reports.insert((device.path.clone(), report.report_id), report);
+                            }
+                        }
+                    }
+
+                    states.push(hid_device_state(
+                        format!("js{}", idx + 1),
+                        device
+                            .product
+                            .clone()
+                            .unwrap_or_else(|| "Unknown Device".to_string()),
+                        format!("{:04x}:{:04x}", device.vendor_id, device.product_id),
+                        reports
+                            .iter()
+                            .filter(|((path, _), _)| path == &device.path)
+                            .map(|(_, report)| report),
+                    ));
+                }
+
+                if let Some(xinput) = &xinput {
+                    states.extend(xinput_states(xinput));
+                }
+
+                if let Err(e) = app_handle.emit("input-state", InputStateEvent { devices: states })
+                {
+                    warn!("Failed to emit input state: {}", e);
+                }
+                thread::sleep(interval);
+            }
+
+            info!("Input stream stopped");
+        });
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
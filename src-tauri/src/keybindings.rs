@@ -1,3 +1,5 @@
+use crate::error::XmlError;
+use crate::sc_keys;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
@@ -8,6 +10,9 @@ pub struct ActionMaps {
     pub profile_name: String,
     pub action_maps: Vec<ActionMap>,
     pub categories: Vec<Category>,
+    /// Inputs declared as modifiers in `<modifiers>`, e.g. "js1_button5"
+    #[serde(default)]
+    pub modifiers: Vec<String>,
     pub devices: DeviceInfo,
 }
 
@@ -273,6 +278,20 @@ impl Rebind {
     fn format_binding(binding: &str) -> String {
         let clean = binding.trim();
 
+        // Chords: "lshift+a" -> "Left Shift + A", "button5+js1_button3" -> "Button 5 + Button 3"
+        if clean.contains('+') {
+            return clean
+                .split('+')
+                .map(|part| Self::format_binding(strip_device_prefix(part.trim())))
+                .collect::<Vec<_>>()
+                .join(" + ");
+        }
+
+        // Keyboard keys and mouse inputs: "np_1" -> "Numpad 1", "mwheel_up" -> "Wheel Up"
+        if let Some(label) = sc_keys::label(clean) {
+            return label.to_string();
+        }
+
         // Handle button inputs: "button3" -> "Button 3"
         if clean.starts_with("button") {
             if let Some(num) = clean.strip_prefix("button") {
@@ -297,6 +316,23 @@ impl Rebind {
     }
 }
 
+/// "js1_button3" -> "button3"; inputs without a device prefix are returned as is
+fn strip_device_prefix(input: &str) -> &str {
+    let Some((device, rest)) = input.split_once('_') else {
+        return input;
+    };
+    let is_device = ["kb", "js", "gp", "mouse"].iter().any(|prefix| {
+        device
+            .strip_prefix(prefix)
+            .is_some_and(|instance| instance.chars().all(|c| c.is_ascii_digit()))
+    });
+    if is_device {
+        rest
+    } else {
+        input
+    }
+}
+
 /// Helper struct for organizing keybindings by category for the UI
 #[derive(Debug, Serialize, Clone)]
 pub struct OrganizedKeybindings {
@@ -355,10 +391,11 @@ impl ActionMaps {
     }
 
     /// Parse XML file into ActionMaps structure using event-based parser
-    pub fn from_xml(xml: &str) -> Result<Self, String> {
+    pub fn from_xml(xml: &str) -> Result<Self, XmlError> {
         let mut profile_name = String::new();
         let mut action_maps = Vec::new();
         let mut categories = Vec::new();
+        let mut modifiers = Vec::new();
         let mut devices = DeviceInfo {
             keyboards: Vec::new(),
             mice: Vec::new(),
@@ -528,6 +565,18 @@ impl ActionMaps {
                                 control_options: Vec::new(),
                             });
                         }
+                        b"mod" => {
+                            // Declared modifier: <modifiers><mod input="js1_button5"/></modifiers>
+                            for attr in e.attributes().flatten() {
+                                if attr.key.as_ref() == b"input" {
+                                    let input =
+                                        String::from_utf8(attr.value.to_vec()).unwrap_or_default();
+                                    if !input.is_empty() && !modifiers.contains(&input) {
+                                        modifiers.push(input);
+                                    }
+                                }
+                            }
+                        }
                         b"rebind" => {
                             let mut input = String::new();
                             let mut multi_tap: Option<u32> = None;
@@ -613,7 +662,7 @@ impl ActionMaps {
                 },
                 Ok(quick_xml::events::Event::Eof) => break,
                 Err(e) => {
-                    return Err(format!("XML parsing error: {}", e));
+                    return Err(XmlError::at(xml, reader.error_position(), e));
                 }
                 _ => {}
             }
@@ -624,6 +673,7 @@ impl ActionMaps {
             profile_name,
             action_maps,
             categories,
+            modifiers,
             devices,
         })
     }
@@ -807,7 +857,15 @@ impl ActionMaps {
             }
         }
 
-        xml.push_str(" <modifiers />\n");
+        if self.modifiers.is_empty() {
+            xml.push_str(" <modifiers />\n");
+        } else {
+            xml.push_str(" <modifiers>\n");
+            for modifier in &self.modifiers {
+                xml.push_str(&format!("  <mod input=\"{}\"/>\n", modifier));
+            }
+            xml.push_str(" </modifiers>\n");
+        }
 
         // Sort actionmaps according to AllBinds.xml order
         let mut sorted_actionmaps_with_bindings: Vec<_> = actionmaps_with_bindings
@@ -849,16 +907,19 @@ impl ActionMaps {
 
                         // Deduplicate rebinds by device TYPE to ensure we only write one per type
                         // Star Citizen only accepts one rebind per device TYPE (Joystick, Keyboard, etc.)
-                        // Keep the LAST rebind for each device type (most recent binding)
-                        let mut type_to_rebind: std::collections::HashMap<String, &Rebind> =
-                            std::collections::HashMap::new();
+                        // Keep the LAST rebind for each device type (most recent binding),
+                        // in the order the types first appear so the output is stable
+                        let mut type_to_rebind: Vec<(InputType, &Rebind)> = Vec::new();
                         for rebind in &action.rebinds {
-                            let device_type = format!("{:?}", rebind.get_device_type());
-                            type_to_rebind.insert(device_type, rebind);
+                            let device_type = rebind.get_device_type();
+                            match type_to_rebind.iter_mut().find(|(t, _)| *t == device_type) {
+                                Some(entry) => entry.1 = rebind,
+                                None => type_to_rebind.push((device_type, rebind)),
+                            }
                         }
 
                         // Write deduplicated rebinds
-                        for rebind in type_to_rebind.values() {
+                        for (_, rebind) in &type_to_rebind {
                             xml.push_str("   <rebind input=\"");
                             xml.push_str(&rebind.input);
                             xml.push_str("\"");
@@ -1081,7 +1142,7 @@ fn capitalize_word(word: &str) -> String {
 
 impl AllBinds {
     /// Parse AllBinds.xml file into AllBinds structure
-    pub fn from_xml(xml: &str) -> Result<Self, String> {
+    pub fn from_xml(xml: &str) -> Result<Self, XmlError> {
         let mut action_maps = Vec::new();
         let mut reader = quick_xml::Reader::from_str(xml);
         let mut buf = vec![];
@@ -1221,7 +1282,7 @@ impl AllBinds {
                 }
                 Ok(quick_xml::events::Event::Eof) => break,
                 Err(e) => {
-                    return Err(format!("XML parsing error: {}", e));
+                    return Err(XmlError::at(xml, reader.error_position(), e));
                 }
                 _ => {}
             }
@@ -1735,281 +1796,461 @@ impl AllBinds {
     }
 }
 
+// Star Citizen supports up to 8 instances per device type (AllBinds declares instances="8")
+pub const MAX_DEVICE_INSTANCES: u8 = 8;
+
 // Device selection struct for unbind profile generation
-#[derive(serde::Deserialize)]
+// `joysticks` / `gamepads` list instance numbers; the older joystick1/joystick2/gamepad
+// flags are still accepted and merged in
+#[derive(serde::Deserialize, Default)]
 pub struct DeviceSelection {
+    #[serde(default)]
     pub keyboard: bool,
+    #[serde(default)]
     pub mouse: bool,
+    #[serde(default)]
     pub gamepad: bool,
+    #[serde(default)]
     pub joystick1: bool,
+    #[serde(default)]
     pub joystick2: bool,
+    #[serde(default)]
+    pub joysticks: Vec<u8>,
+    #[serde(default)]
+    pub gamepads: Vec<u8>,
 }
 
-/// Generate an unbind profile XML that clears all bindings for selected devices
-pub fn generate_unbind_xml(
-    all_binds: &AllBinds,
-    devices: &DeviceSelection,
-) -> Result<String, String> {
-    use std::collections::HashSet;
-
-    let mut xml = String::new();
-
-    // XML declaration
-    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
-
-    // Root ActionMaps element
-    xml.push_str("<ActionMaps version=\"1\" optionsVersion=\"2\" rebindVersion=\"2\" profileName=\"UNBIND_ALL_DEVICES\">\n");
-
-    // Collect all unique categories in order
-    let mut categories_ordered = Vec::new();
-    let mut seen_categories = HashSet::new();
-    for action_map in &all_binds.action_maps {
-        if !action_map.ui_category.is_empty() && !seen_categories.contains(&action_map.ui_category)
-        {
-            categories_ordered.push(action_map.ui_category.clone());
-            seen_categories.insert(action_map.ui_category.clone());
+impl DeviceSelection {
+    /// Selected joystick instances, sorted and de-duplicated
+    pub fn joystick_instances(&self) -> Result<Vec<u8>, String> {
+        let mut instances = self.joysticks.clone();
+        if self.joystick1 {
+            instances.push(1);
+        }
+        if self.joystick2 {
+            instances.push(2);
         }
+        normalize_instances(instances, "joystick")
     }
 
-    // Write CustomisationUIHeader with proper attributes
-    xml.push_str(" <CustomisationUIHeader label=\"UNBIND_ALL_DEVICES\" description=\"Clears all bindings for selected devices\" image=\"\">\n");
-
-    // Write devices section - order matters!
-    xml.push_str("  <devices>\n");
-    if devices.keyboard {
-        xml.push_str("   <keyboard instance=\"1\"/>\n");
-    }
-    if devices.mouse {
-        xml.push_str("   <mouse instance=\"1\"/>\n");
-    }
-    if devices.joystick1 {
-        xml.push_str("   <joystick instance=\"1\"/>\n");
+    /// Selected gamepad instances, sorted and de-duplicated
+    pub fn gamepad_instances(&self) -> Result<Vec<u8>, String> {
+        let mut instances = self.gamepads.clone();
+        if self.gamepad {
+            instances.push(1);
+        }
+        normalize_instances(instances, "gamepad")
     }
-    if devices.joystick2 {
-        xml.push_str("   <joystick instance=\"2\"/>\n");
+
+    /// Every selected device as (type, input prefix), in the order SC expects them
+    fn slots(&self) -> Result<Vec<(InputType, String)>, String> {
+        let mut slots = Vec::new();
+        if self.keyboard {
+            slots.push((InputType::Keyboard, "kb1".to_string()));
+        }
+        if self.mouse {
+            slots.push((InputType::Mouse, "mouse1".to_string()));
+        }
+        for instance in self.gamepad_instances()? {
+            slots.push((InputType::Gamepad, format!("gp{}", instance)));
+        }
+        for instance in self.joystick_instances()? {
+            slots.push((InputType::Joystick, format!("js{}", instance)));
+        }
+        Ok(slots)
     }
-    xml.push_str("  </devices>\n");
+}
 
-    // Write categories section
-    // if !categories_ordered.is_empty() {
-    //     xml.push_str("  <categories>\n");
-    //     for category in &categories_ordered {
-    //         xml.push_str("   <category label=\"");
-    //         xml.push_str(category);
-    //         xml.push_str("\"/>\n");
-    //     }
-    //     xml.push_str("  </categories>\n");
-    // }
+fn normalize_instances(mut instances: Vec<u8>, device: &str) -> Result<Vec<u8>, String> {
+    if let Some(bad) = instances
+        .iter()
+        .find(|i| **i == 0 || **i > MAX_DEVICE_INSTANCES)
+    {
+        return Err(format!(
+            "Invalid {} instance {} (must be 1-{})",
+            device, bad, MAX_DEVICE_INSTANCES
+        ));
+    }
+    instances.sort();
+    instances.dedup();
+    Ok(instances)
+}
 
-    xml.push_str(" </CustomisationUIHeader>\n");
+/// A rule that keeps an action (or one device of it) out of generated profiles
+/// All conditions that are set must match
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProtectedAction {
+    /// Exact action name
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Substring of the action name
+    #[serde(default)]
+    pub name_contains: Option<String>,
+    /// Substring of the AllBinds default for the device being written
+    #[serde(default)]
+    pub default_contains: Option<String>,
+    /// Device types the rule applies to; empty means all
+    #[serde(default)]
+    pub devices: Vec<InputType>,
+    /// Written instead of the action's usual rebinds, e.g. "kb1_escape"
+    #[serde(default)]
+    pub force_input: Option<String>,
+    #[serde(default)]
+    pub activation_mode: Option<String>,
+}
 
-    // Write options for each device type with default Product IDs
-    if devices.keyboard {
-        xml.push_str(" <options type=\"keyboard\" instance=\"1\" Product=\"Keyboard  {6F1D2B61-D5A0-11CF-BFC7-444553540000}\"/>\n");
-    }
-    if devices.mouse {
-        xml.push_str(" <options type=\"mouse\" instance=\"1\" Product=\"Mouse  {6F1D2B62-D5A0-11CF-BFC7-444553540000}\"/>\n");
-    }
-    if devices.joystick1 {
-        xml.push_str(" <options type=\"joystick\" instance=\"1\" Product=\"\"/>\n");
-    }
-    if devices.joystick2 {
-        xml.push_str(" <options type=\"joystick\" instance=\"2\" Product=\"\"/>\n");
-    }
-    if devices.gamepad {
-        xml.push_str(" <options type=\"gamepad\" instance=\"1\" Product=\"\"/>\n");
+impl ProtectedAction {
+    fn matches(&self, action: &AllBindsAction, device: Option<&InputType>) -> bool {
+        if let Some(name) = &self.name {
+            if action.name != *name {
+                return false;
+            }
+        }
+        if let Some(part) = &self.name_contains {
+            if !action.name.contains(part.as_str()) {
+                return false;
+            }
+        }
+        if let Some(device) = device {
+            if !self.devices.is_empty() && !self.devices.contains(device) {
+                return false;
+            }
+        }
+        if let Some(part) = &self.default_contains {
+            let Some(device) = device else {
+                return false;
+            };
+            if !default_for_device(action, device).contains(part.as_str()) {
+                return false;
+            }
+        }
+        true
     }
+}
 
-    // Empty modifiers
-    xml.push_str(" <modifiers />\n");
+/// The rules the generators used to hard-code: menu navigation stays on Escape,
+/// and mouse view axes (yaw/pitch/roll, maxis_ defaults) are left alone
+pub fn default_protected_actions() -> Vec<ProtectedAction> {
+    let mut rules: Vec<ProtectedAction> = ["ui_toggle_pause", "ui_back"]
+        .iter()
+        .map(|name| ProtectedAction {
+            name: Some(name.to_string()),
+            force_input: Some("kb1_escape".to_string()),
+            activation_mode: Some("press".to_string()),
+            ..Default::default()
+        })
+        .collect();
 
-    // Write actionmaps with blank rebinds
-    for action_map in &all_binds.action_maps {
-        xml.push_str(" <actionmap name=\"");
-        xml.push_str(&action_map.name);
-        xml.push_str("\">\n");
+    for part in ["yaw", "pitch", "roll"] {
+        rules.push(ProtectedAction {
+            name_contains: Some(part.to_string()),
+            devices: vec![InputType::Mouse],
+            ..Default::default()
+        });
+    }
+    rules.push(ProtectedAction {
+        default_contains: Some("maxis_".to_string()),
+        devices: vec![InputType::Mouse],
+        ..Default::default()
+    });
 
-        for action in &action_map.actions {
-            xml.push_str("  <action name=\"");
-            xml.push_str(&action.name);
-            xml.push_str("\">\n");
-
-            // Special handling for critical UI actions to ensure they always work
-            if action.name == "ui_toggle_pause" || action.name == "ui_back" {
-                // Set escape key explicitly for menu navigation
-                xml.push_str("   <rebind input=\"kb1_escape\" activationMode=\"press\"/>\n");
-            } else {
-                // Skip mouse axis actions to preserve view controls (rotateyaw, rotatepitch, etc.)
-                // These are critical for camera/view control and SC handles them differently
-                let is_mouse_axis_action = action.name.contains("yaw")
-                    || action.name.contains("pitch")
-                    || action.name.contains("roll")
-                    || action.default_mouse.contains("maxis_");
-
-                // Add blank rebinds for each selected device for all other actions
-                if devices.keyboard {
-                    xml.push_str("   <rebind input=\"kb1_ \"/>\n");
-                }
-                if devices.mouse && !is_mouse_axis_action {
-                    xml.push_str("   <rebind input=\"mouse1_ \"/>\n");
-                }
-                if devices.gamepad {
-                    xml.push_str("   <rebind input=\"gp1_ \"/>\n");
-                }
-                if devices.joystick1 {
-                    xml.push_str("   <rebind input=\"js1_ \"/>\n");
-                }
-                if devices.joystick2 {
-                    xml.push_str("   <rebind input=\"js2_ \"/>\n");
-                }
-            }
+    rules
+}
 
-            xml.push_str("  </action>\n");
-        }
+/// Scope and protection rules shared by the unbind and restore-defaults generators
+#[derive(serde::Deserialize, Default)]
+pub struct ProfileGeneratorOptions {
+    /// Only include these actionmaps (empty = all)
+    #[serde(default)]
+    pub action_maps: Vec<String>,
+    /// Only include actionmaps in these AllBinds UI categories (empty = all)
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Replaces the default protected-actions list when set
+    #[serde(default)]
+    pub protected_actions: Option<Vec<ProtectedAction>>,
+}
 
-        xml.push_str(" </actionmap>\n");
+impl ProfileGeneratorOptions {
+    fn includes(&self, action_map: &AllBindsActionMap) -> bool {
+        (self.action_maps.is_empty() || self.action_maps.contains(&action_map.name))
+            && (self.categories.is_empty() || self.categories.contains(&action_map.ui_category))
     }
+}
 
-    xml.push_str("</ActionMaps>\n");
-
-    Ok(xml)
+fn default_for_device<'a>(action: &'a AllBindsAction, device: &InputType) -> &'a str {
+    match device {
+        InputType::Keyboard => action.default_keyboard.trim(),
+        InputType::Mouse => action.default_mouse.trim(),
+        InputType::Gamepad => action.default_gamepad.trim(),
+        InputType::Joystick => action.default_joystick.trim(),
+        InputType::Unknown => "",
+    }
 }
 
-/// Generate a restore defaults profile XML with only default bindings for selected devices
-pub fn generate_restore_defaults_xml(
-    all_binds: &AllBinds,
-    devices: &DeviceSelection,
-) -> Result<String, String> {
-    let mut xml = String::new();
+/// Which rebinds to write for one action
+/// `input_for` builds the input for a device slot, or None to leave that device out
+fn rebinds_for_action(
+    action: &AllBindsAction,
+    slots: &[(InputType, String)],
+    protected: &[ProtectedAction],
+    input_for: impl Fn(&InputType, &str) -> Option<String>,
+) -> Vec<(String, Option<String>)> {
+    // A forcing rule replaces every rebind of the action
+    if let Some(rule) = protected
+        .iter()
+        .find(|r| r.force_input.is_some() && r.matches(action, None))
+    {
+        return vec![(
+            rule.force_input.clone().unwrap_or_default(),
+            rule.activation_mode.clone(),
+        )];
+    }
+
+    slots
+        .iter()
+        .filter(|(device, _)| {
+            !protected
+                .iter()
+                .any(|r| r.force_input.is_none() && r.matches(action, Some(device)))
+        })
+        .filter_map(|(device, prefix)| input_for(device, prefix))
+        .map(|input| (input, None))
+        .collect()
+}
 
+/// Writes the shared root element, header and device options
+fn write_profile_header(
+    xml: &mut String,
+    profile_name: &str,
+    description: &str,
+    slots: &[(InputType, String)],
+) {
     // XML declaration
     xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
 
     // Root ActionMaps element
-    xml.push_str("<ActionMaps version=\"1\" optionsVersion=\"2\" rebindVersion=\"2\" profileName=\"RESTORE_DEFAULTS\">\n");
+    xml.push_str(&format!(
+        "<ActionMaps version=\"1\" optionsVersion=\"2\" rebindVersion=\"2\" profileName=\"{}\">\n",
+        profile_name
+    ));
 
     // Write CustomisationUIHeader with proper attributes
-    xml.push_str(" <CustomisationUIHeader label=\"RESTORE_DEFAULTS\" description=\"Restores default bindings for selected devices\" image=\"\">\n");
+    xml.push_str(&format!(
+        " <CustomisationUIHeader label=\"{}\" description=\"{}\" image=\"\">\n",
+        profile_name, description
+    ));
 
     // Write devices section - order matters!
     xml.push_str("  <devices>\n");
-    if devices.keyboard {
-        xml.push_str("   <keyboard instance=\"1\"/>\n");
-    }
-    if devices.mouse {
-        xml.push_str("   <mouse instance=\"1\"/>\n");
-    }
-    if devices.gamepad {
-        xml.push_str("   <gamepad instance=\"1\"/>\n");
-    }
-    if devices.joystick1 {
-        xml.push_str("   <joystick instance=\"1\"/>\n");
-    }
-    if devices.joystick2 {
-        xml.push_str("   <joystick instance=\"2\"/>\n");
+    for (device, prefix) in slots {
+        let instance = prefix.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        xml.push_str(&format!(
+            "   <{} instance=\"{}\"/>\n",
+            format!("{:?}", device).to_lowercase(),
+            instance
+        ));
     }
     xml.push_str("  </devices>\n");
 
     xml.push_str(" </CustomisationUIHeader>\n");
 
     // Write options for each device type with default Product IDs
-    if devices.keyboard {
-        xml.push_str(" <options type=\"keyboard\" instance=\"1\" Product=\"Keyboard  {6F1D2B61-D5A0-11CF-BFC7-444553540000}\"/>\n");
-    }
-    if devices.mouse {
-        xml.push_str(" <options type=\"mouse\" instance=\"1\" Product=\"Mouse  {6F1D2B62-D5A0-11CF-BFC7-444553540000}\"/>\n");
-    }
-    if devices.joystick1 {
-        xml.push_str(" <options type=\"joystick\" instance=\"1\" Product=\"\"/>\n");
-    }
-    if devices.joystick2 {
-        xml.push_str(" <options type=\"joystick\" instance=\"2\" Product=\"\"/>\n");
-    }
-    if devices.gamepad {
-        xml.push_str(" <options type=\"gamepad\" instance=\"1\" Product=\"\"/>\n");
+    for (device, prefix) in slots {
+        let instance = prefix.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+        let product = match device {
+            InputType::Keyboard => "Keyboard  {6F1D2B61-D5A0-11CF-BFC7-444553540000}",
+            InputType::Mouse => "Mouse  {6F1D2B62-D5A0-11CF-BFC7-444553540000}",
+            _ => "",
+        };
+        xml.push_str(&format!(
+            " <options type=\"{}\" instance=\"{}\" Product=\"{}\"/>\n",
+            format!("{:?}", device).to_lowercase(),
+            instance,
+            product
+        ));
     }
 
     // Empty modifiers
     xml.push_str(" <modifiers />\n");
+}
 
-    // Write actionmaps with default bindings only (no customizations)
+/// Writes the actionmaps; actions and actionmaps without any rebinds are left out
+fn write_profile_actionmaps(
+    xml: &mut String,
+    all_binds: &AllBinds,
+    options: &ProfileGeneratorOptions,
+    rebinds_for: impl Fn(&AllBindsAction) -> Vec<(String, Option<String>)>,
+) {
     for action_map in &all_binds.action_maps {
-        let mut has_default_bindings = false;
+        if !options.includes(action_map) {
+            continue;
+        }
 
-        // Check if this action map has any default bindings for selected devices
+        let mut body = String::new();
         for action in &action_map.actions {
-            if (devices.keyboard && !action.default_keyboard.trim().is_empty())
-                || (devices.mouse && !action.default_mouse.trim().is_empty())
-                || (devices.gamepad && !action.default_gamepad.trim().is_empty())
-                || (devices.joystick1 && !action.default_joystick.trim().is_empty())
-                || (devices.joystick2 && !action.default_joystick.trim().is_empty())
-            {
-                has_default_bindings = true;
-                break;
+            let rebinds = rebinds_for(action);
+            if rebinds.is_empty() {
+                continue;
+            }
+
+            body.push_str("  <action name=\"");
+            body.push_str(&action.name);
+            body.push_str("\">\n");
+
+            for (input, activation_mode) in rebinds {
+                body.push_str("   <rebind input=\"");
+                body.push_str(&input);
+                body.push('"');
+                if let Some(mode) = activation_mode {
+                    body.push_str(" activationMode=\"");
+                    body.push_str(&mode);
+                    body.push('"');
+                }
+                body.push_str("/>\n");
             }
+
+            body.push_str("  </action>\n");
         }
 
-        // Only write the action map if it has default bindings for selected devices
-        if !has_default_bindings {
+        if body.is_empty() {
             continue;
         }
 
         xml.push_str(" <actionmap name=\"");
         xml.push_str(&action_map.name);
         xml.push_str("\">\n");
+        xml.push_str(&body);
+        xml.push_str(" </actionmap>\n");
+    }
+}
 
-        for action in &action_map.actions {
-            let mut default_inputs: Vec<String> = Vec::new();
+/// Generate an unbind profile XML that clears all bindings for selected devices
+pub fn generate_unbind_xml(
+    all_binds: &AllBinds,
+    devices: &DeviceSelection,
+    options: &ProfileGeneratorOptions,
+) -> Result<String, String> {
+    let slots = devices.slots()?;
+    let default_rules = default_protected_actions();
+    let protected = options
+        .protected_actions
+        .as_deref()
+        .unwrap_or(&default_rules);
 
-            // Collect default bindings for selected devices
-            if devices.keyboard && !action.default_keyboard.trim().is_empty() {
-                default_inputs.push(format!("kb1_{}", action.default_keyboard.trim()));
-            }
-            if devices.mouse && !action.default_mouse.trim().is_empty() {
-                default_inputs.push(format!("mouse1_{}", action.default_mouse.trim()));
-            }
-            if devices.gamepad && !action.default_gamepad.trim().is_empty() {
-                default_inputs.push(format!("gp1_{}", action.default_gamepad.trim()));
-            }
-            if devices.joystick1 && !action.default_joystick.trim().is_empty() {
-                default_inputs.push(format!("js1_{}", action.default_joystick.trim()));
-            }
-            if devices.joystick2 && !action.default_joystick.trim().is_empty() {
-                default_inputs.push(format!("js2_{}", action.default_joystick.trim()));
-            }
+    let mut xml = String::new();
+    write_profile_header(
+        &mut xml,
+        "UNBIND_ALL_DEVICES",
+        "Clears all bindings for selected devices",
+        &slots,
+    );
 
-            // Only write the action if it has default bindings
-            if default_inputs.is_empty() {
-                continue;
-            }
+    // Write actionmaps with blank rebinds
+    write_profile_actionmaps(&mut xml, all_binds, options, |action| {
+        rebinds_for_action(action, &slots, protected, |_, prefix| {
+            Some(format!("{}_ ", prefix))
+        })
+    });
 
-            xml.push_str("  <action name=\"");
-            xml.push_str(&action.name);
-            xml.push_str("\">\n");
-
-            // Write each default binding
-            for input in default_inputs {
-                xml.push_str("   <rebind input=\"");
-                xml.push_str(&input);
-                xml.push_str("\"");
-
-                // Add activation mode if present and not default "press"
-                if !action.activation_mode.is_empty() && action.activation_mode != "press" {
-                    xml.push_str(" activationMode=\"");
-                    xml.push_str(&action.activation_mode);
-                    xml.push_str("\"");
-                }
+    xml.push_str("</ActionMaps>\n");
 
-                xml.push_str("/>\n");
-            }
+    Ok(xml)
+}
 
-            xml.push_str("  </action>\n");
+/// Generate a restore defaults profile XML with only default bindings for selected devices
+pub fn generate_restore_defaults_xml(
+    all_binds: &AllBinds,
+    devices: &DeviceSelection,
+    options: &ProfileGeneratorOptions,
+) -> Result<String, String> {
+    let slots = devices.slots()?;
+    let default_rules = default_protected_actions();
+    let protected = options
+        .protected_actions
+        .as_deref()
+        .unwrap_or(&default_rules);
+
+    let mut xml = String::new();
+    write_profile_header(
+        &mut xml,
+        "RESTORE_DEFAULTS",
+        "Restores default bindings for selected devices",
+        &slots,
+    );
+
+    // Write actionmaps with default bindings only (no customizations)
+    write_profile_actionmaps(&mut xml, all_binds, options, |action| {
+        let mut rebinds = rebinds_for_action(action, &slots, protected, |device, prefix| {
+            let default = default_for_device(action, device);
+            (!default.is_empty()).then(|| format!("{}_{}", prefix, default))
+        });
+
+        // Add activation mode if present and not default "press"
+        if !action.activation_mode.is_empty() && action.activation_mode != "press" {
+            for (_, mode) in rebinds.iter_mut() {
+                mode.get_or_insert_with(|| action.activation_mode.clone());
+            }
         }
 
-        xml.push_str(" </actionmap>\n");
-    }
+        rebinds
+    });
 
     xml.push_str("</ActionMaps>\n");
 
     Ok(xml)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn display(input: &str) -> String {
+        Rebind {
+            input: input.to_string(),
+            multi_tap: None,
+            activation_mode: String::new(),
+        }
+        .get_display_name()
+    }
+
+    #[test]
+    fn format_binding_labels_keys() {
+        assert_eq!(Rebind::format_binding("np_1"), "Numpad 1");
+        assert_eq!(Rebind::format_binding("lbracket"), "[");
+        assert_eq!(Rebind::format_binding("mwheel_up"), "Wheel Up");
+        assert_eq!(Rebind::format_binding("button3"), "Button 3");
+        assert_eq!(Rebind::format_binding("hat1_up"), "Hat 1 Up");
+    }
+
+    #[test]
+    fn format_binding_splits_chords() {
+        assert_eq!(Rebind::format_binding("lshift+a"), "Left Shift + A");
+        assert_eq!(
+            Rebind::format_binding("button5+js1_button3"),
+            "Button 5 + Button 3"
+        );
+        assert_eq!(
+            Rebind::format_binding("lalt+rctrl+np_enter"),
+            "Left Alt + Right Ctrl + Numpad Enter"
+        );
+        assert_eq!(
+            Rebind::format_binding("button5+js1_hat1_left"),
+            "Button 5 + Hat 1 Left"
+        );
+    }
+
+    #[test]
+    fn display_name_of_chords() {
+        assert_eq!(display("kb1_lshift+a"), "Keyboard - Left Shift + A");
+        assert_eq!(
+            display("js1_button5+js1_button3"),
+            "Joystick 1 - Button 5 + Button 3"
+        );
+        assert_eq!(
+            display("LALT+js2_button3"),
+            "Left Alt + Joystick 2 - Button 3"
+        );
+        assert_eq!(display("js1_ "), "Unbound");
+    }
+}
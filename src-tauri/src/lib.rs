@@ -3,10 +3,29 @@ use std::sync::Mutex;
 use tauri::Manager;
 use tauri_plugin_opener::OpenerExt;
 
+mod backup;
+mod characters;
+mod control_map;
+mod device_database;
 mod directinput;
+mod error;
+#[cfg(target_os = "linux")]
+mod evdev_input;
+mod file_access;
 mod hid_reader;
+mod hid_service;
+mod input_source;
+mod input_stream;
 mod keybindings;
-
+mod profile_library;
+mod sc_install;
+mod sc_keys;
+mod session;
+mod template;
+mod transplant;
+mod watcher;
+
+use error::AppError;
 use keybindings::{Action, ActionMap, ActionMaps, AllBinds, MergedBindings, OrganizedKeybindings};
 
 // Resources subfolder name - change this to customize the bundled resources folder
@@ -28,13 +47,6 @@ struct ConflictingBinding {
     action_label: String,
 }
 
-// Struct for Star Citizen installation information
-#[derive(serde::Serialize)]
-struct ScInstallation {
-    name: String,
-    path: String,
-}
-
 // Struct for character file information
 #[derive(serde::Serialize, Clone)]
 struct CharacterFile {
@@ -49,6 +61,12 @@ struct AppState {
     current_bindings: Option<ActionMaps>,
     all_binds: Option<AllBinds>,
     current_file_name: Option<String>,
+    // Full path of the loaded layout (watched for external changes)
+    current_file_path: Option<String>,
+    // Bindings as last read from or written to disk, used as the merge base
+    bindings_base: Option<ActionMaps>,
+    // On-disk session (snapshot + journal) so unsaved work survives restarts
+    session: Option<session::SessionStore>,
 }
 
 impl AppState {
@@ -57,6 +75,85 @@ impl AppState {
             current_bindings: None,
             all_binds: None,
             current_file_name: None,
+            current_file_path: None,
+            bindings_base: None,
+            session: None,
+        }
+    }
+
+    // Journal the rebinds of one action after it was edited
+    fn journal_action(&mut self, action_map_name: &str, action_name: &str) {
+        let needs_compaction = match self.session.as_mut() {
+            Some(store) => {
+                store.record_action(self.current_bindings.as_ref(), action_map_name, action_name);
+                store.needs_compaction()
+            }
+            None => false,
+        };
+
+        if needs_compaction {
+            self.persist();
+        }
+    }
+
+    // Write the whole session to disk (after loads, saves and bulk edits)
+    fn persist(&mut self) {
+        let mut snapshot = session::SessionSnapshot {
+            sequence: 0,
+            current_bindings: self.current_bindings.clone(),
+            current_file_name: self.current_file_name.clone(),
+            current_file_path: self.current_file_path.clone(),
+            bindings_base: self.bindings_base.clone(),
+        };
+
+        if let Some(store) = self.session.as_mut() {
+            if let Err(e) = store.checkpoint(&mut snapshot) {
+                error!("Failed to persist session: {}", e);
+            }
+        }
+    }
+
+    // Open the session store and take over the recovered session
+    fn recover_session(&mut self, dir: &std::path::Path) -> Result<(), AppError> {
+        let (store, snapshot) = session::SessionStore::open(dir)?;
+        self.current_bindings = snapshot.current_bindings;
+        self.current_file_name = snapshot.current_file_name;
+        self.current_file_path = snapshot.current_file_path;
+        self.bindings_base = snapshot.bindings_base;
+        self.session = Some(store);
+        Ok(())
+    }
+
+    // A layout was written to `path`; it only becomes the merge base when it's the loaded
+    // file, other files (e.g. install mappings) don't change what the loaded file holds
+    fn note_saved(&mut self, path: &std::path::Path) {
+        let canonical =
+            |p: &std::path::Path| std::fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+        let is_loaded_file = self
+            .current_file_path
+            .as_deref()
+            .is_some_and(|loaded| canonical(std::path::Path::new(loaded)) == canonical(path));
+        if is_loaded_file {
+            self.bindings_base = self.current_bindings.clone();
+        }
+    }
+
+    // Inputs the loaded profile declares as modifiers, for chord detection
+    fn declared_modifiers(&self) -> Vec<String> {
+        self.current_bindings
+            .as_ref()
+            .map(|b| b.modifiers.clone())
+            .unwrap_or_default()
+    }
+
+    // Edits that haven't been written to a file yet
+    fn has_unsaved_changes(&self) -> bool {
+        let Some(current) = &self.current_bindings else {
+            return false;
+        };
+        match &self.bindings_base {
+            Some(base) => serde_json::to_value(current).ok() != serde_json::to_value(base).ok(),
+            None => !current.action_maps.is_empty(),
         }
     }
 }
@@ -68,33 +165,76 @@ fn greet(name: &str) -> String {
 }
 
 #[tauri::command]
-fn detect_joysticks() -> Result<Vec<directinput::JoystickInfo>, String> {
-    directinput::detect_joysticks()
+fn detect_joysticks(
+    hid: tauri::State<hid_service::HidService>,
+) -> Result<Vec<directinput::JoystickInfo>, AppError> {
+    directinput::detect_joysticks(&hid).map_err(AppError::Input)
 }
 
 #[tauri::command]
-fn get_connected_devices() -> Result<Vec<directinput::DeviceInfo>, String> {
-    directinput::list_connected_devices()
+fn get_connected_devices(
+    hid: tauri::State<hid_service::HidService>,
+) -> Result<Vec<directinput::DeviceInfo>, AppError> {
+    directinput::list_connected_devices(&hid).map_err(AppError::Input)
+}
+
+#[tauri::command]
+fn get_device_database() -> device_database::DatabaseFile {
+    device_database::snapshot()
+}
+
+// Add or replace a device in the user's override file
+#[tauri::command]
+fn save_user_device(entry: device_database::DeviceEntry) -> Result<(), AppError> {
+    device_database::set_user_device(entry)
+}
+
+#[tauri::command]
+fn remove_user_device(vendor_id: String, product_id: String) -> Result<(), AppError> {
+    match (
+        device_database::parse_hex_id(&vendor_id),
+        device_database::parse_hex_id(&product_id),
+    ) {
+        (Some(vendor), Some(product)) => device_database::remove_user_device(vendor, product),
+        _ => Err(AppError::InvalidInput(format!(
+            "Invalid device id {}:{}",
+            vendor_id, product_id
+        ))),
+    }
 }
 
 #[tauri::command]
 fn detect_axis_movement(
     device_uuid: String,
     timeout_millis: Option<u64>,
-) -> Result<Option<directinput::AxisMovement>, String> {
+    hid: tauri::State<hid_service::HidService>,
+    input_stream: tauri::State<Mutex<input_stream::InputStream>>,
+) -> Result<Option<directinput::AxisMovement>, AppError> {
     let timeout = timeout_millis.unwrap_or(100); // Default 100ms for polling
-    directinput::detect_axis_movement_for_device(&device_uuid, timeout)
+    let _pause = input_stream.lock().unwrap().pause();
+    directinput::detect_axis_movement_for_device(&hid, &device_uuid, timeout)
+        .map_err(AppError::Input)
 }
 
 #[tauri::command]
 async fn wait_for_input_binding(
     session_id: String,
     timeout_secs: u64,
-) -> Result<Option<directinput::DetectedInput>, String> {
+    hid: tauri::State<'_, hid_service::HidService>,
+    state: tauri::State<'_, Mutex<AppState>>,
+    input_stream: tauri::State<'_, Mutex<input_stream::InputStream>>,
+) -> Result<Option<directinput::DetectedInput>, AppError> {
+    let hid = hid.inner().clone();
+    let declared_modifiers = state.lock().unwrap().declared_modifiers();
+    let pause = input_stream.lock().unwrap().pause();
     // Run the blocking operation in a separate thread to avoid freezing the UI
-    tokio::task::spawn_blocking(move || directinput::wait_for_input(session_id, timeout_secs))
-        .await
-        .map_err(|e| format!("Task join error: {}", e))?
+    tokio::task::spawn_blocking(move || {
+        let _pause = pause;
+        directinput::wait_for_input(&hid, declared_modifiers, session_id, timeout_secs)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(AppError::Input)
 }
 
 #[tauri::command]
@@ -103,10 +243,19 @@ async fn wait_for_inputs_with_events(
     session_id: String,
     initial_timeout_secs: u64,
     collect_duration_secs: u64,
-) -> Result<(), String> {
+    hid: tauri::State<'_, hid_service::HidService>,
+    state: tauri::State<'_, Mutex<AppState>>,
+    input_stream: tauri::State<'_, Mutex<input_stream::InputStream>>,
+) -> Result<(), AppError> {
+    let hid = hid.inner().clone();
+    let declared_modifiers = state.lock().unwrap().declared_modifiers();
+    let pause = input_stream.lock().unwrap().pause();
     // Run the blocking operation in a separate thread to avoid freezing the UI
     tokio::task::spawn_blocking(move || {
+        let _pause = pause;
         directinput::wait_for_inputs_with_events(
+            &hid,
+            declared_modifiers,
             window,
             session_id,
             initial_timeout_secs,
@@ -115,16 +264,81 @@ async fn wait_for_inputs_with_events(
     })
     .await
     .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(AppError::Input)
+}
+
+// Whether detection can read keyboard keys; on Linux that needs access to the keyboards'
+// event nodes ("input" group membership)
+#[tauri::command]
+fn keyboard_available() -> bool {
+    directinput::keyboard_available()
+}
+
+// Save a device's descriptor and raw reports so detection can be replayed from the file
+#[tauri::command]
+async fn record_input(
+    device_uuid: String,
+    duration_secs: u64,
+    path: String,
+    hid: tauri::State<'_, hid_service::HidService>,
+    input_stream: tauri::State<'_, Mutex<input_stream::InputStream>>,
+    file_access: tauri::State<'_, Mutex<file_access::FileAccess>>,
+) -> Result<usize, AppError> {
+    let path = file_access.lock().unwrap().resolve(&path, &["json"])?;
+    let hid = hid.inner().clone();
+    let pause = input_stream.lock().unwrap().pause();
+    tokio::task::spawn_blocking(move || {
+        let _pause = pause;
+        directinput::record_input(&hid, &device_uuid, duration_secs, &path)
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+    .map_err(AppError::Input)
+}
+
+#[tauri::command]
+fn replay_input_recording(
+    path: String,
+    state: tauri::State<Mutex<AppState>>,
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> Result<Vec<directinput::DetectedInput>, AppError> {
+    let path = file_access.lock().unwrap().resolve(&path, &["json"])?;
+    let declared_modifiers = state.lock().unwrap().declared_modifiers();
+    directinput::replay_recording(&path, declared_modifiers).map_err(AppError::Input)
+}
+
+// Emits "input-state" with every device's live state until stopped
+#[tauri::command]
+fn start_input_stream(
+    rate_hz: Option<u32>,
+    app_handle: tauri::AppHandle,
+    hid: tauri::State<hid_service::HidService>,
+    input_stream: tauri::State<Mutex<input_stream::InputStream>>,
+) -> Result<(), AppError> {
+    input_stream.lock().unwrap().start(
+        app_handle,
+        hid.inner().clone(),
+        rate_hz.unwrap_or(input_stream::DEFAULT_RATE_HZ),
+    );
+    Ok(())
+}
+
+#[tauri::command]
+fn stop_input_stream(
+    input_stream: tauri::State<Mutex<input_stream::InputStream>>,
+) -> Result<(), AppError> {
+    input_stream.lock().unwrap().stop();
+    Ok(())
 }
 
 #[tauri::command]
 fn load_keybindings(
     file_path: String,
     state: tauri::State<Mutex<AppState>>,
-) -> Result<OrganizedKeybindings, String> {
+) -> Result<OrganizedKeybindings, AppError> {
     // Read the XML file
-    let xml_content =
-        std::fs::read_to_string(&file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let xml_content = std::fs::read_to_string(&file_path)
+        .map_err(|e| AppError::io("Failed to read", std::path::Path::new(&file_path), e))?;
 
     // Parse the XML
     let action_maps = ActionMaps::from_xml(&xml_content)?;
@@ -140,6 +354,9 @@ fn load_keybindings(
     let mut app_state = state.lock().unwrap();
     app_state.current_bindings = Some(action_maps.clone());
     app_state.current_file_name = Some(file_name);
+    app_state.current_file_path = Some(file_path.clone());
+    app_state.bindings_base = Some(action_maps.clone());
+    app_state.persist();
 
     // Organize the data for the UI
     Ok(action_maps.organize())
@@ -153,7 +370,28 @@ fn update_binding(
     multi_tap: Option<u32>,
     activation_mode: Option<String>,
     state: tauri::State<Mutex<AppState>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    let mut app_state = state.lock().unwrap();
+    apply_update_binding(
+        &mut app_state,
+        action_map_name.clone(),
+        action_name.clone(),
+        new_input,
+        multi_tap,
+        activation_mode,
+    )?;
+    app_state.journal_action(&action_map_name, &action_name);
+    Ok(())
+}
+
+fn apply_update_binding(
+    app_state: &mut AppState,
+    action_map_name: String,
+    action_name: String,
+    new_input: String,
+    multi_tap: Option<u32>,
+    activation_mode: Option<String>,
+) -> Result<(), AppError> {
     eprintln!("update_binding called with:");
     eprintln!("  action_map_name: '{}'", action_map_name);
     eprintln!("  action_name: '{}'", action_name);
@@ -161,8 +399,6 @@ fn update_binding(
     eprintln!("  multi_tap: {:?}", multi_tap);
     eprintln!("  activation_mode: {:?}", activation_mode);
 
-    let mut app_state = state.lock().unwrap();
-
     if let Some(ref mut bindings) = app_state.current_bindings {
         eprintln!("Current bindings available, checking action maps...");
         eprintln!(
@@ -254,6 +490,7 @@ fn update_binding(
                     profile_name: "User Customizations".to_string(),
                     action_maps: Vec::new(),
                     categories: Vec::new(),
+                    modifiers: Vec::new(),
                     devices: keybindings::DeviceInfo {
                         keyboards: Vec::new(),
                         mice: Vec::new(),
@@ -334,7 +571,10 @@ fn update_binding(
         eprintln!("AllBinds not available");
     }
 
-    Err("Action not found".to_string())
+    Err(AppError::NotFound(format!(
+        "Action not found: {} / {}",
+        action_map_name, action_name
+    )))
 }
 
 #[tauri::command]
@@ -342,7 +582,7 @@ fn reset_binding(
     action_map_name: String,
     action_name: String,
     state: tauri::State<Mutex<AppState>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let mut app_state = state.lock().unwrap();
 
     eprintln!(
@@ -365,22 +605,23 @@ fn reset_binding(
             // If the action map is now empty, optionally remove it
             // (keeping empty action maps shouldn't cause issues)
         }
+        app_state.journal_action(&action_map_name, &action_name);
         Ok(())
     } else {
-        Err("No bindings loaded".to_string())
+        Err(AppError::NoBindingsLoaded)
     }
 }
 
 #[tauri::command]
 fn get_current_bindings(
     state: tauri::State<Mutex<AppState>>,
-) -> Result<OrganizedKeybindings, String> {
+) -> Result<OrganizedKeybindings, AppError> {
     let app_state = state.lock().unwrap();
 
     if let Some(ref bindings) = app_state.current_bindings {
         Ok(bindings.organize())
     } else {
-        Err("No bindings loaded".to_string())
+        Err(AppError::NoBindingsLoaded)
     }
 }
 
@@ -388,7 +629,8 @@ fn get_current_bindings(
 fn export_keybindings(
     file_path: String,
     state: tauri::State<Mutex<AppState>>,
-) -> Result<(), String> {
+    hid: tauri::State<hid_service::HidService>,
+) -> Result<(), AppError> {
     let mut app_state = state.lock().unwrap();
 
     if let Some(ref mut bindings) = app_state.current_bindings {
@@ -410,7 +652,7 @@ fn export_keybindings(
         // Always regenerate device Product strings from detected devices on export
         // This ensures GUIDs are always correct and up-to-date
         bindings.devices.joysticks.clear();
-        if let Ok(detected_devices) = directinput::detect_joysticks() {
+        if let Ok(detected_devices) = directinput::detect_joysticks(&hid) {
             info!(
                 "Populating device Product strings from {} detected devices",
                 detected_devices.len()
@@ -470,33 +712,359 @@ fn export_keybindings(
         let xml_content = bindings.to_xml_with_categories(all_binds);
 
         // Write to file
-        std::fs::write(&file_path, xml_content)
-            .map_err(|e| format!("Failed to write keybindings file: {}", e))?;
-
+        watcher::note_own_write(std::path::Path::new(&file_path), xml_content.as_bytes());
+        std::fs::write(&file_path, xml_content).map_err(|e| {
+            AppError::io(
+                "Failed to write keybindings file",
+                std::path::Path::new(&file_path),
+                e,
+            )
+        })?;
+
+        // The file the editor saves to is its loaded layout from now on (Save As switches)
+        app_state.current_file_name = std::path::Path::new(&file_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        app_state.current_file_path = Some(file_path);
+        app_state.bindings_base = app_state.current_bindings.clone();
+        app_state.persist();
         Ok(())
     } else {
-        Err("No keybindings loaded to export".to_string())
+        Err(AppError::NoBindingsLoaded)
     }
 }
 
 // Template management commands
 #[tauri::command]
-fn save_template(file_path: String, template_json: String) -> Result<(), String> {
-    std::fs::write(&file_path, template_json)
-        .map_err(|e| format!("Failed to save template: {}", e))?;
+fn save_template(
+    file_path: String,
+    template: template::Template,
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> Result<Vec<template::TemplateIssue>, AppError> {
+    let path = file_access.lock().unwrap().resolve(&file_path, &["json"])?;
+
+    let issues = template.validate();
+    let blocking = template::blocking_issues(&issues);
+    if !blocking.is_empty() {
+        return Err(AppError::TemplateInvalid(blocking));
+    }
+
+    // Saving always writes the current format
+    let template = template::Template {
+        version: template::TEMPLATE_VERSION.to_string(),
+        ..template
+    };
+    let json = serde_json::to_string_pretty(&template)?;
+    std::fs::write(&path, json).map_err(|e| AppError::io("Failed to save template", &path, e))?;
+    Ok(issues)
+}
+
+#[tauri::command]
+fn load_template(
+    file_path: String,
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> Result<template::LoadedTemplate, AppError> {
+    let path = file_access.lock().unwrap().resolve(&file_path, &["json"])?;
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| AppError::io("Failed to load template", &path, e))?;
+    let (template, migrated_from) = template::Template::from_json(&json)?;
+    let issues = template.validate();
+    Ok(template::LoadedTemplate {
+        template,
+        migrated_from,
+        issues,
+    })
+}
+
+// Bundled templates live in this folder of the resource dir
+const TEMPLATES_SUBFOLDER: &str = "joystick profiles";
+
+#[tauri::command]
+fn match_device_templates(
+    devices: Vec<directinput::JoystickInfo>,
+    app_handle: tauri::AppHandle,
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> Result<Vec<template::TemplateMatch>, AppError> {
+    // Bundled templates plus any folder the user loaded or saved templates in
+    let mut dirs = vec![resource_dir(&app_handle)?.join(TEMPLATES_SUBFOLDER)];
+    dirs.extend(
+        file_access
+            .lock()
+            .unwrap()
+            .config()
+            .picked_folders
+            .iter()
+            .cloned(),
+    );
+    let templates = template::find_templates(&dirs);
+    info!(
+        "Matching {} device(s) against {} template(s)",
+        devices.len(),
+        templates.len()
+    );
+
+    Ok(devices
+        .iter()
+        .map(|device| {
+            let best = template::best_template(
+                &templates,
+                &template::MatchDevice {
+                    instance: device.id,
+                    name: &device.name,
+                    product_name: device.product_name.as_deref(),
+                    uuid: device.uuid.as_deref(),
+                },
+            );
+            template::TemplateMatch {
+                instance: device.id,
+                device_name: device.name.clone(),
+                best,
+            }
+        })
+        .collect())
+}
+
+fn read_template_json(
+    file_access: &file_access::FileAccess,
+    file_path: &str,
+) -> Result<serde_json::Value, AppError> {
+    let path = file_access.resolve(file_path, &["json"])?;
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| AppError::io("Failed to load template", &path, e))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+#[tauri::command]
+fn generate_control_map(
+    source_template_path: String,
+    source_page: String,
+    target_template_path: String,
+    target_page: String,
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> Result<control_map::GeneratedControlMap, AppError> {
+    let (source_template, target_template) = {
+        let file_access = file_access.lock().unwrap();
+        (
+            read_template_json(&file_access, &source_template_path)?,
+            read_template_json(&file_access, &target_template_path)?,
+        )
+    };
+
+    let source =
+        control_map::find_template_page(&source_template, &source_page).ok_or_else(|| {
+            AppError::NotFound(format!(
+                "Page '{}' not found in source template",
+                source_page
+            ))
+        })?;
+    let target =
+        control_map::find_template_page(&target_template, &target_page).ok_or_else(|| {
+            AppError::NotFound(format!(
+                "Page '{}' not found in target template",
+                target_page
+            ))
+        })?;
+
+    Ok(control_map::ControlMap::from_template_pages(
+        source, target,
+    )?)
+}
+
+#[tauri::command]
+fn mirror_joystick_bindings(
+    source_instance: u8,
+    target_instance: u8,
+    control_map: control_map::ControlMap,
+    mode: control_map::MirrorMode,
+    state: tauri::State<Mutex<AppState>>,
+) -> Result<control_map::MirrorReport, AppError> {
+    let mut app_state = state.lock().unwrap();
+    let bindings = app_state
+        .current_bindings
+        .as_mut()
+        .ok_or(AppError::NoBindingsLoaded)?;
+
+    let report = control_map::mirror_bindings(
+        bindings,
+        source_instance,
+        target_instance,
+        &control_map,
+        mode,
+    )?;
+
+    info!(
+        "Mirrored js{} -> js{} ({:?}): {} rebinds mapped, {} unmapped, {} displaced",
+        source_instance,
+        target_instance,
+        mode,
+        report.mapped_count,
+        report.unmapped.len(),
+        report.displaced.len()
+    );
+
+    app_state.persist();
+    Ok(report)
+}
+
+#[tauri::command]
+fn transplant_bindings(
+    file_path: String,
+    selection: transplant::TransplantSelection,
+    mode: transplant::TransplantMode,
+    state: tauri::State<Mutex<AppState>>,
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> Result<transplant::TransplantReport, AppError> {
+    let path = file_access.lock().unwrap().resolve(&file_path, &["xml"])?;
+    let xml_content =
+        std::fs::read_to_string(&path).map_err(|e| AppError::io("Failed to read", &path, e))?;
+    let donor = ActionMaps::from_xml(&xml_content)?;
+
+    let mut app_state = state.lock().unwrap();
+
+    if app_state.current_bindings.is_none() {
+        eprintln!("Creating new current_bindings structure");
+        app_state.current_bindings = Some(ActionMaps {
+            profile_name: "User Customizations".to_string(),
+            action_maps: Vec::new(),
+            categories: Vec::new(),
+            modifiers: Vec::new(),
+            devices: keybindings::DeviceInfo {
+                keyboards: Vec::new(),
+                mice: Vec::new(),
+                joysticks: Vec::new(),
+                device_options: Vec::new(),
+            },
+        });
+    }
+
+    let AppState {
+        current_bindings,
+        all_binds,
+        ..
+    } = &mut *app_state;
+    let bindings = current_bindings.as_mut().unwrap();
+
+    let report =
+        transplant::transplant_bindings(bindings, &donor, &selection, mode, all_binds.as_ref())?;
+
+    info!(
+        "Transplanted bindings from {} ({:?}): {} applied, {} skipped, {} unchanged",
+        file_path,
+        mode,
+        report.applied.len(),
+        report.skipped.len(),
+        report.unchanged
+    );
+
+    app_state.persist();
+    Ok(report)
+}
+
+#[tauri::command]
+fn start_file_watch(
+    installation_paths: Vec<String>,
+    app_handle: tauri::AppHandle,
+    state: tauri::State<Mutex<AppState>>,
+    file_watcher: tauri::State<Mutex<watcher::FileWatcher>>,
+) -> Result<(), AppError> {
+    let loaded_file = state
+        .lock()
+        .unwrap()
+        .current_file_path
+        .as_ref()
+        .map(std::path::PathBuf::from);
+    let installations: Vec<std::path::PathBuf> = installation_paths
+        .iter()
+        .map(std::path::PathBuf::from)
+        .collect();
+
+    file_watcher
+        .lock()
+        .unwrap()
+        .start(app_handle, loaded_file, &installations);
     Ok(())
 }
 
 #[tauri::command]
-fn load_template(file_path: String) -> Result<String, String> {
-    std::fs::read_to_string(&file_path).map_err(|e| format!("Failed to load template: {}", e))
+fn stop_file_watch(
+    file_watcher: tauri::State<Mutex<watcher::FileWatcher>>,
+) -> Result<(), AppError> {
+    file_watcher.lock().unwrap().stop();
+    Ok(())
+}
+
+// Result of taking an external file change on board
+#[derive(serde::Serialize)]
+struct ExternalChangeResult {
+    bindings: OrganizedKeybindings,
+    merge_report: Option<watcher::MergeReport>,
+}
+
+#[tauri::command]
+fn resolve_external_change(
+    file_path: String,
+    resolution: watcher::ExternalChangeResolution,
+    state: tauri::State<Mutex<AppState>>,
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> Result<ExternalChangeResult, AppError> {
+    let mut app_state = state.lock().unwrap();
+
+    // The loaded file was opened through the dialog; anything else has to pass the sandbox
+    let loaded = app_state
+        .current_file_path
+        .as_deref()
+        .and_then(|current| std::fs::canonicalize(current).ok());
+    let path = match std::fs::canonicalize(&file_path) {
+        Ok(path) if Some(&path) == loaded.as_ref() => path,
+        _ => file_access.lock().unwrap().resolve(&file_path, &["xml"])?,
+    };
+    let xml_content =
+        std::fs::read_to_string(&path).map_err(|e| AppError::io("Failed to read", &path, e))?;
+    let theirs = ActionMaps::from_xml(&xml_content)?;
+
+    let (bindings, merge_report) = match (
+        resolution,
+        app_state.current_bindings.as_ref(),
+        app_state.bindings_base.as_ref(),
+    ) {
+        (watcher::ExternalChangeResolution::Merge, Some(ours), Some(base)) => {
+            let (merged, report) = watcher::merge_bindings(base, ours, &theirs);
+            info!(
+                "Merged external change to {}: {} taken, {} conflicts",
+                file_path,
+                report.taken,
+                report.conflicts.len()
+            );
+            (merged, Some(report))
+        }
+        (watcher::ExternalChangeResolution::Merge, _, _) => return Err(AppError::NoBindingsLoaded),
+        (watcher::ExternalChangeResolution::Reload, _, _) => {
+            info!("Reloading {} after external change", file_path);
+            (theirs.clone(), None)
+        }
+    };
+
+    // The file taken on board is the loaded layout from now on
+    let organized = bindings.organize();
+    app_state.current_bindings = Some(bindings);
+    app_state.current_file_name = std::path::Path::new(&file_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string());
+    app_state.current_file_path = Some(file_path);
+    app_state.bindings_base = Some(theirs);
+    app_state.persist();
+
+    Ok(ExternalChangeResult {
+        bindings: organized,
+        merge_report,
+    })
 }
 
 #[tauri::command]
 fn load_all_binds(
     state: tauri::State<Mutex<AppState>>,
     app_handle: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     // Load AllBinds.xml from resources
     let all_binds_path = if cfg!(debug_assertions) {
         // Development: look in project root
@@ -524,7 +1092,7 @@ fn load_all_binds(
 
     // Read the XML file
     let xml_content = std::fs::read_to_string(&all_binds_path)
-        .map_err(|e| format!("Failed to read AllBinds.xml at {:?}: {}", all_binds_path, e))?;
+        .map_err(|e| AppError::io("Failed to read", &all_binds_path, e))?;
 
     // Parse the XML
     let all_binds = AllBinds::from_xml(&xml_content)?;
@@ -537,7 +1105,7 @@ fn load_all_binds(
 }
 
 #[tauri::command]
-fn get_all_binds_xml(app_handle: tauri::AppHandle) -> Result<String, String> {
+fn get_all_binds_xml(app_handle: tauri::AppHandle) -> Result<String, AppError> {
     // Get the AllBinds.xml path
     let all_binds_path = if cfg!(debug_assertions) {
         // Development: look in project root
@@ -564,11 +1132,11 @@ fn get_all_binds_xml(app_handle: tauri::AppHandle) -> Result<String, String> {
 
     // Read and return the raw XML content
     std::fs::read_to_string(&all_binds_path)
-        .map_err(|e| format!("Failed to read AllBinds.xml at {:?}: {}", all_binds_path, e))
+        .map_err(|e| AppError::io("Failed to read", &all_binds_path, e))
 }
 
 #[tauri::command]
-fn get_merged_bindings(state: tauri::State<Mutex<AppState>>) -> Result<MergedBindings, String> {
+fn get_merged_bindings(state: tauri::State<Mutex<AppState>>) -> Result<MergedBindings, AppError> {
     let app_state = state.lock().unwrap();
 
     if let Some(ref all_binds) = app_state.all_binds {
@@ -576,14 +1144,14 @@ fn get_merged_bindings(state: tauri::State<Mutex<AppState>>) -> Result<MergedBin
         let user_bindings = app_state.current_bindings.as_ref();
         Ok(all_binds.merge_with_user_bindings(user_bindings))
     } else {
-        Err("AllBinds.xml not loaded. Please restart the application.".to_string())
+        Err(AppError::AllBindsNotLoaded)
     }
 }
 
 #[tauri::command]
 fn get_user_customizations(
     state: tauri::State<Mutex<AppState>>,
-) -> Result<Option<ActionMaps>, String> {
+) -> Result<Option<ActionMaps>, AppError> {
     let app_state = state.lock().unwrap();
 
     eprintln!("get_user_customizations called");
@@ -605,7 +1173,7 @@ fn get_user_customizations(
 fn restore_user_customizations(
     customizations: Option<ActionMaps>,
     state: tauri::State<Mutex<AppState>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     eprintln!("restore_user_customizations called");
     eprintln!("  has_data: {}", customizations.is_some());
     if let Some(ref c) = customizations {
@@ -618,6 +1186,7 @@ fn restore_user_customizations(
     // Restore the cached user customizations (delta) to backend state
     // This allows us to preserve unsaved work across app restarts
     app_state.current_bindings = customizations;
+    app_state.persist();
 
     eprintln!("restore_user_customizations completed successfully");
     Ok(())
@@ -629,7 +1198,7 @@ fn find_conflicting_bindings(
     exclude_action_map: String,
     exclude_action: String,
     state: tauri::State<Mutex<AppState>>,
-) -> Result<Vec<ConflictingBinding>, String> {
+) -> Result<Vec<ConflictingBinding>, AppError> {
     let app_state = state.lock().unwrap();
     let mut conflicts = Vec::new();
 
@@ -688,7 +1257,7 @@ fn clear_specific_binding(
     action_name: String,
     input_to_clear: String,
     state: tauri::State<Mutex<AppState>>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     eprintln!("clear_specific_binding called with:");
     eprintln!("  action_map_name: '{}'", action_map_name);
     eprintln!("  action_name: '{}'", action_name);
@@ -766,7 +1335,12 @@ fn clear_specific_binding(
             keybindings::InputType::Keyboard => "kb1_ ".to_string(),
             keybindings::InputType::Mouse => "mouse1_ ".to_string(),
             keybindings::InputType::Gamepad => "gp1_ ".to_string(),
-            keybindings::InputType::Unknown => return Err("Unknown input type".to_string()),
+            keybindings::InputType::Unknown => {
+                return Err(AppError::InvalidInput(format!(
+                    "Unknown input type: {}",
+                    input_to_clear
+                )))
+            }
         }
     } else {
         // No default binding, so we can just remove it entirely
@@ -806,6 +1380,7 @@ fn clear_specific_binding(
             profile_name: "User Customizations".to_string(),
             action_maps: Vec::new(),
             categories: Vec::new(),
+            modifiers: Vec::new(),
             devices: keybindings::DeviceInfo {
                 keyboards: Vec::new(),
                 mice: Vec::new(),
@@ -859,71 +1434,69 @@ fn clear_specific_binding(
         });
 
         eprintln!("Successfully cleared binding with explicit unbind entry");
+        app_state.journal_action(&action_map_name, &action_name);
         Ok(())
     } else {
-        Err("Failed to initialize bindings".to_string())
+        Err(AppError::NoBindingsLoaded)
     }
 }
 
 #[tauri::command]
-fn clear_custom_bindings(state: tauri::State<Mutex<AppState>>) -> Result<(), String> {
+fn clear_custom_bindings(state: tauri::State<Mutex<AppState>>) -> Result<(), AppError> {
     let mut app_state = state.lock().unwrap();
     app_state.current_bindings = None;
     app_state.current_file_name = None;
+    app_state.current_file_path = None;
+    app_state.bindings_base = None;
+    app_state.persist();
     Ok(())
 }
 
 #[tauri::command]
-fn scan_sc_installations(base_path: String) -> Result<Vec<ScInstallation>, String> {
-    use std::path::Path;
-
-    let base = Path::new(&base_path);
-
-    // Check if the base path exists
-    if !base.exists() {
-        return Err("Directory does not exist".to_string());
-    }
-
-    if !base.is_dir() {
-        return Err("Path is not a directory".to_string());
-    }
-
-    let mut installations = Vec::new();
-
-    // Common Star Citizen installation folder names
-    let sc_folders = ["LIVE", "PTU", "EPTU", "TECH-PREVIEW"];
-
-    // Scan for each potential installation
-    for folder_name in &sc_folders {
-        let folder_path = base.join(folder_name);
+fn scan_sc_installations(base_path: String) -> Result<Vec<sc_install::ScInstallation>, AppError> {
+    sc_install::scan_base_path(std::path::Path::new(&base_path))
+}
 
-        // Check if this folder exists
-        if !folder_path.exists() || !folder_path.is_dir() {
-            continue;
-        }
+#[tauri::command]
+fn discover_sc_installations(
+    extra_base_paths: Option<Vec<String>>,
+) -> Vec<sc_install::ScInstallation> {
+    let extra: Vec<std::path::PathBuf> = extra_base_paths
+        .unwrap_or_default()
+        .into_iter()
+        .map(std::path::PathBuf::from)
+        .collect();
+    sc_install::discover_installations(&extra)
+}
 
-        // Check for data.p4k in the Data folder
-        let data_p4k_path = folder_path.join("data.p4k");
+// Session recovered from the backend journal
+#[derive(serde::Serialize)]
+struct SessionState {
+    file_name: Option<String>,
+    file_path: Option<String>,
+    has_bindings: bool,
+    has_unsaved_changes: bool,
+}
 
-        if data_p4k_path.exists() && data_p4k_path.is_file() {
-            installations.push(ScInstallation {
-                name: folder_name.to_string(),
-                path: folder_path.to_string_lossy().to_string(),
-            });
-        }
+#[tauri::command]
+fn get_session_state(state: tauri::State<Mutex<AppState>>) -> SessionState {
+    let app_state = state.lock().unwrap();
+    SessionState {
+        file_name: app_state.current_file_name.clone(),
+        file_path: app_state.current_file_path.clone(),
+        has_bindings: app_state.current_bindings.is_some(),
+        has_unsaved_changes: app_state.has_unsaved_changes(),
     }
-
-    Ok(installations)
 }
 
 #[tauri::command]
-fn get_current_file_name(state: tauri::State<Mutex<AppState>>) -> Result<String, String> {
+fn get_current_file_name(state: tauri::State<Mutex<AppState>>) -> Result<String, AppError> {
     let app_state = state.lock().unwrap();
 
     if let Some(ref file_name) = app_state.current_file_name {
         Ok(file_name.clone())
     } else {
-        Err("No keybindings file loaded".to_string())
+        Err(AppError::NoBindingsLoaded)
     }
 }
 
@@ -931,31 +1504,162 @@ fn get_current_file_name(state: tauri::State<Mutex<AppState>>) -> Result<String,
 fn save_bindings_to_install(
     installation_path: String,
     state: tauri::State<Mutex<AppState>>,
-) -> Result<(), String> {
+    hid: tauri::State<hid_service::HidService>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), AppError> {
     use std::path::Path;
 
     // First, verify the installation path still exists
     let install_path = Path::new(&installation_path);
     if !install_path.exists() {
-        return Err(format!(
+        return Err(AppError::NotFound(format!(
             "Installation folder no longer exists: {}",
             installation_path
-        ));
+        )));
     }
     if !install_path.is_dir() {
-        return Err(format!(
+        return Err(AppError::InvalidInput(format!(
             "Installation path is not a directory: {}",
             installation_path
-        ));
+        )));
     }
 
     let mut app_state = state.lock().unwrap();
+    let (file_name, xml_content) = generate_install_xml(&mut app_state, &hid)?;
+
+    write_bindings_to_install(
+        install_path,
+        &file_name,
+        &xml_content,
+        &backup_root(&app_handle)?,
+    )?;
+
+    app_state.note_saved(&sc_install::mappings_dir(install_path).join(&file_name));
+    app_state.persist();
+    Ok(())
+}
+
+// Result of saving to one installation
+#[derive(serde::Serialize)]
+struct InstallSaveResult {
+    name: String,
+    path: String,
+    // "saved", "unchanged" or "failed"
+    status: String,
+    error: Option<AppError>,
+}
+
+#[tauri::command]
+fn save_bindings_to_installs(
+    base_path: String,
+    installation_names: Option<Vec<String>>,
+    state: tauri::State<Mutex<AppState>>,
+    hid: tauri::State<hid_service::HidService>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<InstallSaveResult>, AppError> {
+    let installations = sc_install::scan_base_path(std::path::Path::new(&base_path))?;
+    let backup_root = backup_root(&app_handle)?;
+
+    let mut app_state = state.lock().unwrap();
+    let (file_name, xml_content) = generate_install_xml(&mut app_state, &hid)?;
+
+    let mut results = Vec::new();
+    for install in installations {
+        if let Some(names) = &installation_names {
+            if !names.contains(&install.name) {
+                continue;
+            }
+        }
+
+        let install_path = std::path::Path::new(&install.path);
+        let (status, error) =
+            match write_bindings_to_install(install_path, &file_name, &xml_content, &backup_root) {
+                Ok(written) => {
+                    app_state.note_saved(&sc_install::mappings_dir(install_path).join(&file_name));
+                    (if written { "saved" } else { "unchanged" }, None)
+                }
+                Err(e) => {
+                    error!("Failed to save bindings to {}: {}", install.path, e);
+                    ("failed", Some(e))
+                }
+            };
+
+        info!("Save to {} ({}): {}", install.name, install.path, status);
+        results.push(InstallSaveResult {
+            name: install.name,
+            path: install.path,
+            status: status.to_string(),
+            error,
+        });
+    }
+
+    if results.iter().any(|r| r.status != "failed") {
+        app_state.persist();
+    }
+
+    Ok(results)
+}
+
+// Regenerate joystick Product strings from the detected devices
+// This ensures GUIDs are always correct and up-to-date
+fn refresh_joystick_products(bindings: &mut ActionMaps, hid: &hid_service::HidService) {
+    bindings.devices.joysticks.clear();
+    if let Ok(detected_devices) = directinput::detect_joysticks(hid) {
+        info!(
+            "Populating device Product strings from {} detected devices",
+            detected_devices.len()
+        );
+
+        for (idx, device) in detected_devices.iter().enumerate() {
+            if device.device_type == "Joystick" {
+                let product_string = if let Some(ref uuid) = device.uuid {
+                    // Convert uuid format "vendor_id:product_id" to SC GUID format
+                    let parts: Vec<&str> = uuid.split(':').collect();
+                    if parts.len() == 2 {
+                        // Pad each part to 4 hex digits and uppercase
+                        let vendor_hex = format!("{:0>4}", parts[0].to_uppercase());
+                        let product_hex = format!("{:0>4}", parts[1].to_uppercase());
+
+                        // Use product_name if available, otherwise fall back to name
+                        let device_display_name =
+                            device.product_name.as_ref().unwrap_or(&device.name);
+
+                        format!(
+                            " {}    {{{}{}-0000-0000-0000-504944564944}}",
+                            device_display_name, product_hex, vendor_hex
+                        )
+                    } else {
+                        let device_display_name =
+                            device.product_name.as_ref().unwrap_or(&device.name);
+                        format!(" {}", device_display_name)
+                    }
+                } else {
+                    let device_display_name = device.product_name.as_ref().unwrap_or(&device.name);
+                    format!(" {}", device_display_name)
+                };
 
+                bindings.devices.joysticks.push(product_string);
+                info!(
+                    "Added joystick {} (instance {}): {}",
+                    device.name,
+                    idx + 1,
+                    bindings.devices.joysticks.last().unwrap()
+                );
+            }
+        }
+    }
+}
+
+// Build the layout XML that gets written into installations, with fresh joystick Product strings
+fn generate_install_xml(
+    app_state: &mut AppState,
+    hid: &hid_service::HidService,
+) -> Result<(String, String), AppError> {
     // Get the filename first (before mutable borrow)
     let file_name = app_state
         .current_file_name
         .as_ref()
-        .ok_or_else(|| "No filename stored".to_string())?
+        .ok_or(AppError::NoBindingsLoaded)?
         .clone();
 
     // Get AllBinds reference (before mutable borrow)
@@ -965,91 +1669,318 @@ fn save_bindings_to_install(
     let bindings = app_state
         .current_bindings
         .as_mut()
-        .ok_or_else(|| "No keybindings loaded".to_string())?;
+        .ok_or(AppError::NoBindingsLoaded)?;
 
     // Always regenerate device Product strings from detected devices on export
-    // This ensures GUIDs are always correct and up-to-date
-    bindings.devices.joysticks.clear();
-    {
-        if let Ok(detected_devices) = directinput::detect_joysticks() {
-            info!(
-                "Populating device Product strings from {} detected devices",
-                detected_devices.len()
-            );
+    refresh_joystick_products(bindings, hid);
 
-            for (idx, device) in detected_devices.iter().enumerate() {
-                if device.device_type == "Joystick" {
-                    let product_string = if let Some(ref uuid) = device.uuid {
-                        // Convert uuid format "vendor_id:product_id" to SC GUID format
-                        let parts: Vec<&str> = uuid.split(':').collect();
-                        if parts.len() == 2 {
-                            // Pad each part to 4 hex digits and uppercase
-                            let vendor_hex = format!("{:0>4}", parts[0].to_uppercase());
-                            let product_hex = format!("{:0>4}", parts[1].to_uppercase());
-
-                            // Use product_name if available, otherwise fall back to name
-                            let device_display_name =
-                                device.product_name.as_ref().unwrap_or(&device.name);
-
-                            format!(
-                                " {}    {{{}{}-0000-0000-0000-504944564944}}",
-                                device_display_name, product_hex, vendor_hex
-                            )
-                        } else {
-                            let device_display_name =
-                                device.product_name.as_ref().unwrap_or(&device.name);
-                            format!(" {}", device_display_name)
-                        }
-                    } else {
-                        let device_display_name =
-                            device.product_name.as_ref().unwrap_or(&device.name);
-                        format!(" {}", device_display_name)
-                    };
+    // Serialize to XML with category information
+    let xml_content = bindings.to_xml_with_categories(all_binds_option.as_ref());
 
-                    bindings.devices.joysticks.push(product_string);
-                    info!(
-                        "Added joystick {} (instance {}): {}",
-                        device.name,
-                        idx + 1,
-                        bindings.devices.joysticks.last().unwrap()
-                    );
-                }
-            }
-        }
-    }
+    Ok((file_name, xml_content))
+}
 
-    // Build the target path: INSTALL\user\client\0\controls\mappings
-    let target_dir = Path::new(&installation_path)
-        .join("user")
-        .join("client")
-        .join("0")
-        .join("controls")
-        .join("mappings");
+// Write the layout into INSTALL\user\client\0\controls\mappings
+// Returns false when the file on disk already has this exact content
+fn write_bindings_to_install(
+    installation_path: &std::path::Path,
+    file_name: &str,
+    xml_content: &str,
+    backup_root: &std::path::Path,
+) -> Result<bool, AppError> {
+    let target_dir = sc_install::mappings_dir(installation_path);
 
     // Create the directory structure if it doesn't exist
     std::fs::create_dir_all(&target_dir)
-        .map_err(|e| format!("Failed to create directory structure: {}", e))?;
+        .map_err(|e| AppError::io("Failed to create", &target_dir, e))?;
 
     // Full path to the target file
-    let target_file = target_dir.join(&file_name);
+    let target_file = target_dir.join(file_name);
 
-    // Serialize to XML with category information
-    let xml_content = bindings.to_xml_with_categories(all_binds_option.as_ref());
+    if std::fs::read(&target_file).is_ok_and(|existing| existing == xml_content.as_bytes()) {
+        return Ok(false);
+    }
 
-    // Write to the target location
-    std::fs::write(&target_file, xml_content)
-        .map_err(|e| format!("Failed to write keybindings file: {}", e))?;
+    // Back up the file we're replacing, then write to the target location
+    backup::write_with_backup(
+        backup_root,
+        &target_file,
+        xml_content.as_bytes(),
+        "save_bindings",
+    )?;
 
-    Ok(())
+    Ok(true)
+}
+
+// Folder in app data where replaced files are snapshotted
+fn backup_root(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf, AppError> {
+    let data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(data_dir.join("backups"))
+}
+
+#[tauri::command]
+fn list_backups(app_handle: tauri::AppHandle) -> Result<Vec<backup::BackupEntry>, AppError> {
+    backup::list_backups(&backup_root(&app_handle)?)
+}
+
+#[tauri::command]
+fn restore_backup(
+    backup_id: String,
+    app_handle: tauri::AppHandle,
+) -> Result<backup::BackupEntry, AppError> {
+    backup::restore_backup(&backup_root(&app_handle)?, &backup_id)
+}
+
+// Folder in app data holding the managed profile library
+fn profile_library_root(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf, AppError> {
+    let data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    Ok(data_dir.join("profiles"))
+}
+
+#[tauri::command]
+fn list_library_profiles(
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<profile_library::ProfileEntry>, AppError> {
+    profile_library::list_profiles(&profile_library_root(&app_handle)?)
+}
+
+#[tauri::command]
+fn import_profile_to_library(
+    file_path: String,
+    name: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<profile_library::ProfileEntry, AppError> {
+    profile_library::import_profile(
+        &profile_library_root(&app_handle)?,
+        std::path::Path::new(&file_path),
+        name,
+    )
+}
+
+#[tauri::command]
+fn save_current_profile_to_library(
+    name: String,
+    state: tauri::State<Mutex<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<profile_library::ProfileEntry, AppError> {
+    let app_state = state.lock().unwrap();
+    let bindings = app_state
+        .current_bindings
+        .as_ref()
+        .ok_or(AppError::NoBindingsLoaded)?;
+
+    let xml_content = bindings.to_xml_with_categories(app_state.all_binds.as_ref());
+    profile_library::add_profile(&profile_library_root(&app_handle)?, &name, &xml_content)
+}
+
+#[tauri::command]
+fn duplicate_library_profile(
+    profile_id: String,
+    new_name: String,
+    app_handle: tauri::AppHandle,
+) -> Result<profile_library::ProfileEntry, AppError> {
+    profile_library::duplicate_profile(&profile_library_root(&app_handle)?, &profile_id, &new_name)
+}
+
+#[tauri::command]
+fn rename_library_profile(
+    profile_id: String,
+    new_name: String,
+    app_handle: tauri::AppHandle,
+) -> Result<profile_library::ProfileEntry, AppError> {
+    profile_library::rename_profile(&profile_library_root(&app_handle)?, &profile_id, &new_name)
+}
+
+#[tauri::command]
+fn update_library_profile_metadata(
+    profile_id: String,
+    update: profile_library::ProfileMetadataUpdate,
+    app_handle: tauri::AppHandle,
+) -> Result<profile_library::ProfileEntry, AppError> {
+    profile_library::update_profile_metadata(
+        &profile_library_root(&app_handle)?,
+        &profile_id,
+        update,
+    )
+}
+
+#[tauri::command]
+fn delete_profile_from_library(
+    profile_id: String,
+    app_handle: tauri::AppHandle,
+) -> Result<(), AppError> {
+    profile_library::delete_profile(&profile_library_root(&app_handle)?, &profile_id)
+}
+
+// Returns false when the installation already had this exact layout
+#[tauri::command]
+fn deploy_profile_to_installation(
+    profile_id: String,
+    installation_path: String,
+    state: tauri::State<Mutex<AppState>>,
+    hid: tauri::State<hid_service::HidService>,
+    app_handle: tauri::AppHandle,
+) -> Result<bool, AppError> {
+    let install_path = std::path::Path::new(&installation_path);
+    if !install_path.is_dir() {
+        return Err(AppError::NotFound(format!(
+            "Installation folder no longer exists: {}",
+            installation_path
+        )));
+    }
+
+    let (metadata, mut bindings) =
+        profile_library::load_profile(&profile_library_root(&app_handle)?, &profile_id)?;
+
+    // Same treatment as save_bindings_to_install: fresh device GUIDs, profile named after the file
+    refresh_joystick_products(&mut bindings, &hid);
+    let stem = profile_library::layout_file_stem(&metadata.name);
+    bindings.profile_name = stem.clone();
+
+    let xml_content = {
+        let app_state = state.lock().unwrap();
+        bindings.to_xml_with_categories(app_state.all_binds.as_ref())
+    };
+
+    let written = write_bindings_to_install(
+        install_path,
+        &format!("layout_{}_exported.xml", stem),
+        &xml_content,
+        &backup_root(&app_handle)?,
+    )?;
+
+    info!(
+        "Deployed profile '{}' to {} ({})",
+        metadata.name,
+        installation_path,
+        if written { "written" } else { "unchanged" }
+    );
+    Ok(written)
 }
 
 #[tauri::command]
-fn write_binary_file(path: String, contents: Vec<u8>) -> Result<(), String> {
-    std::fs::write(&path, contents).map_err(|e| format!("Failed to write file: {}", e))
+fn write_binary_file(
+    path: String,
+    contents: Vec<u8>,
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> Result<(), AppError> {
+    let path = file_access.lock().unwrap().resolve(&path, &["png"])?;
+    std::fs::write(&path, contents).map_err(|e| AppError::io("Failed to write", &path, e))
+}
+
+// Folders that can become allowed roots; they're picked in a dialog opened here, so
+// the webview can't name one itself
+#[derive(serde::Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum FolderRole {
+    ScBase,
+    CharacterLibrary,
 }
 
 #[tauri::command]
-fn log_error(message: String, stack: Option<String>) -> Result<(), String> {
+async fn choose_folder(
+    role: FolderRole,
+    app_handle: tauri::AppHandle,
+    file_access: tauri::State<'_, Mutex<file_access::FileAccess>>,
+) -> Result<Option<String>, AppError> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let title = match role {
+        FolderRole::ScBase => "Select Star Citizen Installation Directory",
+        FolderRole::CharacterLibrary => "Select Character Library Directory",
+    };
+    let Some(picked) = app_handle
+        .dialog()
+        .file()
+        .set_title(title)
+        .blocking_pick_folder()
+    else {
+        return Ok(None);
+    };
+    let folder = picked
+        .into_path()
+        .map_err(|e| AppError::InvalidInput(format!("Unusable folder: {}", e)))?;
+
+    let mut file_access = file_access.lock().unwrap();
+    match role {
+        FolderRole::ScBase => file_access.set_sc_base(folder.clone())?,
+        FolderRole::CharacterLibrary => file_access.set_character_library(folder.clone())?,
+    }
+    Ok(Some(folder.to_string_lossy().to_string()))
+}
+
+#[derive(serde::Deserialize)]
+struct FileDialogFilter {
+    name: String,
+    extensions: Vec<String>,
+}
+
+// Open or save dialog for one file; the folder of the picked file becomes an allowed root
+#[tauri::command]
+async fn choose_file(
+    save: bool,
+    filter: FileDialogFilter,
+    default_path: Option<String>,
+    app_handle: tauri::AppHandle,
+    file_access: tauri::State<'_, Mutex<file_access::FileAccess>>,
+) -> Result<Option<String>, AppError> {
+    use tauri_plugin_dialog::DialogExt;
+
+    let extensions: Vec<&str> = filter.extensions.iter().map(String::as_str).collect();
+    let mut dialog = app_handle
+        .dialog()
+        .file()
+        .add_filter(filter.name, &extensions);
+    if let Some(default_path) = default_path.as_deref().map(std::path::Path::new) {
+        // A folder opens the dialog there; a file path also suggests the file name
+        if default_path.is_dir() {
+            dialog = dialog.set_directory(default_path);
+        } else {
+            if let Some(parent) = default_path.parent().filter(|p| p.is_dir()) {
+                dialog = dialog.set_directory(parent);
+            }
+            if let Some(name) = default_path.file_name() {
+                dialog = dialog.set_file_name(name.to_string_lossy());
+            }
+        }
+    }
+
+    let picked = if save {
+        dialog.blocking_save_file()
+    } else {
+        dialog.blocking_pick_file()
+    };
+    let Some(picked) = picked else {
+        return Ok(None);
+    };
+    let path = picked
+        .into_path()
+        .map_err(|e| AppError::InvalidInput(format!("Unusable file path: {}", e)))?;
+
+    if let Some(folder) = path.parent() {
+        file_access
+            .lock()
+            .unwrap()
+            .add_picked_folder(folder.to_path_buf())?;
+    }
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+#[tauri::command]
+fn get_allowed_folders(
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> file_access::RootConfig {
+    file_access.lock().unwrap().config().clone()
+}
+
+#[tauri::command]
+fn log_error(message: String, stack: Option<String>) -> Result<(), AppError> {
     if let Some(stack_trace) = stack {
         error!("JavaScript Error: {}\nStack: {}", message, stack_trace);
     } else {
@@ -1059,13 +1990,13 @@ fn log_error(message: String, stack: Option<String>) -> Result<(), String> {
 }
 
 #[tauri::command]
-fn log_info(message: String) -> Result<(), String> {
+fn log_info(message: String) -> Result<(), AppError> {
     info!("{}", message);
     Ok(())
 }
 
 #[tauri::command]
-fn get_log_file_path(app_handle: tauri::AppHandle) -> Result<String, String> {
+fn get_log_file_path(app_handle: tauri::AppHandle) -> Result<String, AppError> {
     let log_dir = app_handle
         .path()
         .app_log_dir()
@@ -1075,8 +2006,8 @@ fn get_log_file_path(app_handle: tauri::AppHandle) -> Result<String, String> {
     Ok(log_file.to_string_lossy().to_string())
 }
 
-#[tauri::command]
-fn get_resource_dir(app_handle: tauri::AppHandle) -> Result<String, String> {
+// Folder holding the bundled templates and images
+fn resource_dir(app_handle: &tauri::AppHandle) -> Result<std::path::PathBuf, AppError> {
     let resource_dir = if cfg!(debug_assertions) {
         // Development: look in project root
         let exe_path =
@@ -1099,15 +2030,20 @@ fn get_resource_dir(app_handle: tauri::AppHandle) -> Result<String, String> {
             .join(RESOURCES_SUBFOLDER)
     };
 
-    Ok(resource_dir.to_string_lossy().to_string())
+    Ok(resource_dir)
+}
+
+#[tauri::command]
+fn get_resource_dir(app_handle: tauri::AppHandle) -> Result<String, AppError> {
+    Ok(resource_dir(&app_handle)?.to_string_lossy().to_string())
 }
 
 #[tauri::command]
-async fn open_url(app_handle: tauri::AppHandle, url: String) -> Result<(), String> {
+async fn open_url(app_handle: tauri::AppHandle, url: String) -> Result<(), AppError> {
     app_handle
         .opener()
         .open_url(&url, None::<&str>)
-        .map_err(|e| format!("Failed to open URL: {}", e))
+        .map_err(|e| AppError::Other(format!("Failed to open URL: {}", e)))
 }
 
 fn setup_logging(app_handle: &tauri::AppHandle) -> Result<(), Box<dyn std::error::Error>> {
@@ -1160,17 +2096,33 @@ struct RemoveUnbindResult {
     removed_count: usize,
 }
 
+#[tauri::command]
+fn get_default_protected_actions() -> Vec<keybindings::ProtectedAction> {
+    keybindings::default_protected_actions()
+}
+
 #[tauri::command]
 fn generate_unbind_profile(
     devices: keybindings::DeviceSelection,
+    options: Option<keybindings::ProfileGeneratorOptions>,
     base_path: String,
     state: tauri::State<Mutex<AppState>>,
-) -> Result<UnbindProfileResult, String> {
+    app_handle: tauri::AppHandle,
+) -> Result<UnbindProfileResult, AppError> {
     use std::fs;
 
+    let backup_root = backup_root(&app_handle)?;
+
     info!(
-        "Generating unbind profile for devices: keyboard={}, mouse={}, gamepad={}, js1={}, js2={}",
-        devices.keyboard, devices.mouse, devices.gamepad, devices.joystick1, devices.joystick2
+        "Generating unbind profile for devices: keyboard={}, mouse={}, gamepads={:?}, joysticks={:?}",
+        devices.keyboard,
+        devices.mouse,
+        devices
+            .gamepad_instances()
+            .map_err(AppError::InvalidInput)?,
+        devices
+            .joystick_instances()
+            .map_err(AppError::InvalidInput)?
     );
     info!("Using base path: {}", base_path);
 
@@ -1181,10 +2133,11 @@ fn generate_unbind_profile(
     let all_binds = app_state
         .all_binds
         .as_ref()
-        .ok_or("AllBinds not loaded. Please load the keybindings first.")?;
+        .ok_or(AppError::AllBindsNotLoaded)?;
 
     // Generate the unbind XML
-    let unbind_xml = keybindings::generate_unbind_xml(all_binds, &devices)?;
+    let unbind_xml =
+        keybindings::generate_unbind_xml(all_binds, &devices, &options.unwrap_or_default())?;
 
     info!("Generated unbind XML, length: {} bytes", unbind_xml.len());
 
@@ -1192,7 +2145,7 @@ fn generate_unbind_profile(
     let mut saved_locations = Vec::new();
 
     // Get SC installations
-    match scan_sc_installations(base_path.clone()) {
+    match sc_install::scan_base_path(std::path::Path::new(&base_path)) {
         Ok(installations) => {
             info!("Found {} SC installations", installations.len());
             for install in installations {
@@ -1200,25 +2153,34 @@ fn generate_unbind_profile(
                     "Processing installation: {} at {}",
                     install.name, install.path
                 );
-                let mappings_dir = format!("{}\\user\\client\\0\\controls\\mappings", install.path);
+                let mappings_dir = sc_install::mappings_dir(std::path::Path::new(&install.path));
 
                 // Create directory if it doesn't exist
                 if let Err(e) = fs::create_dir_all(&mappings_dir) {
                     error!(
                         "Failed to create mappings directory {}: {}",
-                        mappings_dir, e
+                        mappings_dir.display(),
+                        e
                     );
                     continue;
                 }
 
-                let file_path = format!("{}\\UNBIND_ALL.xml", mappings_dir);
-                info!("Attempting to write to: {}", file_path);
-                match fs::write(&file_path, &unbind_xml) {
+                let file_path = mappings_dir.join("UNBIND_ALL.xml");
+                info!("Attempting to write to: {}", file_path.display());
+                match backup::write_with_backup(
+                    &backup_root,
+                    &file_path,
+                    unbind_xml.as_bytes(),
+                    "unbind_profile",
+                ) {
                     Ok(_) => {
-                        info!("Successfully saved unbind profile to: {}", file_path);
-                        saved_locations.push(file_path);
+                        info!(
+                            "Successfully saved unbind profile to: {}",
+                            file_path.display()
+                        );
+                        saved_locations.push(file_path.to_string_lossy().to_string());
                     }
-                    Err(e) => error!("Failed to write to {}: {}", file_path, e),
+                    Err(e) => error!("Failed to write to {}: {}", file_path.display(), e),
                 }
             }
         }
@@ -1233,8 +2195,13 @@ fn generate_unbind_profile(
     // If no installations found, save to current directory as fallback
     if saved_locations.is_empty() {
         let fallback_path = "UNBIND_ALL.xml";
-        fs::write(fallback_path, &unbind_xml)
-            .map_err(|e| format!("Failed to write unbind profile: {}", e))?;
+        fs::write(fallback_path, &unbind_xml).map_err(|e| {
+            AppError::io(
+                "Failed to write unbind profile",
+                std::path::Path::new(fallback_path),
+                e,
+            )
+        })?;
         saved_locations.push(fallback_path.to_string());
         info!(
             "Saved unbind profile to current directory: {}",
@@ -1246,32 +2213,33 @@ fn generate_unbind_profile(
 }
 
 #[tauri::command]
-fn remove_unbind_profile() -> Result<RemoveUnbindResult, String> {
+fn remove_unbind_profile(base_path: Option<String>) -> Result<RemoveUnbindResult, AppError> {
     use std::fs;
 
     info!("Removing unbind profile files");
 
     let mut removed_count = 0;
 
-    // Get base path for SC installations
-    let base_path = "C:\\Program Files\\Roberts Space Industries\\StarCitizen".to_string();
+    // Use the given base path, or everything discovery can find
+    let installations = match base_path {
+        Some(base_path) => sc_install::scan_base_path(std::path::Path::new(&base_path)),
+        None => Ok(sc_install::discover_installations(&[])),
+    };
 
     // Get SC installations
-    match scan_sc_installations(base_path) {
+    match installations {
         Ok(installations) => {
             for install in installations {
-                let file_path = format!(
-                    "{}\\user\\client\\0\\controls\\mappings\\UNBIND_ALL.xml",
-                    install.path
-                );
+                let file_path = sc_install::mappings_dir(std::path::Path::new(&install.path))
+                    .join("UNBIND_ALL.xml");
 
                 if fs::metadata(&file_path).is_ok() {
                     match fs::remove_file(&file_path) {
                         Ok(_) => {
-                            info!("Removed unbind profile from: {}", file_path);
+                            info!("Removed unbind profile from: {}", file_path.display());
                             removed_count += 1;
                         }
-                        Err(e) => error!("Failed to remove {}: {}", file_path, e),
+                        Err(e) => error!("Failed to remove {}: {}", file_path.display(), e),
                     }
                 }
             }
@@ -1299,14 +2267,25 @@ fn remove_unbind_profile() -> Result<RemoveUnbindResult, String> {
 #[tauri::command]
 fn generate_restore_defaults_profile(
     devices: keybindings::DeviceSelection,
+    options: Option<keybindings::ProfileGeneratorOptions>,
     base_path: String,
     state: tauri::State<Mutex<AppState>>,
-) -> Result<UnbindProfileResult, String> {
+    app_handle: tauri::AppHandle,
+) -> Result<UnbindProfileResult, AppError> {
     use std::fs;
 
+    let backup_root = backup_root(&app_handle)?;
+
     info!(
-        "Generating restore defaults profile for devices: keyboard={}, mouse={}, gamepad={}, js1={}, js2={}",
-        devices.keyboard, devices.mouse, devices.gamepad, devices.joystick1, devices.joystick2
+        "Generating restore defaults profile for devices: keyboard={}, mouse={}, gamepads={:?}, joysticks={:?}",
+        devices.keyboard,
+        devices.mouse,
+        devices
+            .gamepad_instances()
+            .map_err(AppError::InvalidInput)?,
+        devices
+            .joystick_instances()
+            .map_err(AppError::InvalidInput)?
     );
     info!("Using base path: {}", base_path);
 
@@ -1317,10 +2296,14 @@ fn generate_restore_defaults_profile(
     let all_binds = app_state
         .all_binds
         .as_ref()
-        .ok_or("AllBinds not loaded. Please load the keybindings first.")?;
+        .ok_or(AppError::AllBindsNotLoaded)?;
 
     // Generate the restore defaults XML
-    let restore_defaults_xml = keybindings::generate_restore_defaults_xml(all_binds, &devices)?;
+    let restore_defaults_xml = keybindings::generate_restore_defaults_xml(
+        all_binds,
+        &devices,
+        &options.unwrap_or_default(),
+    )?;
 
     info!(
         "Generated restore defaults XML, length: {} bytes",
@@ -1331,7 +2314,7 @@ fn generate_restore_defaults_profile(
     let mut saved_locations = Vec::new();
 
     // Get SC installations
-    match scan_sc_installations(base_path.clone()) {
+    match sc_install::scan_base_path(std::path::Path::new(&base_path)) {
         Ok(installations) => {
             info!("Found {} SC installations", installations.len());
             for install in installations {
@@ -1339,28 +2322,34 @@ fn generate_restore_defaults_profile(
                     "Processing installation: {} at {}",
                     install.name, install.path
                 );
-                let mappings_dir = format!("{}\\user\\client\\0\\controls\\mappings", install.path);
+                let mappings_dir = sc_install::mappings_dir(std::path::Path::new(&install.path));
 
                 // Create directory if it doesn't exist
                 if let Err(e) = fs::create_dir_all(&mappings_dir) {
                     error!(
                         "Failed to create mappings directory {}: {}",
-                        mappings_dir, e
+                        mappings_dir.display(),
+                        e
                     );
                     continue;
                 }
 
-                let file_path = format!("{}\\RESTORE_DEFAULTS.xml", mappings_dir);
-                info!("Attempting to write to: {}", file_path);
-                match fs::write(&file_path, &restore_defaults_xml) {
+                let file_path = mappings_dir.join("RESTORE_DEFAULTS.xml");
+                info!("Attempting to write to: {}", file_path.display());
+                match backup::write_with_backup(
+                    &backup_root,
+                    &file_path,
+                    restore_defaults_xml.as_bytes(),
+                    "restore_defaults_profile",
+                ) {
                     Ok(_) => {
                         info!(
                             "Successfully saved restore defaults profile to: {}",
-                            file_path
+                            file_path.display()
                         );
-                        saved_locations.push(file_path);
+                        saved_locations.push(file_path.to_string_lossy().to_string());
                     }
-                    Err(e) => error!("Failed to write to {}: {}", file_path, e),
+                    Err(e) => error!("Failed to write to {}: {}", file_path.display(), e),
                 }
             }
         }
@@ -1375,8 +2364,13 @@ fn generate_restore_defaults_profile(
     // If no installations found, save to current directory as fallback
     if saved_locations.is_empty() {
         let fallback_path = "RESTORE_DEFAULTS.xml";
-        fs::write(fallback_path, &restore_defaults_xml)
-            .map_err(|e| format!("Failed to write restore defaults profile: {}", e))?;
+        fs::write(fallback_path, &restore_defaults_xml).map_err(|e| {
+            AppError::io(
+                "Failed to write restore defaults profile",
+                std::path::Path::new(fallback_path),
+                e,
+            )
+        })?;
         saved_locations.push(fallback_path.to_string());
         info!(
             "Saved restore defaults profile to current directory: {}",
@@ -1388,32 +2382,38 @@ fn generate_restore_defaults_profile(
 }
 
 #[tauri::command]
-fn remove_restore_defaults_profile() -> Result<RemoveUnbindResult, String> {
+fn remove_restore_defaults_profile(
+    base_path: Option<String>,
+) -> Result<RemoveUnbindResult, AppError> {
     use std::fs;
 
     info!("Removing restore defaults profile files");
 
     let mut removed_count = 0;
 
-    // Get base path for SC installations
-    let base_path = "C:\\Program Files\\Roberts Space Industries\\StarCitizen".to_string();
+    // Use the given base path, or everything discovery can find
+    let installations = match base_path {
+        Some(base_path) => sc_install::scan_base_path(std::path::Path::new(&base_path)),
+        None => Ok(sc_install::discover_installations(&[])),
+    };
 
     // Get SC installations
-    match scan_sc_installations(base_path) {
+    match installations {
         Ok(installations) => {
             for install in installations {
-                let file_path = format!(
-                    "{}\\user\\client\\0\\controls\\mappings\\RESTORE_DEFAULTS.xml",
-                    install.path
-                );
+                let file_path = sc_install::mappings_dir(std::path::Path::new(&install.path))
+                    .join("RESTORE_DEFAULTS.xml");
 
                 if fs::metadata(&file_path).is_ok() {
                     match fs::remove_file(&file_path) {
                         Ok(_) => {
-                            info!("Removed restore defaults profile from: {}", file_path);
+                            info!(
+                                "Removed restore defaults profile from: {}",
+                                file_path.display()
+                            );
                             removed_count += 1;
                         }
-                        Err(e) => error!("Failed to remove {}: {}", file_path, e),
+                        Err(e) => error!("Failed to remove {}: {}", file_path.display(), e),
                     }
                 }
             }
@@ -1439,7 +2439,7 @@ fn remove_restore_defaults_profile() -> Result<RemoveUnbindResult, String> {
 }
 
 #[tauri::command]
-fn scan_character_files(directory_path: String) -> Result<Vec<CharacterFile>, String> {
+fn scan_character_files(directory_path: String) -> Result<Vec<CharacterFile>, AppError> {
     use std::fs;
     use std::time::UNIX_EPOCH;
 
@@ -1452,16 +2452,20 @@ fn scan_character_files(directory_path: String) -> Result<Vec<CharacterFile>, St
     }
 
     if !dir_path.is_dir() {
-        return Err("Path is not a directory".to_string());
+        return Err(AppError::InvalidInput(format!(
+            "Path is not a directory: {}",
+            directory_path
+        )));
     }
 
     let mut characters = Vec::new();
 
     // Read directory entries
-    let entries = fs::read_dir(dir_path).map_err(|e| format!("Failed to read directory: {}", e))?;
+    let entries =
+        fs::read_dir(dir_path).map_err(|e| AppError::io("Failed to read", dir_path, e))?;
 
     for entry in entries {
-        let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
+        let entry = entry.map_err(|e| AppError::io("Failed to read entry in", dir_path, e))?;
         let path = entry.path();
 
         // Only process .chf files
@@ -1471,13 +2475,16 @@ fn scan_character_files(directory_path: String) -> Result<Vec<CharacterFile>, St
                     if let Some(file_name) = path.file_name() {
                         if let Some(name_str) = file_name.to_str() {
                             // Get file metadata
-                            let metadata = fs::metadata(&path)
-                                .map_err(|e| format!("Failed to read metadata: {}", e))?;
+                            let metadata = fs::metadata(&path).map_err(|e| {
+                                AppError::io("Failed to read metadata of", &path, e)
+                            })?;
 
                             let size = metadata.len();
                             let modified = metadata
                                 .modified()
-                                .map_err(|e| format!("Failed to get modified time: {}", e))?
+                                .map_err(|e| {
+                                    AppError::io("Failed to get modified time of", &path, e)
+                                })?
                                 .duration_since(UNIX_EPOCH)
                                 .map_err(|e| format!("Time error: {}", e))?
                                 .as_secs();
@@ -1501,40 +2508,64 @@ fn scan_character_files(directory_path: String) -> Result<Vec<CharacterFile>, St
     Ok(characters)
 }
 
+#[tauri::command]
+fn compare_character_files(
+    library_path: Option<String>,
+    base_path: String,
+) -> Result<Vec<characters::CharacterReport>, AppError> {
+    let installations = sc_install::scan_base_path(std::path::Path::new(&base_path))?;
+    Ok(characters::character_report(
+        library_path.as_deref().map(std::path::Path::new),
+        &installations,
+    ))
+}
+
+// A different file already at the target is only replaced when `overwrite` is true;
+// otherwise the result status is "conflict" and nothing is written
 #[tauri::command]
 fn deploy_character_to_installation(
     character_name: String,
     library_path: String,
     installation_path: String,
-) -> Result<(), String> {
-    use std::fs;
-    use std::path::Path;
-
-    let source_path = Path::new(&library_path).join(&character_name);
-    let target_dir = Path::new(&installation_path)
-        .join("user")
-        .join("client")
-        .join("0")
-        .join("customcharacters");
-
-    // Create target directory if it doesn't exist
-    fs::create_dir_all(&target_dir)
-        .map_err(|e| format!("Failed to create target directory: {}", e))?;
-
-    let target_path = target_dir.join(&character_name);
+    overwrite: Option<bool>,
+    app_handle: tauri::AppHandle,
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> Result<characters::CharacterTransfer, AppError> {
+    file_access::check_file_name(&character_name)?;
+
+    let (library_path, installation_path) = {
+        let file_access = file_access.lock().unwrap();
+        (
+            file_access.resolve_dir(&library_path)?,
+            file_access.resolve_dir(&installation_path)?,
+        )
+    };
+    let source_path = library_path.join(&character_name);
+    let target_path = sc_install::custom_characters_dir(&installation_path).join(&character_name);
 
-    // Copy the file
-    fs::copy(&source_path, &target_path)
-        .map_err(|e| format!("Failed to copy character file: {}", e))?;
+    let status = characters::copy_character(
+        &source_path,
+        &target_path,
+        overwrite.unwrap_or(false),
+        &backup_root(&app_handle)?,
+        "deploy_character",
+    )?;
 
     info!(
-        "Deployed character {} from {} to {}",
+        "Deploy character {} from {} to {}: {:?}",
         character_name,
         source_path.display(),
-        target_path.display()
+        target_path.display(),
+        status
     );
 
-    Ok(())
+    Ok(characters::CharacterTransfer {
+        name: character_name,
+        source_path: source_path.to_string_lossy().to_string(),
+        target_path: target_path.to_string_lossy().to_string(),
+        status,
+        error: None,
+    })
 }
 
 #[tauri::command]
@@ -1542,81 +2573,138 @@ fn import_character_to_library(
     character_name: String,
     installation_path: String,
     library_path: String,
-) -> Result<(), String> {
-    use std::fs;
-    use std::path::Path;
-
-    let source_path = Path::new(&installation_path)
-        .join("user")
-        .join("client")
-        .join("0")
-        .join("customcharacters")
-        .join(&character_name);
-
-    let target_dir = Path::new(&library_path);
-
-    // Create library directory if it doesn't exist
-    fs::create_dir_all(&target_dir)
-        .map_err(|e| format!("Failed to create library directory: {}", e))?;
-
-    let target_path = target_dir.join(&character_name);
+    overwrite: Option<bool>,
+    app_handle: tauri::AppHandle,
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> Result<characters::CharacterTransfer, AppError> {
+    file_access::check_file_name(&character_name)?;
+
+    let (installation_path, library_path) = {
+        let file_access = file_access.lock().unwrap();
+        (
+            file_access.resolve_dir(&installation_path)?,
+            file_access.resolve_dir(&library_path)?,
+        )
+    };
+    let source_path = sc_install::custom_characters_dir(&installation_path).join(&character_name);
+    let target_path = library_path.join(&character_name);
 
-    // Copy the file
-    fs::copy(&source_path, &target_path)
-        .map_err(|e| format!("Failed to copy character file: {}", e))?;
+    let status = characters::copy_character(
+        &source_path,
+        &target_path,
+        overwrite.unwrap_or(false),
+        &backup_root(&app_handle)?,
+        "import_character",
+    )?;
 
     info!(
-        "Imported character {} from {} to {}",
+        "Import character {} from {} to {}: {:?}",
         character_name,
         source_path.display(),
-        target_path.display()
+        target_path.display(),
+        status
     );
 
-    Ok(())
+    Ok(characters::CharacterTransfer {
+        name: character_name,
+        source_path: source_path.to_string_lossy().to_string(),
+        target_path: target_path.to_string_lossy().to_string(),
+        status,
+        error: None,
+    })
+}
+
+// Copy every library character into every installation under `base_path`
+// Call without `overwrite` first; "conflict" results list the files that would be replaced
+#[tauri::command]
+fn sync_character_library(
+    library_path: String,
+    base_path: String,
+    overwrite: Option<bool>,
+    app_handle: tauri::AppHandle,
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> Result<Vec<characters::CharacterTransfer>, AppError> {
+    let (library_path, base_path) = {
+        let file_access = file_access.lock().unwrap();
+        (
+            file_access.resolve_dir(&library_path)?,
+            file_access.resolve_dir(&base_path)?,
+        )
+    };
+    let installations = sc_install::scan_base_path(&base_path)?;
+    let results = characters::sync_library(
+        &library_path,
+        &installations,
+        overwrite.unwrap_or(false),
+        &backup_root(&app_handle)?,
+    );
+
+    info!(
+        "Synced character library to {} installation(s): {} result(s)",
+        installations.len(),
+        results.len()
+    );
+    Ok(results)
 }
 
 // ===== HID Debug Commands =====
 
 #[tauri::command]
-fn list_hid_devices() -> Result<Vec<hid_reader::HidDeviceListItem>, String> {
-    hid_reader::list_hid_game_controllers()
+fn list_hid_devices(
+    hid: tauri::State<hid_service::HidService>,
+) -> Result<Vec<hid_reader::HidDeviceListItem>, AppError> {
+    hid.devices().map_err(AppError::Hid)
 }
 
 #[tauri::command]
-fn read_hid_device_report(device_path: String, timeout_ms: Option<i32>) -> Result<Vec<u8>, String> {
+fn read_hid_device_report(
+    device_path: String,
+    timeout_ms: Option<i32>,
+    hid: tauri::State<hid_service::HidService>,
+) -> Result<Vec<u8>, AppError> {
     let timeout = timeout_ms.unwrap_or(50);
-    hid_reader::read_hid_report(&device_path, timeout)
+    hid.read_report(&device_path, timeout)
+        .map_err(AppError::Hid)
 }
 
 #[tauri::command]
 fn parse_hid_report(
     report: Vec<u8>,
     device_path: String,
-) -> Result<hid_reader::HidAxisReport, String> {
-    // Use descriptor-based parsing for accuracy
-    // Get descriptor once and parse
-    let descriptor = hid_reader::get_hid_descriptor_bytes(&device_path)?;
-    hid_reader::parse_hid_axes_from_descriptor_bytes(&report, &descriptor)
+    hid: tauri::State<hid_service::HidService>,
+) -> Result<hid_reader::HidAxisReport, AppError> {
+    // The descriptor is fetched and compiled once per device, then cached
+    let parser = hid.parser(&device_path).map_err(AppError::Hid)?;
+    parser
+        .parse(&report)
+        .map(hid_reader::HidAxisReport::from)
+        .map_err(AppError::Hid)
 }
 
 #[tauri::command]
 fn parse_hid_report_with_descriptor(
     report: Vec<u8>,
     descriptor: Vec<u8>,
-) -> Result<hid_reader::HidAxisReport, String> {
-    hid_reader::parse_hid_axes_from_descriptor_bytes(&report, &descriptor)
+) -> Result<hid_reader::HidAxisReport, AppError> {
+    hid_reader::parse_hid_axes_from_descriptor_bytes(&report, &descriptor).map_err(AppError::Hid)
 }
 
 #[tauri::command]
-fn get_hid_descriptor_bytes(device_path: String) -> Result<Vec<u8>, String> {
-    hid_reader::get_hid_descriptor_bytes(&device_path)
+fn get_hid_descriptor_bytes(
+    device_path: String,
+    hid: tauri::State<hid_service::HidService>,
+) -> Result<Vec<u8>, AppError> {
+    hid.descriptor(&device_path).map_err(AppError::Hid)
 }
 
 #[tauri::command]
 fn get_hid_axis_names(
     device_path: String,
-) -> Result<std::collections::HashMap<u32, String>, String> {
-    hid_reader::get_axis_names_from_descriptor(&device_path)
+    hid: tauri::State<hid_service::HidService>,
+) -> Result<std::collections::HashMap<u32, String>, AppError> {
+    hid.descriptor(&device_path)
+        .and_then(|d| hid_reader::get_axis_names_from_descriptor(&d))
+        .map_err(AppError::Hid)
 }
 
 fn find_matching_hid_device(
@@ -1696,9 +2784,13 @@ fn find_matching_hid_device(
 }
 
 #[tauri::command]
-fn get_hid_device_path(device_name: String) -> Result<Option<String>, String> {
-    let hid_devices = hid_reader::list_hid_game_controllers()
-        .map_err(|e| format!("Failed to list HID devices: {}", e))?;
+fn get_hid_device_path(
+    device_name: String,
+    hid: tauri::State<hid_service::HidService>,
+) -> Result<Option<String>, AppError> {
+    let hid_devices = hid
+        .devices()
+        .map_err(|e| AppError::Hid(format!("Failed to list HID devices: {}", e)))?;
 
     if let Some(device) = find_matching_hid_device(&device_name, &hid_devices) {
         Ok(Some(device.path))
@@ -1710,12 +2802,14 @@ fn get_hid_device_path(device_name: String) -> Result<Option<String>, String> {
 #[tauri::command]
 fn get_axis_names_for_device(
     device_name: String,
-) -> Result<std::collections::HashMap<u32, String>, String> {
+    hid: tauri::State<hid_service::HidService>,
+) -> Result<std::collections::HashMap<u32, String>, AppError> {
     // Try to find a matching HID device by name
     // This helps bridge the gap between DirectInput devices and HID devices
 
-    let hid_devices = hid_reader::list_hid_game_controllers()
-        .map_err(|e| format!("Failed to list HID devices: {}", e))?;
+    let hid_devices = hid
+        .devices()
+        .map_err(|e| AppError::Hid(format!("Failed to list HID devices: {}", e)))?;
 
     eprintln!(
         "[Axis Names] Looking for device matching: '{}'",
@@ -1735,25 +2829,29 @@ fn get_axis_names_for_device(
             "[Axis Names] Found HID device for '{}': {:?}",
             device_name, device.product
         );
-        hid_reader::get_axis_names_from_descriptor(&device.path)
+        hid.descriptor(&device.path)
+            .and_then(|d| hid_reader::get_axis_names_from_descriptor(&d))
+            .map_err(AppError::Hid)
     } else {
         eprintln!(
             "[Axis Names] No matching HID device found for '{}'",
             device_name
         );
-        Err(format!(
+        Err(AppError::NotFound(format!(
             "No HID device found matching name: {}",
             device_name
-        ))
+        )))
     }
 }
 
 #[tauri::command]
 fn get_directinput_to_hid_mapping(
     device_name: String,
-) -> Result<std::collections::HashMap<u32, u32>, String> {
-    let hid_devices = hid_reader::list_hid_game_controllers()
-        .map_err(|e| format!("Failed to list HID devices: {}", e))?;
+    hid: tauri::State<hid_service::HidService>,
+) -> Result<std::collections::HashMap<u32, u32>, AppError> {
+    let hid_devices = hid
+        .devices()
+        .map_err(|e| AppError::Hid(format!("Failed to list HID devices: {}", e)))?;
 
     eprintln!(
         "[Axis Mapping] Looking for device matching: '{}'",
@@ -1765,16 +2863,18 @@ fn get_directinput_to_hid_mapping(
             "[Axis Mapping] Found HID device for '{}': {:?}",
             device_name, device.product
         );
-        hid_reader::get_directinput_to_hid_axis_mapping(&device.path)
+        hid.descriptor(&device.path)
+            .and_then(|d| hid_reader::get_directinput_to_hid_axis_mapping(&d))
+            .map_err(AppError::Hid)
     } else {
         eprintln!(
             "[Axis Mapping] No matching HID device found for '{}'",
             device_name
         );
-        Err(format!(
+        Err(AppError::NotFound(format!(
             "No HID device found matching name: {}",
             device_name
-        ))
+        )))
     }
 }
 
@@ -1784,14 +2884,20 @@ fn get_directinput_to_hid_mapping(
 fn delete_character_from_library(
     character_name: String,
     library_path: String,
-) -> Result<(), String> {
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> Result<(), AppError> {
     use std::fs;
     use std::path::Path;
 
-    let file_path = Path::new(&library_path).join(&character_name);
+    let file_path = file_access.lock().unwrap().resolve_in(
+        Path::new(&library_path),
+        &character_name,
+        &["chf"],
+    )?;
 
     // Delete the file
-    fs::remove_file(&file_path).map_err(|e| format!("Failed to delete character file: {}", e))?;
+    fs::remove_file(&file_path)
+        .map_err(|e| AppError::io("Failed to delete character file", &file_path, e))?;
 
     info!("Deleted character {} from library", character_name);
 
@@ -1802,22 +2908,22 @@ fn delete_character_from_library(
 fn delete_character_from_installation(
     character_name: String,
     installation_path: String,
-) -> Result<(), String> {
+    file_access: tauri::State<Mutex<file_access::FileAccess>>,
+) -> Result<(), AppError> {
     use std::fs;
     use std::path::Path;
 
     // Build path to character file in installation
     // Path format: {install}\user\client\0\customcharacters\{character_name}
-    let char_file_path = Path::new(&installation_path)
-        .join("user")
-        .join("client")
-        .join("0")
-        .join("customcharacters")
-        .join(&character_name);
+    let char_file_path = file_access.lock().unwrap().resolve_in(
+        &sc_install::custom_characters_dir(Path::new(&installation_path)),
+        &character_name,
+        &["chf"],
+    )?;
 
     // Delete the file
     fs::remove_file(&char_file_path)
-        .map_err(|e| format!("Failed to delete character file: {}", e))?;
+        .map_err(|e| AppError::io("Failed to delete character file", &char_file_path, e))?;
 
     info!("Deleted character {} from installation", character_name);
 
@@ -1830,14 +2936,26 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .manage(Mutex::new(AppState::new()))
+        .manage(Mutex::new(watcher::FileWatcher::default()))
+        .manage(Mutex::new(file_access::FileAccess::default()))
+        .manage(hid_service::HidService::default())
+        .manage(Mutex::new(input_stream::InputStream::default()))
         .invoke_handler(tauri::generate_handler![
             get_app_version,
             greet,
             detect_joysticks,
             get_connected_devices,
+            get_device_database,
+            save_user_device,
+            remove_user_device,
             detect_axis_movement,
             wait_for_input_binding,
             wait_for_inputs_with_events,
+            keyboard_available,
+            record_input,
+            replay_input_recording,
+            start_input_stream,
+            stop_input_stream,
             load_keybindings,
             update_binding,
             reset_binding,
@@ -1845,6 +2963,13 @@ pub fn run() {
             export_keybindings,
             save_template,
             load_template,
+            match_device_templates,
+            generate_control_map,
+            mirror_joystick_bindings,
+            transplant_bindings,
+            start_file_watch,
+            stop_file_watch,
+            resolve_external_change,
             load_all_binds,
             get_all_binds_xml,
             get_merged_bindings,
@@ -1854,19 +2979,38 @@ pub fn run() {
             clear_specific_binding,
             clear_custom_bindings,
             scan_sc_installations,
+            discover_sc_installations,
             get_current_file_name,
+            get_session_state,
             save_bindings_to_install,
+            save_bindings_to_installs,
+            list_backups,
+            restore_backup,
+            list_library_profiles,
+            import_profile_to_library,
+            save_current_profile_to_library,
+            duplicate_library_profile,
+            rename_library_profile,
+            update_library_profile_metadata,
+            delete_profile_from_library,
+            deploy_profile_to_installation,
             write_binary_file,
+            choose_folder,
+            choose_file,
+            get_allowed_folders,
             log_error,
             log_info,
             get_log_file_path,
             get_resource_dir,
             open_url,
+            get_default_protected_actions,
             generate_unbind_profile,
             remove_unbind_profile,
             generate_restore_defaults_profile,
             remove_restore_defaults_profile,
             scan_character_files,
+            compare_character_files,
+            sync_character_library,
             deploy_character_to_installation,
             import_character_to_library,
             delete_character_from_library,
@@ -1887,6 +3031,46 @@ pub fn run() {
                 eprintln!("Failed to set up logging: {}", e);
             }
 
+            // Recover the last session (loaded file and unsaved edits)
+            match app.path().app_data_dir() {
+                Ok(data_dir) => {
+                    let state = app.state::<Mutex<AppState>>();
+                    let result = state
+                        .lock()
+                        .unwrap()
+                        .recover_session(&data_dir.join("session"));
+                    if let Err(e) = result {
+                        error!("Failed to recover session: {}", e);
+                    }
+                }
+                Err(e) => error!("Failed to get app data directory: {}", e),
+            }
+
+            // Known devices, with the user's additions from app data
+            match (resource_dir(app.handle()), app.path().app_data_dir()) {
+                (Ok(resources), Ok(data_dir)) => device_database::init(
+                    &resources.join(device_database::DATABASE_FILE),
+                    data_dir.join(device_database::DATABASE_FILE),
+                ),
+                _ => error!("Failed to locate the device database"),
+            }
+
+            // Watch for controllers being plugged in or out
+            app.state::<hid_service::HidService>()
+                .start(app.handle().clone());
+
+            // Folders the file commands may touch
+            match app.path().app_data_dir() {
+                Ok(data_dir) => {
+                    let resources = resource_dir(app.handle()).ok();
+                    app.state::<Mutex<file_access::FileAccess>>()
+                        .lock()
+                        .unwrap()
+                        .init(data_dir, resources);
+                }
+                Err(e) => error!("Failed to get app data directory: {}", e),
+            }
+
             Ok(())
         })
         .build(tauri::generate_context!())
@@ -1897,3 +3081,40 @@ pub fn run() {
             }
         });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saving_unchanged_bindings_twice_reports_unchanged() {
+        let install = tempfile::tempdir().unwrap();
+        let backups = tempfile::tempdir().unwrap();
+        // Actions with a rebind for several device types, written in one order per type
+        let bindings = ActionMaps::from_xml(
+            r#"<ActionMaps profileName="test">
+ <actionmap name="spaceship_weapons">
+  <action name="v_attack1"><rebind input="kb1_space"/><rebind input="js1_button1"/><rebind input="gp1_a"/></action>
+  <action name="v_attack2"><rebind input="js2_button2"/><rebind input="kb1_f"/><rebind input="gp1_b"/></action>
+  <action name="v_attack3"><rebind input="gp1_x"/><rebind input="kb1_g"/><rebind input="js1_button3"/></action>
+ </actionmap>
+</ActionMaps>"#,
+        )
+        .unwrap();
+
+        let save = || {
+            write_bindings_to_install(
+                install.path(),
+                "layout_test_exported.xml",
+                &bindings.to_xml_with_categories(None),
+                backups.path(),
+            )
+            .unwrap()
+        };
+        assert!(save());
+        for _ in 0..10 {
+            assert!(!save());
+        }
+        assert!(backup::list_backups(backups.path()).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,257 @@
+use crate::backup;
+use crate::error::AppError;
+use crate::keybindings::ActionMaps;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const META_FILE: &str = "profile.json";
+const LAYOUT_FILE: &str = "layout.xml";
+
+/// Metadata stored next to each layout in the library
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProfileMetadata {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Devices the layout is made for, e.g. "VKB Gladiator NXT EVO (L)"
+    #[serde(default)]
+    pub target_devices: Vec<String>,
+    #[serde(default)]
+    pub notes: String,
+    /// RFC 3339 timestamps
+    pub created: String,
+    pub modified: String,
+}
+
+/// A library entry as shown in the UI
+#[derive(Debug, Serialize, Clone)]
+pub struct ProfileEntry {
+    #[serde(flatten)]
+    pub metadata: ProfileMetadata,
+    pub layout_path: String,
+}
+
+/// Editable metadata fields; None leaves a field unchanged
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ProfileMetadataUpdate {
+    pub tags: Option<Vec<String>>,
+    pub target_devices: Option<Vec<String>>,
+    pub notes: Option<String>,
+}
+
+/// Layout file name used when deploying a profile into an installation
+/// (also used as the profile name inside the XML)
+pub fn layout_file_stem(name: &str) -> String {
+    let stem: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() || c == '-' || c == '_' => c,
+            _ => '_',
+        })
+        .collect();
+
+    if stem.is_empty() {
+        "Profile".to_string()
+    } else {
+        stem
+    }
+}
+
+fn now() -> String {
+    chrono::Local::now().to_rfc3339()
+}
+
+fn profile_dir(library: &Path, id: &str) -> Result<PathBuf, AppError> {
+    // Ids are folder names we generated; refuse anything that could leave the library
+    if id.is_empty() || id.contains(['/', '\\']) || id.contains("..") {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid profile id: {}",
+            id
+        )));
+    }
+    let dir = library.join(id);
+    if !dir.join(META_FILE).is_file() {
+        return Err(AppError::NotFound(format!("Profile not found: {}", id)));
+    }
+    Ok(dir)
+}
+
+fn read_metadata(dir: &Path) -> Result<ProfileMetadata, AppError> {
+    let path = dir.join(META_FILE);
+    let json = fs::read_to_string(&path)
+        .map_err(|e| AppError::io("Failed to read profile metadata", &path, e))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+fn read_layout(dir: &Path) -> Result<String, AppError> {
+    let path = dir.join(LAYOUT_FILE);
+    fs::read_to_string(&path).map_err(|e| AppError::io("Failed to read profile layout", &path, e))
+}
+
+fn write_metadata(dir: &Path, metadata: &ProfileMetadata) -> Result<(), AppError> {
+    let json = serde_json::to_string_pretty(metadata)?;
+    backup::write_atomic(&dir.join(META_FILE), json.as_bytes())
+}
+
+fn entry(dir: &Path, metadata: ProfileMetadata) -> ProfileEntry {
+    ProfileEntry {
+        metadata,
+        layout_path: dir.join(LAYOUT_FILE).to_string_lossy().to_string(),
+    }
+}
+
+/// Create a new profile folder holding `layout_xml`
+fn create_profile(
+    library: &Path,
+    name: &str,
+    layout_xml: &str,
+    template: Option<&ProfileMetadata>,
+) -> Result<ProfileEntry, AppError> {
+    // Make sure it's a layout we can actually read
+    ActionMaps::from_xml(layout_xml)?;
+
+    let stamp = chrono::Local::now().format("%Y%m%d%H%M%S%3f").to_string();
+    let mut id = stamp.clone();
+    let mut counter = 1;
+    while library.join(&id).exists() {
+        id = format!("{}-{}", stamp, counter);
+        counter += 1;
+    }
+
+    let dir = library.join(&id);
+    fs::create_dir_all(&dir)
+        .map_err(|e| AppError::io("Failed to create profile folder", &dir, e))?;
+    backup::write_atomic(&dir.join(LAYOUT_FILE), layout_xml.as_bytes())?;
+
+    let created = now();
+    let metadata = ProfileMetadata {
+        id,
+        name: name.trim().to_string(),
+        tags: template.map(|t| t.tags.clone()).unwrap_or_default(),
+        target_devices: template
+            .map(|t| t.target_devices.clone())
+            .unwrap_or_default(),
+        notes: template.map(|t| t.notes.clone()).unwrap_or_default(),
+        created: created.clone(),
+        modified: created,
+    };
+    write_metadata(&dir, &metadata)?;
+
+    info!(
+        "Added profile '{}' ({}) to library",
+        metadata.name, metadata.id
+    );
+    Ok(entry(&dir, metadata))
+}
+
+/// All profiles in the library, sorted by name
+pub fn list_profiles(library: &Path) -> Result<Vec<ProfileEntry>, AppError> {
+    let entries = match fs::read_dir(library) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(AppError::io("Failed to read profile library", library, e)),
+    };
+
+    let mut profiles: Vec<ProfileEntry> = entries
+        .flatten()
+        .filter_map(|e| {
+            let dir = e.path();
+            read_metadata(&dir).ok().map(|m| entry(&dir, m))
+        })
+        .collect();
+
+    profiles.sort_by(|a, b| {
+        a.metadata
+            .name
+            .to_lowercase()
+            .cmp(&b.metadata.name.to_lowercase())
+    });
+    Ok(profiles)
+}
+
+/// Copy a layout file from anywhere on disk into the library
+pub fn import_profile(
+    library: &Path,
+    file_path: &Path,
+    name: Option<String>,
+) -> Result<ProfileEntry, AppError> {
+    let xml =
+        fs::read_to_string(file_path).map_err(|e| AppError::io("Failed to read", file_path, e))?;
+    let name = name.unwrap_or_else(|| {
+        file_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "Imported Profile".to_string())
+    });
+    create_profile(library, &name, &xml, None)
+}
+
+/// Add a layout that is already in memory (e.g. the one being edited)
+pub fn add_profile(library: &Path, name: &str, layout_xml: &str) -> Result<ProfileEntry, AppError> {
+    create_profile(library, name, layout_xml, None)
+}
+
+pub fn duplicate_profile(
+    library: &Path,
+    id: &str,
+    new_name: &str,
+) -> Result<ProfileEntry, AppError> {
+    let dir = profile_dir(library, id)?;
+    let metadata = read_metadata(&dir)?;
+    let xml = read_layout(&dir)?;
+    create_profile(library, new_name, &xml, Some(&metadata))
+}
+
+pub fn rename_profile(library: &Path, id: &str, new_name: &str) -> Result<ProfileEntry, AppError> {
+    if new_name.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "Profile name cannot be empty".to_string(),
+        ));
+    }
+    let dir = profile_dir(library, id)?;
+    let mut metadata = read_metadata(&dir)?;
+    metadata.name = new_name.trim().to_string();
+    metadata.modified = now();
+    write_metadata(&dir, &metadata)?;
+    Ok(entry(&dir, metadata))
+}
+
+pub fn update_profile_metadata(
+    library: &Path,
+    id: &str,
+    update: ProfileMetadataUpdate,
+) -> Result<ProfileEntry, AppError> {
+    let dir = profile_dir(library, id)?;
+    let mut metadata = read_metadata(&dir)?;
+    if let Some(tags) = update.tags {
+        metadata.tags = tags;
+    }
+    if let Some(target_devices) = update.target_devices {
+        metadata.target_devices = target_devices;
+    }
+    if let Some(notes) = update.notes {
+        metadata.notes = notes;
+    }
+    metadata.modified = now();
+    write_metadata(&dir, &metadata)?;
+    Ok(entry(&dir, metadata))
+}
+
+pub fn delete_profile(library: &Path, id: &str) -> Result<(), AppError> {
+    let dir = profile_dir(library, id)?;
+    fs::remove_dir_all(&dir).map_err(|e| AppError::io("Failed to delete profile", &dir, e))?;
+    info!("Deleted profile {} from library", id);
+    Ok(())
+}
+
+/// Parse a profile's layout for deploying or loading
+pub fn load_profile(library: &Path, id: &str) -> Result<(ProfileMetadata, ActionMaps), AppError> {
+    let dir = profile_dir(library, id)?;
+    let metadata = read_metadata(&dir)?;
+    let xml = read_layout(&dir)?;
+    Ok((metadata, ActionMaps::from_xml(&xml)?))
+}
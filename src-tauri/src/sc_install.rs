@@ -0,0 +1,477 @@
+use crate::error::AppError;
+use log::{info, warn};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Channels in the order the UI should list them; anything else found on disk follows
+const KNOWN_CHANNELS: [&str; 4] = ["LIVE", "PTU", "EPTU", "TECH-PREVIEW"];
+
+/// Folder the launcher creates inside a library folder
+const GAME_FOLDER: &str = "StarCitizen";
+
+/// A Star Citizen channel folder (LIVE, PTU, ...) that contains `data.p4k`
+#[derive(Debug, serde::Serialize, Clone)]
+pub struct ScInstallation {
+    pub name: String,
+    pub path: String,
+}
+
+/// `INSTALL/user/client/0`
+pub fn user_client_dir(installation: &Path) -> PathBuf {
+    installation.join("user").join("client").join("0")
+}
+
+/// `INSTALL/user/client/0/controls/mappings`
+pub fn mappings_dir(installation: &Path) -> PathBuf {
+    user_client_dir(installation)
+        .join("controls")
+        .join("mappings")
+}
+
+/// `INSTALL/user/client/0/customcharacters`
+pub fn custom_characters_dir(installation: &Path) -> PathBuf {
+    user_client_dir(installation).join("customcharacters")
+}
+
+fn is_channel_dir(path: &Path) -> bool {
+    path.is_dir() && path.join("data.p4k").is_file()
+}
+
+fn channel_order(name: &str) -> usize {
+    KNOWN_CHANNELS
+        .iter()
+        .position(|c| c.eq_ignore_ascii_case(name))
+        .unwrap_or(KNOWN_CHANNELS.len())
+}
+
+/// Scan a StarCitizen folder for channel folders containing `data.p4k`
+/// Any channel name is accepted; LIVE/PTU/EPTU/TECH-PREVIEW sort first
+pub fn scan_base_path(base: &Path) -> Result<Vec<ScInstallation>, AppError> {
+    if !base.exists() {
+        return Err(AppError::NotFound(format!(
+            "Directory does not exist: {}",
+            base.display()
+        )));
+    }
+    if !base.is_dir() {
+        return Err(AppError::InvalidInput(format!(
+            "Path is not a directory: {}",
+            base.display()
+        )));
+    }
+
+    let entries =
+        fs::read_dir(base).map_err(|e| AppError::io("Failed to read directory", base, e))?;
+
+    let mut installations: Vec<ScInstallation> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_channel_dir(path))
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy().to_string();
+            Some(ScInstallation {
+                name,
+                path: path.to_string_lossy().to_string(),
+            })
+        })
+        .collect();
+
+    installations.sort_by(|a, b| {
+        channel_order(&a.name)
+            .cmp(&channel_order(&b.name))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    Ok(installations)
+}
+
+/// Turn a library folder (or a StarCitizen / channel folder) into the StarCitizen base folder
+fn resolve_base(candidate: &Path) -> Option<PathBuf> {
+    if is_channel_dir(candidate) {
+        return candidate.parent().map(Path::to_path_buf);
+    }
+    let game = candidate.join(GAME_FOLDER);
+    if game.is_dir() {
+        return Some(game);
+    }
+    if candidate.is_dir()
+        && candidate
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().eq_ignore_ascii_case(GAME_FOLDER))
+    {
+        return Some(candidate.to_path_buf());
+    }
+    None
+}
+
+/// Roots that may contain a Windows file system: the real one on Windows,
+/// Wine/Proton prefixes (their `drive_c`) elsewhere
+fn windows_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+
+    if cfg!(windows) {
+        roots.push(PathBuf::from("C:\\"));
+        return roots;
+    }
+
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let mut prefixes: Vec<PathBuf> = Vec::new();
+
+    if let Some(prefix) = std::env::var_os("WINEPREFIX") {
+        prefixes.push(PathBuf::from(prefix));
+    }
+
+    if let Some(home) = &home {
+        prefixes.push(home.join(".wine"));
+        // Default prefix of the LUG helper / Lutris installer
+        prefixes.push(home.join("Games").join("star-citizen"));
+
+        // Proton prefixes for non-Steam games added to Steam
+        for steam in [
+            home.join(".steam").join("steam"),
+            home.join(".local").join("share").join("Steam"),
+        ] {
+            if let Ok(entries) = fs::read_dir(steam.join("steamapps").join("compatdata")) {
+                prefixes.extend(entries.flatten().map(|e| e.path().join("pfx")));
+            }
+        }
+    }
+
+    roots.extend(
+        prefixes
+            .into_iter()
+            .map(|prefix| prefix.join("drive_c"))
+            .filter(|drive| drive.is_dir()),
+    );
+    roots
+}
+
+/// RSI Launcher data folders (`AppData/Roaming/rsilauncher`) reachable from a root
+fn launcher_data_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if cfg!(windows) {
+        if let Some(appdata) = std::env::var_os("APPDATA") {
+            dirs.push(PathBuf::from(appdata).join("rsilauncher"));
+        }
+        return dirs;
+    }
+
+    if let Ok(users) = fs::read_dir(root.join("users")) {
+        for user in users.flatten() {
+            dirs.push(
+                user.path()
+                    .join("AppData")
+                    .join("Roaming")
+                    .join("rsilauncher"),
+            );
+        }
+    }
+    dirs
+}
+
+/// Collect string values stored under keys mentioning "library" in launcher JSON settings
+fn collect_library_values(value: &serde_json::Value, in_library_key: bool, out: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(s) if in_library_key => out.push(s.clone()),
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_library_values(item, in_library_key, out);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (key, item) in map {
+                let is_library = in_library_key || key.to_lowercase().contains("library");
+                collect_library_values(item, is_library, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Paths from the launcher log: "Launching Star Citizen LIVE from (C:\...\StarCitizen\LIVE)"
+fn collect_log_paths(log: &str, out: &mut Vec<String>) {
+    for line in log.lines() {
+        let Some(start) = line.find("Launching Star Citizen") else {
+            continue;
+        };
+        let rest = &line[start..];
+        if let (Some(open), Some(close)) = (rest.find(" from ("), rest.rfind(')')) {
+            let path = &rest[open + " from (".len()..close];
+            if !path.is_empty() {
+                out.push(path.to_string());
+            }
+        }
+    }
+}
+
+/// Map a Windows path from the launcher settings onto the root it was found under
+/// ("C:\Games\RSI" inside a Wine prefix becomes "<prefix>/drive_c/Games/RSI")
+fn map_launcher_path(root: &Path, raw: &str) -> PathBuf {
+    if cfg!(windows) {
+        return PathBuf::from(raw);
+    }
+
+    let normalized = raw.replace('\\', "/");
+    match normalized.split_once(":/") {
+        Some((drive, rest)) if drive.eq_ignore_ascii_case("c") => rest
+            .split('/')
+            .filter(|p| !p.is_empty())
+            .fold(root.to_path_buf(), |path, part| path.join(part)),
+        Some((drive, rest)) => {
+            // Other drive letters live next to drive_c as dosdevices links
+            let dosdevice = root
+                .parent()
+                .unwrap_or(root)
+                .join("dosdevices")
+                .join(format!("{}:", drive.to_lowercase()));
+            rest.split('/')
+                .filter(|p| !p.is_empty())
+                .fold(dosdevice, |path, part| path.join(part))
+        }
+        None => PathBuf::from(normalized),
+    }
+}
+
+/// Library folders configured in the RSI Launcher under a given root
+pub fn launcher_library_folders(root: &Path) -> Vec<PathBuf> {
+    let mut raw_paths = Vec::new();
+
+    for data_dir in launcher_data_dirs(root) {
+        let Ok(entries) = fs::read_dir(&data_dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == "json") {
+                match fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+                {
+                    Some(json) => collect_library_values(&json, false, &mut raw_paths),
+                    None => warn!("Could not read launcher settings {}", path.display()),
+                }
+            }
+        }
+
+        if let Ok(log) = fs::read_to_string(data_dir.join("logs").join("log.log")) {
+            collect_log_paths(&log, &mut raw_paths);
+        }
+    }
+
+    raw_paths
+        .iter()
+        .map(|raw| map_launcher_path(root, raw))
+        .collect()
+}
+
+/// Find every Star Citizen installation we can: launcher library folders, the default
+/// install location, Wine/Proton prefixes and any extra base folders passed in
+pub fn discover_installations(extra_bases: &[PathBuf]) -> Vec<ScInstallation> {
+    let mut candidates: Vec<PathBuf> = extra_bases.to_vec();
+
+    for root in windows_roots() {
+        candidates.extend(launcher_library_folders(&root));
+        candidates.push(
+            root.join("Program Files")
+                .join("Roberts Space Industries")
+                .join(GAME_FOLDER),
+        );
+    }
+
+    let mut seen_bases = HashSet::new();
+    let mut seen_installs = HashSet::new();
+    let mut installations = Vec::new();
+
+    for candidate in candidates {
+        let Some(base) = resolve_base(&candidate) else {
+            continue;
+        };
+        let key = fs::canonicalize(&base).unwrap_or_else(|_| base.clone());
+        if !seen_bases.insert(key) {
+            continue;
+        }
+
+        if let Ok(found) = scan_base_path(&base) {
+            for install in found {
+                let key = fs::canonicalize(&install.path)
+                    .unwrap_or_else(|_| PathBuf::from(&install.path));
+                if seen_installs.insert(key) {
+                    installations.push(install);
+                }
+            }
+        }
+    }
+
+    info!(
+        "Discovered {} Star Citizen installation(s)",
+        installations.len()
+    );
+    installations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, b"").unwrap();
+    }
+
+    fn channel(base: &Path, name: &str) -> PathBuf {
+        let dir = base.join(name);
+        touch(&dir.join("data.p4k"));
+        dir
+    }
+
+    fn names(installations: &[ScInstallation]) -> Vec<&str> {
+        installations.iter().map(|i| i.name.as_str()).collect()
+    }
+
+    #[test]
+    fn scan_base_path_orders_known_channels_first() {
+        let tmp = TempDir::new().unwrap();
+        let base = tmp.path().join(GAME_FOLDER);
+        for name in ["HOTFIX", "PTU", "LIVE", "TECH-PREVIEW", "EPTU"] {
+            channel(&base, name);
+        }
+        // No data.p4k: not a channel
+        fs::create_dir_all(base.join("Logs")).unwrap();
+        touch(&base.join("readme.txt"));
+
+        let found = scan_base_path(&base).unwrap();
+        assert_eq!(
+            names(&found),
+            ["LIVE", "PTU", "EPTU", "TECH-PREVIEW", "HOTFIX"]
+        );
+        assert_eq!(PathBuf::from(&found[0].path), base.join("LIVE"));
+    }
+
+    #[test]
+    fn scan_base_path_rejects_missing_and_file_paths() {
+        let tmp = TempDir::new().unwrap();
+        let file = tmp.path().join("file.txt");
+        touch(&file);
+
+        assert!(scan_base_path(&tmp.path().join("missing")).is_err());
+        assert!(scan_base_path(&file).is_err());
+        assert!(scan_base_path(tmp.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn resolve_base_accepts_library_game_and_channel_folders() {
+        let tmp = TempDir::new().unwrap();
+        let base = tmp.path().join(GAME_FOLDER);
+        let live = channel(&base, "LIVE");
+
+        assert_eq!(resolve_base(tmp.path()), Some(base.clone()));
+        assert_eq!(resolve_base(&base), Some(base.clone()));
+        assert_eq!(resolve_base(&live), Some(base));
+        assert_eq!(resolve_base(&tmp.path().join("missing")), None);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn map_launcher_path_maps_into_wine_prefix() {
+        let prefix = Path::new("/home/pilot/Games/star-citizen");
+        let drive_c = prefix.join("drive_c");
+
+        assert_eq!(
+            map_launcher_path(&drive_c, "C:\\Program Files\\Roberts Space Industries"),
+            drive_c
+                .join("Program Files")
+                .join("Roberts Space Industries")
+        );
+        assert_eq!(
+            map_launcher_path(&drive_c, "c:/Games//RSI/"),
+            drive_c.join("Games").join("RSI")
+        );
+        assert_eq!(
+            map_launcher_path(&drive_c, "D:\\Games\\RSI"),
+            prefix
+                .join("dosdevices")
+                .join("d:")
+                .join("Games")
+                .join("RSI")
+        );
+        assert_eq!(
+            map_launcher_path(&drive_c, "/mnt/games/RSI"),
+            PathBuf::from("/mnt/games/RSI")
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn launcher_library_folders_reads_settings_and_log_in_prefix() {
+        let tmp = TempDir::new().unwrap();
+        // Proton-style prefix: compatdata/<id>/pfx/drive_c
+        let drive_c = tmp
+            .path()
+            .join("compatdata")
+            .join("1234")
+            .join("pfx")
+            .join("drive_c");
+        let launcher = drive_c
+            .join("users")
+            .join("steamuser")
+            .join("AppData")
+            .join("Roaming")
+            .join("rsilauncher");
+        fs::create_dir_all(launcher.join("logs")).unwrap();
+        fs::write(
+            launcher.join("settings.json"),
+            r#"{"library": {"defaultPath": "C:\\Games\\RSI"}, "theme": "C:\\ignored"}"#,
+        )
+        .unwrap();
+        fs::write(launcher.join("broken.json"), "{ not json").unwrap();
+        fs::write(
+            launcher.join("logs").join("log.log"),
+            "[info] Launching Star Citizen LIVE from (D:\\StarCitizen\\LIVE)\n[info] other\n",
+        )
+        .unwrap();
+
+        let folders = launcher_library_folders(&drive_c);
+        assert_eq!(
+            folders,
+            [
+                drive_c.join("Games").join("RSI"),
+                drive_c
+                    .parent()
+                    .unwrap()
+                    .join("dosdevices")
+                    .join("d:")
+                    .join("StarCitizen")
+                    .join("LIVE"),
+            ]
+        );
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn launcher_library_folder_resolves_to_installations() {
+        let tmp = TempDir::new().unwrap();
+        let drive_c = tmp.path().join(".wine").join("drive_c");
+        let library = drive_c.join("Games").join("RSI");
+        channel(&library.join(GAME_FOLDER), "LIVE");
+        channel(&library.join(GAME_FOLDER), "PTU");
+        let launcher = drive_c
+            .join("users")
+            .join("pilot")
+            .join("AppData")
+            .join("Roaming")
+            .join("rsilauncher");
+        fs::create_dir_all(&launcher).unwrap();
+        fs::write(
+            launcher.join("library.json"),
+            r#"{"libraryFolder": "C:\\Games\\RSI"}"#,
+        )
+        .unwrap();
+
+        let folders = launcher_library_folders(&drive_c);
+        let base = resolve_base(&folders[0]).unwrap();
+        assert_eq!(names(&scan_base_path(&base).unwrap()), ["LIVE", "PTU"]);
+    }
+}
@@ -0,0 +1,225 @@
+/// SC keyboard vocabulary: HID Keyboard/Keypad page (0x07) usage, Windows virtual-key code
+/// (0 when the key has no code of its own), Linux evdev key code, SC key name (e.g. "np_1",
+/// "lbracket") and label
+pub const KEYBOARD_KEYS: [(u16, u16, u16, &str, &str); 104] = [
+    (0x04, 0x41, 30, "a", "A"),
+    (0x05, 0x42, 48, "b", "B"),
+    (0x06, 0x43, 46, "c", "C"),
+    (0x07, 0x44, 32, "d", "D"),
+    (0x08, 0x45, 18, "e", "E"),
+    (0x09, 0x46, 33, "f", "F"),
+    (0x0A, 0x47, 34, "g", "G"),
+    (0x0B, 0x48, 35, "h", "H"),
+    (0x0C, 0x49, 23, "i", "I"),
+    (0x0D, 0x4A, 36, "j", "J"),
+    (0x0E, 0x4B, 37, "k", "K"),
+    (0x0F, 0x4C, 38, "l", "L"),
+    (0x10, 0x4D, 50, "m", "M"),
+    (0x11, 0x4E, 49, "n", "N"),
+    (0x12, 0x4F, 24, "o", "O"),
+    (0x13, 0x50, 25, "p", "P"),
+    (0x14, 0x51, 16, "q", "Q"),
+    (0x15, 0x52, 19, "r", "R"),
+    (0x16, 0x53, 31, "s", "S"),
+    (0x17, 0x54, 20, "t", "T"),
+    (0x18, 0x55, 22, "u", "U"),
+    (0x19, 0x56, 47, "v", "V"),
+    (0x1A, 0x57, 17, "w", "W"),
+    (0x1B, 0x58, 45, "x", "X"),
+    (0x1C, 0x59, 21, "y", "Y"),
+    (0x1D, 0x5A, 44, "z", "Z"),
+    (0x1E, 0x31, 2, "1", "1"),
+    (0x1F, 0x32, 3, "2", "2"),
+    (0x20, 0x33, 4, "3", "3"),
+    (0x21, 0x34, 5, "4", "4"),
+    (0x22, 0x35, 6, "5", "5"),
+    (0x23, 0x36, 7, "6", "6"),
+    (0x24, 0x37, 8, "7", "7"),
+    (0x25, 0x38, 9, "8", "8"),
+    (0x26, 0x39, 10, "9", "9"),
+    (0x27, 0x30, 11, "0", "0"),
+    (0x28, 0x0D, 28, "enter", "Enter"),
+    (0x29, 0x1B, 1, "escape", "Esc"),
+    (0x2A, 0x08, 14, "backspace", "Backspace"),
+    (0x2B, 0x09, 15, "tab", "Tab"),
+    (0x2C, 0x20, 57, "space", "Space"),
+    (0x2D, 0xBD, 12, "minus", "-"),
+    (0x2E, 0xBB, 13, "equals", "="),
+    (0x2F, 0xDB, 26, "lbracket", "["),
+    (0x30, 0xDD, 27, "rbracket", "]"),
+    (0x31, 0xDC, 43, "backslash", "\\"),
+    (0x33, 0xBA, 39, "semicolon", ";"),
+    (0x34, 0xDE, 40, "apostrophe", "'"),
+    (0x35, 0xC0, 41, "grave", "`"),
+    (0x36, 0xBC, 51, "comma", ","),
+    (0x37, 0xBE, 52, "period", "."),
+    (0x38, 0xBF, 53, "slash", "/"),
+    (0x39, 0x14, 58, "capslock", "Caps Lock"),
+    (0x3A, 0x70, 59, "f1", "F1"),
+    (0x3B, 0x71, 60, "f2", "F2"),
+    (0x3C, 0x72, 61, "f3", "F3"),
+    (0x3D, 0x73, 62, "f4", "F4"),
+    (0x3E, 0x74, 63, "f5", "F5"),
+    (0x3F, 0x75, 64, "f6", "F6"),
+    (0x40, 0x76, 65, "f7", "F7"),
+    (0x41, 0x77, 66, "f8", "F8"),
+    (0x42, 0x78, 67, "f9", "F9"),
+    (0x43, 0x79, 68, "f10", "F10"),
+    (0x44, 0x7A, 87, "f11", "F11"),
+    (0x45, 0x7B, 88, "f12", "F12"),
+    (0x46, 0x2C, 99, "print", "Print Screen"),
+    (0x47, 0x91, 70, "scrolllock", "Scroll Lock"),
+    (0x48, 0x13, 119, "pause", "Pause"),
+    (0x49, 0x2D, 110, "insert", "Insert"),
+    (0x4A, 0x24, 102, "home", "Home"),
+    (0x4B, 0x21, 104, "pgup", "Page Up"),
+    (0x4C, 0x2E, 111, "delete", "Delete"),
+    (0x4D, 0x23, 107, "end", "End"),
+    (0x4E, 0x22, 109, "pgdn", "Page Down"),
+    (0x4F, 0x27, 106, "right", "Right Arrow"),
+    (0x50, 0x25, 105, "left", "Left Arrow"),
+    (0x51, 0x28, 108, "down", "Down Arrow"),
+    (0x52, 0x26, 103, "up", "Up Arrow"),
+    (0x53, 0x90, 69, "numlock", "Num Lock"),
+    (0x54, 0x6F, 98, "np_divide", "Numpad /"),
+    (0x55, 0x6A, 55, "np_multiply", "Numpad *"),
+    (0x56, 0x6D, 74, "np_subtract", "Numpad -"),
+    (0x57, 0x6B, 78, "np_add", "Numpad +"),
+    // Windows reports it as Enter
+    (0x58, 0, 96, "np_enter", "Numpad Enter"),
+    (0x59, 0x61, 79, "np_1", "Numpad 1"),
+    (0x5A, 0x62, 80, "np_2", "Numpad 2"),
+    (0x5B, 0x63, 81, "np_3", "Numpad 3"),
+    (0x5C, 0x64, 75, "np_4", "Numpad 4"),
+    (0x5D, 0x65, 76, "np_5", "Numpad 5"),
+    (0x5E, 0x66, 77, "np_6", "Numpad 6"),
+    (0x5F, 0x67, 71, "np_7", "Numpad 7"),
+    (0x60, 0x68, 72, "np_8", "Numpad 8"),
+    (0x61, 0x69, 73, "np_9", "Numpad 9"),
+    (0x62, 0x60, 82, "np_0", "Numpad 0"),
+    (0x63, 0x6E, 83, "np_period", "Numpad ."),
+    (0x65, 0x5D, 127, "apps", "Menu"),
+    (0xE0, 0xA2, 29, "lctrl", "Left Ctrl"),
+    (0xE1, 0xA0, 42, "lshift", "Left Shift"),
+    (0xE2, 0xA4, 56, "lalt", "Left Alt"),
+    (0xE3, 0x5B, 125, "lwin", "Left Windows"),
+    (0xE4, 0xA3, 97, "rctrl", "Right Ctrl"),
+    (0xE5, 0xA1, 54, "rshift", "Right Shift"),
+    (0xE6, 0xA5, 100, "ralt", "Right Alt"),
+    (0xE7, 0x5C, 126, "rwin", "Right Windows"),
+];
+
+/// Mouse button number (0 for the wheel), Windows virtual-key code (0 when it can't be
+/// polled), SC input name and display label
+pub const MOUSE_INPUTS: [(u32, u16, &str, &str); 7] = [
+    (1, 0x01, "mouse1", "Left Button"),
+    (2, 0x02, "mouse2", "Right Button"),
+    (3, 0x04, "mouse3", "Middle Button"),
+    (4, 0x05, "mouse4", "Button 4"),
+    (5, 0x06, "mouse5", "Button 5"),
+    (0, 0, "mwheel_up", "Wheel Up"),
+    (0, 0, "mwheel_down", "Wheel Down"),
+];
+
+/// Keys SC accepts as modifiers in keyboard chords like "kb1_lalt+f"
+pub const MODIFIER_KEYS: [&str; 6] = ["lalt", "ralt", "lctrl", "rctrl", "lshift", "rshift"];
+
+/// Display label of an SC key or mouse input name, e.g. "lbracket" -> "["
+pub fn label(name: &str) -> Option<&'static str> {
+    KEYBOARD_KEYS
+        .iter()
+        .map(|(_, _, _, n, l)| (n, l))
+        .chain(MOUSE_INPUTS.iter().map(|(_, _, n, l)| (n, l)))
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, l)| *l)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn by_hid(usage: u16) -> Option<&'static str> {
+        KEYBOARD_KEYS
+            .iter()
+            .find(|(hid, ..)| *hid == usage)
+            .map(|(.., name, _)| *name)
+    }
+
+    fn by_vk(vk: u16) -> Option<&'static str> {
+        KEYBOARD_KEYS
+            .iter()
+            .find(|(_, code, ..)| *code != 0 && *code == vk)
+            .map(|(.., name, _)| *name)
+    }
+
+    fn by_evdev(code: u16) -> Option<&'static str> {
+        KEYBOARD_KEYS
+            .iter()
+            .find(|(_, _, evdev, ..)| *evdev == code)
+            .map(|(.., name, _)| *name)
+    }
+
+    #[test]
+    fn codes_and_names_are_unique() {
+        let mut hid = HashSet::new();
+        let mut vk = HashSet::new();
+        let mut evdev = HashSet::new();
+        let mut names = HashSet::new();
+        for (hid_usage, vk_code, evdev_code, name, _) in KEYBOARD_KEYS {
+            assert!(hid.insert(hid_usage), "HID usage {:#04x} twice", hid_usage);
+            assert!(
+                vk_code == 0 || vk.insert(vk_code),
+                "VK {:#04x} twice",
+                vk_code
+            );
+            assert!(evdev.insert(evdev_code), "evdev code {} twice", evdev_code);
+            assert!(names.insert(name), "{} twice", name);
+        }
+        for (.., name, _) in MOUSE_INPUTS {
+            assert!(names.insert(name), "{} twice", name);
+        }
+    }
+
+    #[test]
+    fn lookups_find_the_sc_name() {
+        // HID Keyboard/Keypad page
+        assert_eq!(by_hid(0x04), Some("a"));
+        assert_eq!(by_hid(0x59), Some("np_1"));
+        assert_eq!(by_hid(0xE1), Some("lshift"));
+        // Windows virtual-key codes
+        assert_eq!(by_vk(0x41), Some("a"));
+        assert_eq!(by_vk(0x61), Some("np_1"));
+        assert_eq!(by_vk(0xA0), Some("lshift"));
+        assert_eq!(by_vk(0xA5), Some("ralt"));
+        // Linux evdev key codes
+        assert_eq!(by_evdev(30), Some("a"));
+        assert_eq!(by_evdev(79), Some("np_1"));
+        assert_eq!(by_evdev(42), Some("lshift"));
+        assert_eq!(by_evdev(100), Some("ralt"));
+
+        assert_eq!(by_hid(0x00), None);
+        assert_eq!(by_vk(0), None);
+    }
+
+    #[test]
+    fn labels_round_trip() {
+        for (.., name, label_text) in KEYBOARD_KEYS {
+            assert_eq!(label(name), Some(label_text), "{}", name);
+            assert_eq!(label(&name.to_uppercase()), Some(label_text), "{}", name);
+        }
+        for (.., name, label_text) in MOUSE_INPUTS {
+            assert_eq!(label(name), Some(label_text), "{}", name);
+        }
+        assert_eq!(label("np_1"), Some("Numpad 1"));
+        assert_eq!(label("mwheel_up"), Some("Wheel Up"));
+        assert_eq!(label("button3"), None);
+    }
+
+    #[test]
+    fn modifier_keys_are_keyboard_keys() {
+        for modifier in MODIFIER_KEYS {
+            assert!(label(modifier).is_some(), "{}", modifier);
+        }
+    }
+}
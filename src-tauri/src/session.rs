@@ -0,0 +1,210 @@
+use crate::backup;
+use crate::error::AppError;
+use crate::keybindings::{Action, ActionMap, ActionMaps, DeviceInfo, Rebind};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const SNAPSHOT_FILE: &str = "session.json";
+const JOURNAL_FILE: &str = "journal.jsonl";
+
+/// Journal entries written before the snapshot is rewritten and the journal truncated
+const COMPACT_AFTER: usize = 200;
+
+/// The persisted part of the app state
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SessionSnapshot {
+    /// Sequence number of the last journal entry folded into this snapshot
+    #[serde(default)]
+    pub sequence: u64,
+    pub current_bindings: Option<ActionMaps>,
+    pub current_file_name: Option<String>,
+    pub current_file_path: Option<String>,
+    pub bindings_base: Option<ActionMaps>,
+}
+
+/// One journaled mutation
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum JournalEntry {
+    /// The rebinds an action ended up with; None when the action was removed
+    SetAction {
+        seq: u64,
+        action_map: String,
+        action: String,
+        rebinds: Option<Vec<Rebind>>,
+    },
+}
+
+/// Session files in the app data directory: a snapshot plus an append-only journal
+/// of the edits made since. Snapshots are rewritten atomically; a torn last journal
+/// line (crash mid-append) is ignored on recovery.
+pub struct SessionStore {
+    dir: PathBuf,
+    sequence: u64,
+    journal_len: usize,
+}
+
+impl SessionStore {
+    /// Open the session directory and recover the last session from it
+    pub fn open(dir: &Path) -> Result<(SessionStore, SessionSnapshot), AppError> {
+        fs::create_dir_all(dir)
+            .map_err(|e| AppError::io("Failed to create session directory", dir, e))?;
+
+        let mut snapshot = match fs::read_to_string(dir.join(SNAPSHOT_FILE)) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable session snapshot: {}", e);
+                SessionSnapshot::default()
+            }),
+            Err(_) => SessionSnapshot::default(),
+        };
+
+        let mut replayed = 0;
+        if let Ok(journal) = fs::read_to_string(dir.join(JOURNAL_FILE)) {
+            for line in journal.lines().filter(|l| !l.trim().is_empty()) {
+                let entry: JournalEntry = match serde_json::from_str(line) {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        warn!("Stopping journal replay at unreadable entry: {}", e);
+                        break;
+                    }
+                };
+                if apply_entry(&mut snapshot, entry) {
+                    replayed += 1;
+                }
+            }
+        }
+
+        info!(
+            "Recovered session (file: {:?}, {} journal entries replayed)",
+            snapshot.current_file_name, replayed
+        );
+
+        let mut store = SessionStore {
+            dir: dir.to_path_buf(),
+            sequence: snapshot.sequence,
+            journal_len: 0,
+        };
+
+        // Fold the replayed journal into a fresh snapshot
+        if replayed > 0 {
+            store.checkpoint(&mut snapshot.clone())?;
+        }
+
+        Ok((store, snapshot))
+    }
+
+    /// Journal the current rebinds of one action
+    pub fn record_action(&mut self, bindings: Option<&ActionMaps>, action_map: &str, action: &str) {
+        let rebinds = bindings
+            .and_then(|b| b.action_maps.iter().find(|am| am.name == action_map))
+            .and_then(|am| am.actions.iter().find(|a| a.name == action))
+            .map(|a| a.rebinds.clone());
+
+        self.sequence += 1;
+        let entry = JournalEntry::SetAction {
+            seq: self.sequence,
+            action_map: action_map.to_string(),
+            action: action.to_string(),
+            rebinds,
+        };
+
+        if let Err(e) = self.append(&entry) {
+            warn!("Failed to journal session change: {}", e);
+        }
+        self.journal_len += 1;
+    }
+
+    /// True once the journal is long enough to be folded into the snapshot
+    pub fn needs_compaction(&self) -> bool {
+        self.journal_len >= COMPACT_AFTER
+    }
+
+    /// Write a full snapshot and start a new journal
+    pub fn checkpoint(&mut self, snapshot: &mut SessionSnapshot) -> Result<(), AppError> {
+        snapshot.sequence = self.sequence;
+        let json = serde_json::to_string(snapshot)?;
+        backup::write_atomic(&self.dir.join(SNAPSHOT_FILE), json.as_bytes())?;
+
+        // Entries at or below the snapshot sequence are skipped on replay, so a crash
+        // between these two steps is harmless
+        let journal = self.dir.join(JOURNAL_FILE);
+        fs::write(&journal, "")
+            .map_err(|e| AppError::io("Failed to reset session journal", &journal, e))?;
+        self.journal_len = 0;
+        Ok(())
+    }
+
+    fn append(&self, entry: &JournalEntry) -> Result<(), AppError> {
+        let line = serde_json::to_string(entry)?;
+        let journal = self.dir.join(JOURNAL_FILE);
+        let write = || -> std::io::Result<()> {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&journal)?;
+            writeln!(file, "{}", line)?;
+            file.sync_data()
+        };
+        write().map_err(|e| AppError::io("Failed to write session journal", &journal, e))
+    }
+}
+
+/// Apply one journal entry; returns false for entries already in the snapshot
+fn apply_entry(snapshot: &mut SessionSnapshot, entry: JournalEntry) -> bool {
+    match entry {
+        JournalEntry::SetAction {
+            seq,
+            action_map,
+            action,
+            rebinds,
+        } => {
+            if seq <= snapshot.sequence {
+                return false;
+            }
+            snapshot.sequence = seq;
+
+            let bindings = snapshot.current_bindings.get_or_insert_with(|| ActionMaps {
+                profile_name: "User Customizations".to_string(),
+                action_maps: Vec::new(),
+                categories: Vec::new(),
+                modifiers: Vec::new(),
+                devices: DeviceInfo {
+                    keyboards: Vec::new(),
+                    mice: Vec::new(),
+                    joysticks: Vec::new(),
+                    device_options: Vec::new(),
+                },
+            });
+
+            let map = match bindings
+                .action_maps
+                .iter()
+                .position(|am| am.name == action_map)
+            {
+                Some(idx) => &mut bindings.action_maps[idx],
+                None => {
+                    bindings.action_maps.push(ActionMap {
+                        name: action_map,
+                        actions: Vec::new(),
+                    });
+                    bindings.action_maps.last_mut().unwrap()
+                }
+            };
+
+            match rebinds {
+                Some(rebinds) => match map.actions.iter_mut().find(|a| a.name == action) {
+                    Some(existing) => existing.rebinds = rebinds,
+                    None => map.actions.push(Action {
+                        name: action,
+                        rebinds,
+                    }),
+                },
+                None => map.actions.retain(|a| a.name != action),
+            }
+            true
+        }
+    }
+}
@@ -0,0 +1,920 @@
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Version written by the paged (multi-device) template format
+pub const TEMPLATE_VERSION: &str = "2.0";
+
+/// Logical axes a raw HID axis can be mapped to
+pub const LOGICAL_AXES: &[&str] = &[
+    "x", "y", "z", "rotx", "roty", "rotz", "slider", "slider2", "hat",
+];
+
+/// Raw axis indices the editor offers (0-7)
+const RAW_AXIS_COUNT: u32 = 8;
+
+/// A joystick template: a picture of one or more devices with their controls placed on it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Template {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    /// Product the template was drawn for, matched against device names
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub joystick_model: Option<String>,
+    /// Extra USB ids ("vvvv:pppp") the template fits, e.g. other revisions of the same stick
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hardware_ids: Vec<String>,
+    #[serde(default)]
+    pub image_width: u32,
+    #[serde(default)]
+    pub image_height: u32,
+    #[serde(default)]
+    pub pages: Vec<TemplatePage>,
+    /// Keys this version doesn't know about, kept so saving doesn't drop them
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// One device in a template
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TemplatePage {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub device_uuid: String,
+    #[serde(default)]
+    pub device_name: String,
+    /// "js1", "gp1", ... or empty when the page isn't tied to an instance
+    #[serde(default, alias = "devicePrefix")]
+    pub device_prefix: String,
+    /// Pre-1.1 instance number, superseded by `device_prefix`
+    #[serde(
+        rename = "joystickNumber",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub joystick_number: Option<u32>,
+    #[serde(default)]
+    pub axis_profile: String,
+    /// Raw axis index ("0".."7") -> logical axis name
+    #[serde(default)]
+    pub axis_mapping: BTreeMap<String, String>,
+    #[serde(default)]
+    pub image_path: String,
+    #[serde(default)]
+    pub image_data_url: Option<String>,
+    /// Page whose image is shown flipped on this page
+    #[serde(default)]
+    pub mirror_from_page_id: String,
+    #[serde(default)]
+    pub buttons: Vec<TemplateButton>,
+    /// Keys this version doesn't know about, kept so saving doesn't drop them
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub enum ButtonType {
+    #[default]
+    #[serde(rename = "simple")]
+    Simple,
+    #[serde(rename = "hat4way")]
+    Hat4Way,
+    #[serde(rename = "hat2way-vertical")]
+    Hat2WayVertical,
+    #[serde(rename = "hat2way-horizontal")]
+    Hat2WayHorizontal,
+}
+
+impl ButtonType {
+    /// The name used in template files
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ButtonType::Simple => "simple",
+            ButtonType::Hat4Way => "hat4way",
+            ButtonType::Hat2WayVertical => "hat2way-vertical",
+            ButtonType::Hat2WayHorizontal => "hat2way-horizontal",
+        }
+    }
+
+    /// Input slots a button of this type may fill
+    pub fn slots(&self) -> &'static [&'static str] {
+        match self {
+            ButtonType::Simple => &["main"],
+            ButtonType::Hat4Way => &["up", "down", "left", "right", "push"],
+            ButtonType::Hat2WayVertical => &["up", "down", "push"],
+            ButtonType::Hat2WayHorizontal => &["left", "right", "push"],
+        }
+    }
+}
+
+/// The SC inputs behind a button, e.g. `main: "button3"` or `up: "hat1_up"`
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ButtonInputs {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub main: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub up: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub down: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub left: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub right: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub push: Option<String>,
+}
+
+impl ButtonInputs {
+    /// Filled slots as (slot, input)
+    pub fn filled(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("main", &self.main),
+            ("up", &self.up),
+            ("down", &self.down),
+            ("left", &self.left),
+            ("right", &self.right),
+            ("push", &self.push),
+        ]
+        .into_iter()
+        .filter_map(|(slot, input)| {
+            input
+                .as_deref()
+                .filter(|i| !i.trim().is_empty())
+                .map(|i| (slot, i))
+        })
+        .collect()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateButton {
+    /// Editor-generated, usually a millisecond timestamp (possibly fractional)
+    pub id: f64,
+    #[serde(default)]
+    pub name: String,
+    pub button_pos: Point,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_pos: Option<Point>,
+    #[serde(default)]
+    pub button_type: ButtonType,
+    #[serde(default)]
+    pub inputs: ButtonInputs,
+    /// Oldest single-input format: "button", "axis" or "hat" plus `input_id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_id: Option<Value>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSeverity {
+    /// The template can't be saved like this
+    Error,
+    /// Saved as-is, but probably not what the user meant
+    Warning,
+}
+
+/// A problem found by `Template::validate`
+#[derive(Debug, Serialize, Clone)]
+pub struct TemplateIssue {
+    pub severity: IssueSeverity,
+    pub page: Option<String>,
+    pub button: Option<String>,
+    pub message: String,
+}
+
+/// A template read from disk, upgraded to the current format
+#[derive(Debug, Serialize, Clone)]
+pub struct LoadedTemplate {
+    pub template: Template,
+    /// The version the file had when it needed upgrading
+    pub migrated_from: Option<String>,
+    pub issues: Vec<TemplateIssue>,
+}
+
+impl Template {
+    /// Parse template JSON of any version, upgrading old formats first
+    /// Returns the template and the version it was migrated from, if any
+    pub fn from_json(json: &str) -> Result<(Self, Option<String>), serde_json::Error> {
+        let mut value: Value = serde_json::from_str(json)?;
+        let migrated_from = migrate(&mut value);
+        Ok((serde_json::from_value(value)?, migrated_from))
+    }
+
+    pub fn validate(&self) -> Vec<TemplateIssue> {
+        let mut issues = Vec::new();
+        let page_ids: Vec<&str> = self.pages.iter().map(|p| p.id.as_str()).collect();
+        let mut seen_pages = HashSet::new();
+
+        for id in &self.hardware_ids {
+            if parse_hardware_id(id).is_none() {
+                issues.push(TemplateIssue {
+                    severity: IssueSeverity::Error,
+                    page: None,
+                    button: None,
+                    message: format!("Invalid hardware id '{}' (expected vvvv:pppp)", id),
+                });
+            }
+        }
+
+        for page in &self.pages {
+            let mut issue = |severity, button: Option<&TemplateButton>, message: String| {
+                issues.push(TemplateIssue {
+                    severity,
+                    page: Some(page.name.clone()),
+                    button: button.map(|b| b.name.clone()),
+                    message,
+                })
+            };
+
+            if page.id.trim().is_empty() {
+                issue(IssueSeverity::Error, None, "Page has no id".to_string());
+            } else if !seen_pages.insert(page.id.as_str()) {
+                issue(
+                    IssueSeverity::Error,
+                    None,
+                    format!("Duplicate page id '{}'", page.id),
+                );
+            }
+
+            if !page.device_prefix.is_empty() && !is_device_prefix(&page.device_prefix) {
+                issue(
+                    IssueSeverity::Error,
+                    None,
+                    format!(
+                        "Invalid device prefix '{}' (expected e.g. js1 or gp1)",
+                        page.device_prefix
+                    ),
+                );
+            }
+
+            if !page.mirror_from_page_id.is_empty() {
+                if page.mirror_from_page_id == page.id {
+                    issue(
+                        IssueSeverity::Error,
+                        None,
+                        "Page mirrors its own image".to_string(),
+                    );
+                } else if !page_ids.contains(&page.mirror_from_page_id.as_str()) {
+                    issue(
+                        IssueSeverity::Error,
+                        None,
+                        format!("Mirrors unknown page '{}'", page.mirror_from_page_id),
+                    );
+                }
+            }
+
+            let mut mapped_axes = HashSet::new();
+            for (raw, logical) in &page.axis_mapping {
+                if !raw.parse::<u32>().is_ok_and(|i| i < RAW_AXIS_COUNT) {
+                    issue(
+                        IssueSeverity::Error,
+                        None,
+                        format!("Axis index '{}' is not between 0 and 7", raw),
+                    );
+                }
+                if !LOGICAL_AXES.contains(&logical.as_str()) {
+                    issue(
+                        IssueSeverity::Warning,
+                        None,
+                        format!("Axis {} is mapped to unknown axis '{}'", raw, logical),
+                    );
+                } else if !mapped_axes.insert(logical.as_str()) {
+                    issue(
+                        IssueSeverity::Warning,
+                        None,
+                        format!("Axis '{}' is mapped more than once", logical),
+                    );
+                }
+            }
+
+            let mut seen_buttons = Vec::new();
+            for button in &page.buttons {
+                if seen_buttons.contains(&button.id) {
+                    issue(
+                        IssueSeverity::Error,
+                        Some(button),
+                        format!("Duplicate button id {}", button.id),
+                    );
+                }
+                seen_buttons.push(button.id);
+
+                let slots = button.button_type.slots();
+                let filled = button.inputs.filled();
+                for (slot, _) in &filled {
+                    if !slots.contains(slot) {
+                        issue(
+                            IssueSeverity::Error,
+                            Some(button),
+                            format!(
+                                "'{}' input is not allowed on a {} button",
+                                slot,
+                                button.button_type.as_str()
+                            ),
+                        );
+                    }
+                }
+                if filled.is_empty() && button.input_type.is_none() {
+                    issue(
+                        IssueSeverity::Warning,
+                        Some(button),
+                        "Button has no inputs assigned".to_string(),
+                    );
+                }
+            }
+        }
+
+        issues
+    }
+}
+
+/// "js1", "gp2", ...
+fn is_device_prefix(prefix: &str) -> bool {
+    ["js", "gp"].iter().any(|kind| {
+        prefix
+            .strip_prefix(kind)
+            .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+    })
+}
+
+/// "js2_button11" -> "button11"; inputs are stored without the instance since 1.1
+fn strip_instance(input: &str) -> String {
+    match input.split_once('_') {
+        Some((prefix, rest)) if is_device_prefix(prefix) => rest.to_string(),
+        _ => input.to_string(),
+    }
+}
+
+fn strip_button_instances(page: &mut Map<String, Value>) {
+    let Some(buttons) = page.get_mut("buttons").and_then(|b| b.as_array_mut()) else {
+        return;
+    };
+    for inputs in buttons
+        .iter_mut()
+        .filter_map(|b| b.get_mut("inputs"))
+        .filter_map(|i| i.as_object_mut())
+    {
+        for input in inputs.values_mut() {
+            if let Some(s) = input.as_str() {
+                *input = Value::String(strip_instance(s));
+            }
+        }
+    }
+}
+
+/// Pre-1.1 pages name their instance with `joystickNumber` and prefix every input with it
+fn migrate_instance_number(page: &mut Map<String, Value>) {
+    let has_prefix = page.contains_key("device_prefix") || page.contains_key("devicePrefix");
+    if has_prefix {
+        return;
+    }
+    if let Some(number) = page.remove("joystickNumber").and_then(|n| n.as_u64()) {
+        page.insert("device_prefix".to_string(), format!("js{}", number).into());
+        strip_button_instances(page);
+    }
+}
+
+/// Build a page from a v1 `leftStick` / `rightStick` entry, which is either
+/// `{joystickNumber, buttons}` or a bare button array
+fn page_from_stick(stick: Value, id: &str, name: &str, default_number: u64) -> Map<String, Value> {
+    let mut page = match stick {
+        Value::Object(map) => map,
+        Value::Array(buttons) => {
+            let mut map = Map::new();
+            map.insert("buttons".to_string(), Value::Array(buttons));
+            map
+        }
+        _ => Map::new(),
+    };
+    if !page.contains_key("joystickNumber")
+        && !page.contains_key("device_prefix")
+        && !page.contains_key("devicePrefix")
+    {
+        page.insert("joystickNumber".to_string(), default_number.into());
+    }
+    migrate_instance_number(&mut page);
+    page.insert("id".to_string(), id.into());
+    page.entry("name").or_insert_with(|| name.into());
+    page.entry("buttons")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    page
+}
+
+fn take_string(template: &mut Map<String, Value>, key: &str) -> Option<String> {
+    template
+        .remove(key)
+        .and_then(|v| v.as_str().map(str::to_string))
+        .filter(|s| !s.is_empty())
+}
+
+/// Upgrade a template to the paged format in place
+/// Returns the original version when anything changed
+pub fn migrate(value: &mut Value) -> Option<String> {
+    let template = value.as_object_mut()?;
+    let version = template
+        .get("version")
+        .and_then(|v| v.as_str())
+        .unwrap_or("1.0")
+        .to_string();
+    if version == TEMPLATE_VERSION {
+        return None;
+    }
+
+    let has_pages = template
+        .get("pages")
+        .and_then(|p| p.as_array())
+        .is_some_and(|p| !p.is_empty());
+    let left = template.remove("leftStick");
+    let right = template.remove("rightStick");
+    // The oldest single-stick files keep their buttons at the top level
+    let flat = template.remove("buttons");
+
+    let image_type = take_string(template, "imageType");
+    let image_flipped = template.remove("imageFlipped");
+    let image_path = take_string(template, "imagePath");
+    let image_data_url = take_string(template, "imageDataUrl");
+    let left_image = (
+        take_string(template, "leftImagePath"),
+        take_string(template, "leftImageDataUrl"),
+    );
+    let right_image = (
+        take_string(template, "rightImagePath"),
+        take_string(template, "rightImageDataUrl"),
+    );
+
+    if has_pages {
+        // 1.x files that already have pages; any leftStick/rightStick copy is stale
+        if let Some(pages) = template.get_mut("pages").and_then(|p| p.as_array_mut()) {
+            for page in pages.iter_mut().filter_map(|p| p.as_object_mut()) {
+                if let Some(prefix) = page.remove("devicePrefix") {
+                    page.entry("device_prefix").or_insert(prefix);
+                }
+                migrate_instance_number(page);
+            }
+        }
+    } else {
+        let (left, right) = match (left, right, flat) {
+            (None, None, Some(buttons)) => (None, Some(buttons)),
+            (left, right, _) => (left, right),
+        };
+        let mut left = page_from_stick(left.unwrap_or(Value::Null), "left_stick", "Left Stick", 1);
+        let mut right = page_from_stick(
+            right.unwrap_or(Value::Null),
+            "right_stick",
+            "Right Stick",
+            2,
+        );
+
+        let set_image = |page: &mut Map<String, Value>,
+                         (path, data): (Option<String>, Option<String>)| {
+            page.insert("image_path".to_string(), path.unwrap_or_default().into());
+            page.insert("image_data_url".to_string(), data.into());
+        };
+        if image_type.as_deref() == Some("dual") {
+            set_image(&mut left, left_image);
+            set_image(&mut right, right_image);
+        } else {
+            // A single picture drawn for one hand, shown flipped for the other;
+            // old files stored the flipped side as a boolean (true = left)
+            let left_flipped = match image_flipped {
+                Some(Value::Bool(flipped)) => flipped,
+                Some(Value::String(side)) => side == "left",
+                _ => false,
+            };
+            let (shown, mirrored, mirrored_from) = if left_flipped {
+                (&mut right, &mut left, "right_stick")
+            } else {
+                (&mut left, &mut right, "left_stick")
+            };
+            set_image(shown, (image_path, image_data_url));
+            mirrored.insert("mirror_from_page_id".to_string(), mirrored_from.into());
+        }
+
+        template.insert(
+            "pages".to_string(),
+            Value::Array(vec![Value::Object(left), Value::Object(right)]),
+        );
+    }
+
+    template.insert("version".to_string(), TEMPLATE_VERSION.into());
+    info!(
+        "Migrated template from version {} to {}",
+        version, TEMPLATE_VERSION
+    );
+    Some(version)
+}
+
+/// Errors only; warnings never block a save
+pub fn blocking_issues(issues: &[TemplateIssue]) -> Vec<TemplateIssue> {
+    issues
+        .iter()
+        .filter(|i| i.severity == IssueSeverity::Error)
+        .cloned()
+        .collect()
+}
+
+/// A connected device to find a template for
+#[derive(Debug, Clone)]
+pub struct MatchDevice<'a> {
+    /// SC instance number (the N in jsN)
+    pub instance: usize,
+    pub name: &'a str,
+    pub product_name: Option<&'a str>,
+    pub uuid: Option<&'a str>,
+}
+
+/// The template page that best fits a device
+#[derive(Debug, Serialize, Clone)]
+pub struct TemplateCandidate {
+    pub path: String,
+    pub template_name: String,
+    pub page_id: String,
+    pub page_name: String,
+    /// 0.0 - 1.0; 1.0 is a VID/PID match on a page set up for the same instance
+    pub confidence: f32,
+    pub reason: String,
+}
+
+/// Best template for one connected device
+#[derive(Debug, Serialize, Clone)]
+pub struct TemplateMatch {
+    pub instance: usize,
+    pub device_name: String,
+    pub best: Option<TemplateCandidate>,
+}
+
+/// USB vendor/product ids from "vvvv:pppp" or a 32-digit DirectInput/SDL GUID
+/// (little-endian vendor at bytes 4-5, product at bytes 8-9)
+pub fn parse_hardware_id(uuid: &str) -> Option<(u16, u16)> {
+    let uuid = uuid.trim();
+    if let Some((vendor, product)) = uuid.split_once(':') {
+        return Some((
+            u16::from_str_radix(vendor, 16).ok()?,
+            u16::from_str_radix(product, 16).ok()?,
+        ));
+    }
+    if uuid.len() != 32 || !uuid.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let le_u16 = |at: usize| {
+        let lo = u8::from_str_radix(&uuid[at..at + 2], 16).ok()?;
+        let hi = u8::from_str_radix(&uuid[at + 2..at + 4], 16).ok()?;
+        Some(u16::from_le_bytes([lo, hi]))
+    };
+    Some((le_u16(8)?, le_u16(16)?))
+}
+
+fn name_tokens(name: &str) -> Vec<String> {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.to_ascii_lowercase())
+        .collect()
+}
+
+/// How alike two product names are, 0.0 - 0.7
+fn name_similarity(a: &str, b: &str) -> f32 {
+    let (a, b) = (name_tokens(a), name_tokens(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let (joined_a, joined_b) = (a.concat(), b.concat());
+    if joined_a == joined_b {
+        return 0.7;
+    }
+    if joined_a.contains(&joined_b) || joined_b.contains(&joined_a) {
+        return 0.6;
+    }
+    let shared = a.iter().filter(|t| b.contains(t)).count();
+    let total = a.len() + b.len() - shared;
+    let overlap = shared as f32 / total as f32;
+    if overlap >= 0.5 {
+        0.5 * overlap
+    } else {
+        0.0
+    }
+}
+
+/// Score one template page for a device; None when nothing ties them together
+fn page_score(
+    template: &Template,
+    page: &TemplatePage,
+    device: &MatchDevice,
+) -> Option<(f32, String)> {
+    let device_id = device.uuid.and_then(parse_hardware_id);
+    let page_id = parse_hardware_id(&page.device_uuid);
+
+    let (mut score, reason) = match device_id {
+        Some(id) if page_id == Some(id) => (0.95, "VID/PID matches the page device".to_string()),
+        Some(id)
+            if template
+                .hardware_ids
+                .iter()
+                .any(|h| parse_hardware_id(h) == Some(id)) =>
+        {
+            (0.85, "VID/PID is listed in the template".to_string())
+        }
+        _ => {
+            let device_names = [Some(device.name), device.product_name];
+            let template_names = [
+                Some(page.device_name.as_str()),
+                template.joystick_model.as_deref(),
+            ];
+            let (best, matched) = device_names
+                .iter()
+                .flatten()
+                .flat_map(|d| {
+                    template_names
+                        .iter()
+                        .flatten()
+                        .map(move |t| (name_similarity(d, t), *t))
+                })
+                .fold((0.0, ""), |best, candidate| {
+                    if candidate.0 > best.0 {
+                        candidate
+                    } else {
+                        best
+                    }
+                });
+            if best <= 0.0 {
+                return None;
+            }
+            // A different VID/PID on the page makes a name match unlikely to be the same stick
+            let best = if device_id.is_some() && page_id.is_some() {
+                best * 0.5
+            } else {
+                best
+            };
+            (best, format!("Device name matches '{}'", matched))
+        }
+    };
+
+    // Tie-break between identical sticks: prefer the page set up for this instance
+    if page.device_prefix == format!("js{}", device.instance) {
+        score = f32::min(score + 0.05, 1.0);
+    }
+    Some((score, reason))
+}
+
+/// The best page across all templates for a device
+pub fn best_template(
+    templates: &[(PathBuf, Template)],
+    device: &MatchDevice,
+) -> Option<TemplateCandidate> {
+    let mut best: Option<TemplateCandidate> = None;
+    for (path, template) in templates {
+        for page in &template.pages {
+            let Some((confidence, reason)) = page_score(template, page, device) else {
+                continue;
+            };
+            if best.as_ref().is_some_and(|b| b.confidence >= confidence) {
+                continue;
+            }
+            best = Some(TemplateCandidate {
+                path: path.to_string_lossy().to_string(),
+                template_name: template.name.clone(),
+                page_id: page.id.clone(),
+                page_name: page.name.clone(),
+                confidence,
+                reason,
+            });
+        }
+    }
+    best
+}
+
+/// Every readable template in the given folders (not recursive), sorted by path
+pub fn find_templates(dirs: &[PathBuf]) -> Vec<(PathBuf, Template)> {
+    let mut paths: Vec<PathBuf> = dirs
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("json"))
+        })
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let template = read_template(&path)?;
+            // Any JSON object parses as an (empty) template; only keep ones with content
+            let has_content = template.pages.iter().any(|p| !p.buttons.is_empty());
+            has_content.then_some((path, template))
+        })
+        .collect()
+}
+
+fn read_template(path: &Path) -> Option<Template> {
+    let json = fs::read_to_string(path).ok()?;
+    Template::from_json(&json)
+        .ok()
+        .map(|(template, _)| template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profiles_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("../joystick profiles")
+    }
+
+    fn bundled(file: &str) -> (PathBuf, Template) {
+        let path = profiles_dir().join(file);
+        let template = read_template(&path).unwrap();
+        (path, template)
+    }
+
+    fn migrated(file: &str) -> (Template, Option<String>) {
+        let json = fs::read_to_string(profiles_dir().join(file)).unwrap();
+        Template::from_json(&json).unwrap()
+    }
+
+    fn inputs(page: &TemplatePage) -> Vec<&str> {
+        page.buttons
+            .iter()
+            .flat_map(|button| button.inputs.filled())
+            .map(|(_, input)| input)
+            .collect()
+    }
+
+    #[test]
+    fn migrates_paged_v1_template() {
+        let (template, migrated_from) = migrated("x52pro.json");
+        assert_eq!(migrated_from.as_deref(), Some("1.0"));
+        assert_eq!(template.version, TEMPLATE_VERSION);
+        assert_eq!(template.joystick_model.as_deref(), Some("Logitech X52 Pro"));
+
+        // The stale leftStick/rightStick copies are dropped, the existing page is kept
+        assert!(!template.extra.contains_key("leftStick"));
+        assert!(!template.extra.contains_key("rightStick"));
+        assert_eq!(template.pages.len(), 1);
+        let page = &template.pages[0];
+        assert_eq!(page.name, "H.O.T.A.S Pair");
+        assert_eq!(page.device_prefix, "js1");
+        assert_eq!(page.joystick_number, None);
+        assert_eq!(page.buttons.len(), 28);
+        assert!(inputs(page).contains(&"button35"));
+        assert!(inputs(page).iter().all(|input| !input.starts_with("js")));
+        // A key this version doesn't use survives
+        assert!(page.extra.contains_key("joystick_prefix"));
+
+        assert!(blocking_issues(&template.validate()).is_empty());
+    }
+
+    #[test]
+    fn migrates_two_stick_v1_template() {
+        let (template, migrated_from) = migrated("VKB_Gladiator_NXT_test.json");
+        assert_eq!(migrated_from.as_deref(), Some("1.0"));
+        assert_eq!(template.version, TEMPLATE_VERSION);
+        assert!(template.extra.is_empty());
+
+        let [left, right] = &template.pages[..] else {
+            panic!("expected two pages, got {}", template.pages.len());
+        };
+        assert_eq!(
+            (left.id.as_str(), left.name.as_str()),
+            ("left_stick", "Left Stick")
+        );
+        assert_eq!(
+            (right.id.as_str(), right.name.as_str()),
+            ("right_stick", "Right Stick")
+        );
+        assert_eq!(left.device_prefix, "js2");
+        assert_eq!(right.device_prefix, "js1");
+        assert_eq!((left.buttons.len(), right.buttons.len()), (16, 17));
+        assert!(inputs(left).contains(&"button11"));
+        assert!(inputs(right).iter().all(|input| !input.starts_with("js")));
+
+        // imageFlipped "left": the picture is drawn for the right stick and mirrored on the left
+        assert_eq!(right.image_path, "vkb_right.png");
+        assert!(right.image_data_url.is_some());
+        assert_eq!(left.mirror_from_page_id, "right_stick");
+        assert!(left.image_data_url.is_none());
+
+        assert!(blocking_issues(&template.validate()).is_empty());
+    }
+
+    #[test]
+    fn saved_template_loads_unchanged() {
+        let (mut template, _) = migrated("x52pro.json");
+        template
+            .extra
+            .insert("editorTheme".to_string(), "dark".into());
+        let saved = serde_json::to_string(&template).unwrap();
+
+        let (reloaded, migrated_from) = Template::from_json(&saved).unwrap();
+        assert_eq!(migrated_from, None);
+        assert_eq!(reloaded.extra.get("editorTheme"), Some(&"dark".into()));
+        assert_eq!(serde_json::to_string(&reloaded).unwrap(), saved);
+    }
+
+    fn device<'a>(instance: usize, name: &'a str, uuid: Option<&'a str>) -> MatchDevice<'a> {
+        MatchDevice {
+            instance,
+            name,
+            product_name: None,
+            uuid,
+        }
+    }
+
+    #[test]
+    fn parse_hardware_id_reads_both_forms() {
+        assert_eq!(
+            parse_hardware_id("030000001d2300000102000000000000"),
+            Some((0x231d, 0x0201))
+        );
+        assert_eq!(
+            parse_hardware_id("030000001d2300000002000000000000"),
+            Some((0x231d, 0x0200))
+        );
+        assert_eq!(parse_hardware_id(" 06a3:0762 "), Some((0x06a3, 0x0762)));
+        assert_eq!(parse_hardware_id("06a3-0762"), None);
+        assert_eq!(parse_hardware_id("030000001d23"), None);
+        assert_eq!(parse_hardware_id("zz0000001d2300000102000000000000"), None);
+    }
+
+    #[test]
+    fn page_device_id_beats_template_hardware_ids() {
+        let (path, mut template) = bundled("x52pro.json");
+        template.hardware_ids = vec!["06a3:0255".to_string()];
+        let templates = [(path, template)];
+
+        let exact = best_template(&templates, &device(3, "Joystick", Some("06a3:0762"))).unwrap();
+        assert_eq!(exact.confidence, 0.95);
+        assert_eq!(exact.reason, "VID/PID matches the page device");
+
+        let listed = best_template(&templates, &device(3, "Joystick", Some("06a3:0255"))).unwrap();
+        assert_eq!(listed.confidence, 0.85);
+        assert_eq!(listed.reason, "VID/PID is listed in the template");
+    }
+
+    #[test]
+    fn name_match_is_halved_when_the_ids_differ() {
+        let templates = [bundled("x52pro.json")];
+
+        let by_name = best_template(&templates, &device(3, "Logitech X52 Pro", None)).unwrap();
+        assert_eq!(by_name.confidence, 0.7);
+        assert_eq!(by_name.reason, "Device name matches 'Logitech X52 Pro'");
+
+        let other_stick = best_template(
+            &templates,
+            &device(3, "Logitech X52 Pro", Some("06a3:075c")),
+        )
+        .unwrap();
+        assert_eq!(other_stick.confidence, 0.35);
+
+        assert!(best_template(&templates, &device(3, "Thrustmaster T16000M", None)).is_none());
+    }
+
+    #[test]
+    fn instance_breaks_the_tie_between_identical_sticks() {
+        let templates = [bundled("VKB Gladiator NXT Dual.json")];
+
+        let js1 = best_template(&templates, &device(1, "VKB Gladiator NXT", None)).unwrap();
+        assert_eq!(js1.page_name, "Right Stick");
+        let js2 = best_template(&templates, &device(2, "VKB Gladiator NXT", None)).unwrap();
+        assert_eq!(js2.page_name, "Left Stick");
+        assert_eq!(js1.confidence, js2.confidence);
+        assert!(js1.confidence > 0.6);
+
+        // The VID/PID still wins over the instance
+        let left = best_template(
+            &templates,
+            &device(1, "VKB Gladiator NXT", Some("231d:0201")),
+        )
+        .unwrap();
+        assert_eq!(left.page_name, "Left Stick");
+    }
+
+    #[test]
+    fn bundled_templates_are_found() {
+        let names: Vec<String> = find_templates(&[profiles_dir()])
+            .into_iter()
+            .map(|(_, template)| template.name)
+            .collect();
+        assert_eq!(
+            names,
+            ["Dual VKB Gladiator NXT", "VKB Gladiator NXT", "x52pro"]
+        );
+    }
+}
@@ -0,0 +1,506 @@
+use crate::error::AppError;
+use crate::keybindings::{Action, ActionMap, ActionMaps, AllBinds, InputType, Rebind};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Which rebinds to take from the donor layout
+/// Empty lists don't filter; every non-empty list has to match
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct TransplantSelection {
+    #[serde(default)]
+    pub action_maps: Vec<String>,
+    /// AllBinds `UICategory` values, e.g. "@ui_CCFPS"
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Device types ("keyboard", "mouse", "joystick", "gamepad") or
+    /// device instances as they appear in inputs ("js2", "kb1", "gp1")
+    #[serde(default)]
+    pub devices: Vec<String>,
+}
+
+/// What to do when the current profile already has something for the donor rebind
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransplantMode {
+    /// Donor rebind always replaces the current one for that device type
+    Overwrite,
+    /// Only fill actions that have no rebind (or a cleared one) for that device type
+    FillUnbound,
+    /// Skip if the action is bound differently for that device type,
+    /// or the donor input is already used by another action
+    SkipOnConflict,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TransplantChange {
+    pub action_map: String,
+    pub action: String,
+    pub old_input: Option<String>,
+    pub new_input: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TransplantSkip {
+    pub action_map: String,
+    pub action: String,
+    pub input: String,
+    pub reason: String,
+}
+
+/// Change report returned to the UI
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct TransplantReport {
+    pub applied: Vec<TransplantChange>,
+    pub skipped: Vec<TransplantSkip>,
+    pub unchanged: usize,
+}
+
+impl TransplantSelection {
+    fn matches_action_map(&self, name: &str, categories: &HashMap<&str, &str>) -> bool {
+        if !self.action_maps.is_empty() && !self.action_maps.iter().any(|am| am == name) {
+            return false;
+        }
+        if !self.categories.is_empty() {
+            let Some(category) = categories.get(name) else {
+                return false;
+            };
+            if !self.categories.iter().any(|c| c == category) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn matches_rebind(&self, rebind: &Rebind) -> bool {
+        if self.devices.is_empty() {
+            return true;
+        }
+
+        let device_type = format!("{:?}", rebind.get_device_type()).to_lowercase();
+        let device_token = device_token(&rebind.input);
+
+        self.devices.iter().any(|d| {
+            let d = d.to_lowercase();
+            d == device_type || Some(d.as_str()) == device_token.as_deref()
+        })
+    }
+}
+
+/// Device part of an input, e.g. "lalt+js2_button3" -> "js2", "kb1_a" -> "kb1"
+fn device_token(input: &str) -> Option<String> {
+    input.split('+').find_map(|part| {
+        let (device, _) = part.trim().split_once('_')?;
+        let is_device = ["kb", "mouse", "js", "gp"]
+            .iter()
+            .any(|prefix| device.starts_with(prefix));
+        is_device.then(|| device.to_lowercase())
+    })
+}
+
+fn is_cleared(rebind: &Rebind) -> bool {
+    rebind
+        .input
+        .trim()
+        .split_once('_')
+        .is_some_and(|(_, rest)| rest.trim().is_empty())
+}
+
+/// Copy the selected rebinds from `donor` into `target`
+pub fn transplant_bindings(
+    target: &mut ActionMaps,
+    donor: &ActionMaps,
+    selection: &TransplantSelection,
+    mode: TransplantMode,
+    all_binds: Option<&AllBinds>,
+) -> Result<TransplantReport, AppError> {
+    // Category selection needs AllBinds.xml
+    if !selection.categories.is_empty() && all_binds.is_none() {
+        return Err(AppError::AllBindsNotLoaded);
+    }
+
+    let categories: HashMap<&str, &str> = all_binds
+        .map(|ab| {
+            ab.action_maps
+                .iter()
+                .map(|am| (am.name.as_str(), am.ui_category.as_str()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut report = TransplantReport::default();
+
+    for donor_map in &donor.action_maps {
+        if !selection.matches_action_map(&donor_map.name, &categories) {
+            continue;
+        }
+
+        for donor_action in &donor_map.actions {
+            for donor_rebind in &donor_action.rebinds {
+                if !selection.matches_rebind(donor_rebind) {
+                    continue;
+                }
+
+                let device_type = donor_rebind.get_device_type();
+                if device_type == InputType::Unknown {
+                    continue;
+                }
+
+                let current = target
+                    .action_maps
+                    .iter()
+                    .find(|am| am.name == donor_map.name)
+                    .and_then(|am| am.actions.iter().find(|a| a.name == donor_action.name))
+                    .and_then(|a| {
+                        a.rebinds
+                            .iter()
+                            .find(|r| r.get_device_type() == device_type)
+                    });
+
+                if let Some(current) = current {
+                    if current.input == donor_rebind.input
+                        && current.activation_mode == donor_rebind.activation_mode
+                        && current.multi_tap == donor_rebind.multi_tap
+                    {
+                        report.unchanged += 1;
+                        continue;
+                    }
+                }
+
+                let skip_reason = match mode {
+                    TransplantMode::Overwrite => None,
+                    TransplantMode::FillUnbound => current
+                        .filter(|c| !is_cleared(c))
+                        .map(|c| format!("Already bound to {}", c.input)),
+                    TransplantMode::SkipOnConflict => {
+                        if let Some(c) = current.filter(|c| !is_cleared(c)) {
+                            Some(format!("Already bound to {}", c.input))
+                        } else if is_cleared(donor_rebind) {
+                            None
+                        } else {
+                            find_other_user(
+                                target,
+                                donor_rebind,
+                                &donor_map.name,
+                                &donor_action.name,
+                            )
+                            .map(|(am, a)| {
+                                format!("{} is already used by {}/{}", donor_rebind.input, am, a)
+                            })
+                        }
+                    }
+                };
+
+                if let Some(reason) = skip_reason {
+                    report.skipped.push(TransplantSkip {
+                        action_map: donor_map.name.clone(),
+                        action: donor_action.name.clone(),
+                        input: donor_rebind.input.clone(),
+                        reason,
+                    });
+                    continue;
+                }
+
+                let old_input = current.map(|c| c.input.clone());
+                set_rebind(
+                    target,
+                    &donor_map.name,
+                    &donor_action.name,
+                    donor_rebind.clone(),
+                );
+                report.applied.push(TransplantChange {
+                    action_map: donor_map.name.clone(),
+                    action: donor_action.name.clone(),
+                    old_input,
+                    new_input: donor_rebind.input.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Find another action in `target` that already uses the same input
+fn find_other_user(
+    target: &ActionMaps,
+    rebind: &Rebind,
+    action_map_name: &str,
+    action_name: &str,
+) -> Option<(String, String)> {
+    target.action_maps.iter().find_map(|am| {
+        am.actions
+            .iter()
+            .find(|a| {
+                !(am.name == action_map_name && a.name == action_name)
+                    && a.rebinds.iter().any(|r| r.input == rebind.input)
+            })
+            .map(|a| (am.name.clone(), a.name.clone()))
+    })
+}
+
+/// Insert a rebind, replacing any existing rebind of the same device type
+pub fn set_rebind(
+    target: &mut ActionMaps,
+    action_map_name: &str,
+    action_name: &str,
+    rebind: Rebind,
+) {
+    let action_map = match target
+        .action_maps
+        .iter()
+        .position(|am| am.name == action_map_name)
+    {
+        Some(idx) => &mut target.action_maps[idx],
+        None => {
+            target.action_maps.push(ActionMap {
+                name: action_map_name.to_string(),
+                actions: Vec::new(),
+            });
+            target.action_maps.last_mut().unwrap()
+        }
+    };
+
+    let action = match action_map
+        .actions
+        .iter()
+        .position(|a| a.name == action_name)
+    {
+        Some(idx) => &mut action_map.actions[idx],
+        None => {
+            action_map.actions.push(Action {
+                name: action_name.to_string(),
+                rebinds: Vec::new(),
+            });
+            action_map.actions.last_mut().unwrap()
+        }
+    };
+
+    // Star Citizen only allows ONE rebind per device TYPE per action
+    let device_type = rebind.get_device_type();
+    action
+        .rebinds
+        .retain(|r| r.get_device_type() != device_type);
+    action.rebinds.push(rebind);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A layout from (action map, action, inputs)
+    fn layout(actions: &[(&str, &str, &[&str])]) -> ActionMaps {
+        let mut maps =
+            ActionMaps::from_xml(r#"<ActionMaps profileName="test"></ActionMaps>"#).unwrap();
+        for (action_map, action, inputs) in actions {
+            if !maps.action_maps.iter().any(|am| am.name == *action_map) {
+                maps.action_maps.push(ActionMap {
+                    name: action_map.to_string(),
+                    actions: Vec::new(),
+                });
+            }
+            let action_map = maps
+                .action_maps
+                .iter_mut()
+                .find(|am| am.name == *action_map)
+                .unwrap();
+            action_map.actions.push(Action {
+                name: action.to_string(),
+                rebinds: inputs.iter().map(|input| rebind(input)).collect(),
+            });
+        }
+        maps
+    }
+
+    fn rebind(input: &str) -> Rebind {
+        Rebind {
+            input: input.to_string(),
+            multi_tap: None,
+            activation_mode: String::new(),
+        }
+    }
+
+    fn inputs<'a>(maps: &'a ActionMaps, action_map: &str, action: &str) -> Vec<&'a str> {
+        maps.action_maps
+            .iter()
+            .find(|am| am.name == action_map)
+            .and_then(|am| am.actions.iter().find(|a| a.name == action))
+            .map(|a| a.rebinds.iter().map(|r| r.input.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    fn transplant(
+        target: &mut ActionMaps,
+        donor: &ActionMaps,
+        mode: TransplantMode,
+    ) -> TransplantReport {
+        transplant_bindings(target, donor, &TransplantSelection::default(), mode, None).unwrap()
+    }
+
+    #[test]
+    fn set_rebind_replaces_only_the_same_device_type() {
+        let mut maps = layout(&[("seat_general", "v_eject", &["kb1_f", "js1_button3"])]);
+
+        set_rebind(&mut maps, "seat_general", "v_eject", rebind("js2_button7"));
+        assert_eq!(
+            inputs(&maps, "seat_general", "v_eject"),
+            ["kb1_f", "js2_button7"]
+        );
+
+        set_rebind(&mut maps, "seat_general", "v_eject", rebind("lalt+kb1_g"));
+        assert_eq!(
+            inputs(&maps, "seat_general", "v_eject"),
+            ["js2_button7", "lalt+kb1_g"]
+        );
+    }
+
+    #[test]
+    fn set_rebind_creates_missing_action_maps_and_actions() {
+        let mut maps = layout(&[]);
+        set_rebind(
+            &mut maps,
+            "spaceship_weapons",
+            "v_attack1",
+            rebind("js1_button1"),
+        );
+        assert_eq!(
+            inputs(&maps, "spaceship_weapons", "v_attack1"),
+            ["js1_button1"]
+        );
+    }
+
+    #[test]
+    fn overwrite_reports_the_replaced_input() {
+        let mut target = layout(&[
+            ("seat_general", "v_eject", &["kb1_f", "js1_button3"]),
+            ("seat_general", "v_exit", &["js1_button4"]),
+        ]);
+        let donor = layout(&[
+            ("seat_general", "v_eject", &["js1_button9"]),
+            ("seat_general", "v_exit", &["js1_button4"]),
+        ]);
+
+        let report = transplant(&mut target, &donor, TransplantMode::Overwrite);
+
+        assert_eq!(
+            inputs(&target, "seat_general", "v_eject"),
+            ["kb1_f", "js1_button9"]
+        );
+        assert_eq!(report.applied.len(), 1);
+        assert_eq!(report.applied[0].old_input.as_deref(), Some("js1_button3"));
+        assert_eq!(report.applied[0].new_input, "js1_button9");
+        assert_eq!(report.unchanged, 1);
+        assert!(report.skipped.is_empty());
+    }
+
+    #[test]
+    fn current_rebind_follows_earlier_donor_rebinds() {
+        // Two donor rebinds of one device type on one action: the second sees the first
+        let donor = layout(&[("seat_general", "v_eject", &["js1_button1", "js1_button2"])]);
+
+        let mut target = layout(&[("seat_general", "v_eject", &["js1_button3"])]);
+        let report = transplant(&mut target, &donor, TransplantMode::Overwrite);
+        assert_eq!(inputs(&target, "seat_general", "v_eject"), ["js1_button2"]);
+        let old_inputs: Vec<_> = report
+            .applied
+            .iter()
+            .map(|c| c.old_input.as_deref())
+            .collect();
+        assert_eq!(old_inputs, [Some("js1_button3"), Some("js1_button1")]);
+
+        let mut target = layout(&[]);
+        let report = transplant(&mut target, &donor, TransplantMode::FillUnbound);
+        assert_eq!(inputs(&target, "seat_general", "v_eject"), ["js1_button1"]);
+        assert_eq!(report.applied.len(), 1);
+        assert_eq!(report.applied[0].old_input, None);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].reason, "Already bound to js1_button1");
+    }
+
+    #[test]
+    fn fill_unbound_only_fills_empty_and_cleared_rebinds() {
+        let mut target = layout(&[
+            ("seat_general", "v_eject", &["js1_button3"]),
+            ("seat_general", "v_exit", &["js1_ "]),
+        ]);
+        let donor = layout(&[
+            ("seat_general", "v_eject", &["js1_button9"]),
+            ("seat_general", "v_exit", &["js1_button8"]),
+            ("seat_general", "v_lock", &["kb1_l"]),
+        ]);
+
+        let report = transplant(&mut target, &donor, TransplantMode::FillUnbound);
+
+        assert_eq!(inputs(&target, "seat_general", "v_eject"), ["js1_button3"]);
+        assert_eq!(inputs(&target, "seat_general", "v_exit"), ["js1_button8"]);
+        assert_eq!(inputs(&target, "seat_general", "v_lock"), ["kb1_l"]);
+        let applied: Vec<_> = report
+            .applied
+            .iter()
+            .map(|c| (c.action.as_str(), c.old_input.as_deref()))
+            .collect();
+        assert_eq!(applied, [("v_exit", Some("js1_ ")), ("v_lock", None)]);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].action, "v_eject");
+    }
+
+    #[test]
+    fn skip_on_conflict_skips_inputs_used_elsewhere() {
+        let mut target = layout(&[("seat_general", "v_exit", &["js1_button8"])]);
+        let donor = layout(&[
+            ("seat_general", "v_eject", &["js1_button8"]),
+            ("seat_general", "v_lock", &["js1_button9"]),
+        ]);
+
+        let report = transplant(&mut target, &donor, TransplantMode::SkipOnConflict);
+
+        assert!(inputs(&target, "seat_general", "v_eject").is_empty());
+        assert_eq!(inputs(&target, "seat_general", "v_lock"), ["js1_button9"]);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(
+            report.skipped[0].reason,
+            "js1_button8 is already used by seat_general/v_exit"
+        );
+    }
+
+    #[test]
+    fn selection_filters_devices_and_needs_all_binds_for_categories() {
+        let donor = layout(&[
+            ("seat_general", "v_eject", &["kb1_f", "js2_button3"]),
+            ("seat_general", "v_exit", &["js1_button4"]),
+        ]);
+
+        let mut target = layout(&[]);
+        let selection = TransplantSelection {
+            devices: vec!["js2".to_string(), "keyboard".to_string()],
+            ..Default::default()
+        };
+        transplant_bindings(
+            &mut target,
+            &donor,
+            &selection,
+            TransplantMode::Overwrite,
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            inputs(&target, "seat_general", "v_eject"),
+            ["kb1_f", "js2_button3"]
+        );
+        assert!(inputs(&target, "seat_general", "v_exit").is_empty());
+
+        let selection = TransplantSelection {
+            categories: vec!["@ui_CCSpaceFlight".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            transplant_bindings(
+                &mut target,
+                &donor,
+                &selection,
+                TransplantMode::Overwrite,
+                None
+            ),
+            Err(AppError::AllBindsNotLoaded)
+        ));
+    }
+}
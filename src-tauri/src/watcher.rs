@@ -0,0 +1,307 @@
+use crate::keybindings::{ActionMaps, Rebind};
+use crate::sc_install;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tauri::Emitter;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// Content hashes of files this app wrote itself, so our own saves don't trigger events
+static OWN_WRITES: OnceLock<Mutex<HashMap<PathBuf, u64>>> = OnceLock::new();
+
+fn content_hash(contents: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Remember that we just wrote `contents` to `path`
+pub fn note_own_write(path: &Path, contents: &[u8]) {
+    let own = OWN_WRITES.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Ok(mut own) = own.lock() {
+        own.insert(normalize(path), content_hash(contents));
+    }
+}
+
+fn is_own_write(path: &Path) -> bool {
+    let Some(own) = OWN_WRITES.get() else {
+        return false;
+    };
+    let Ok(own) = own.lock() else {
+        return false;
+    };
+    match (own.get(&normalize(path)), fs::read(path)) {
+        (Some(hash), Ok(contents)) => *hash == content_hash(&contents),
+        _ => false,
+    }
+}
+
+/// Payload of the "bindings-file-changed" event
+#[derive(Debug, Serialize, Clone)]
+pub struct FileChangeEvent {
+    pub path: String,
+    /// "modified", "created" or "removed"
+    pub kind: String,
+    /// True when this is the layout currently loaded in the editor
+    pub is_loaded_file: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    size: u64,
+}
+
+fn stamp(path: &Path) -> Option<FileStamp> {
+    let meta = fs::metadata(path).ok()?;
+    meta.is_file().then(|| FileStamp {
+        modified: meta.modified().ok(),
+        size: meta.len(),
+    })
+}
+
+/// Current stamps of the loaded file and every XML in the watched mapping folders
+fn snapshot(loaded_file: Option<&Path>, folders: &[PathBuf]) -> HashMap<PathBuf, FileStamp> {
+    let mut files = HashMap::new();
+
+    if let Some(path) = loaded_file {
+        if let Some(s) = stamp(path) {
+            files.insert(path.to_path_buf(), s);
+        }
+    }
+
+    for folder in folders {
+        let Ok(entries) = fs::read_dir(folder) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("xml"))
+            {
+                continue;
+            }
+            if let Some(s) = stamp(&path) {
+                files.insert(path, s);
+            }
+        }
+    }
+
+    files
+}
+
+/// Background poller for external changes to layout files
+#[derive(Default)]
+pub struct FileWatcher {
+    stop: Option<Arc<AtomicBool>>,
+}
+
+impl FileWatcher {
+    /// Start watching (replacing any previous watch)
+    /// `installations` are channel folders; their `controls/mappings` folders are watched
+    pub fn start(
+        &mut self,
+        app_handle: tauri::AppHandle,
+        loaded_file: Option<PathBuf>,
+        installations: &[PathBuf],
+    ) {
+        self.stop();
+
+        let folders: Vec<PathBuf> = installations
+            .iter()
+            .map(|install| sc_install::mappings_dir(install))
+            .collect();
+
+        info!(
+            "Watching {:?} and {} mapping folder(s) for changes",
+            loaded_file,
+            folders.len()
+        );
+
+        let stop = Arc::new(AtomicBool::new(false));
+        self.stop = Some(stop.clone());
+
+        thread::spawn(move || {
+            let loaded_norm = loaded_file.as_deref().map(normalize);
+            let mut previous = snapshot(loaded_file.as_deref(), &folders);
+
+            while !stop.load(Ordering::Relaxed) {
+                thread::sleep(POLL_INTERVAL);
+
+                let current = snapshot(loaded_file.as_deref(), &folders);
+                let paths: BTreeSet<&PathBuf> = previous.keys().chain(current.keys()).collect();
+
+                for path in paths {
+                    let kind = match (previous.get(path), current.get(path)) {
+                        (None, Some(_)) => "created",
+                        (Some(_), None) => "removed",
+                        (Some(a), Some(b)) if a != b => "modified",
+                        _ => continue,
+                    };
+
+                    if kind != "removed" && is_own_write(path) {
+                        continue;
+                    }
+
+                    let event = FileChangeEvent {
+                        path: path.to_string_lossy().to_string(),
+                        kind: kind.to_string(),
+                        is_loaded_file: loaded_norm.as_deref() == Some(normalize(path).as_path()),
+                    };
+                    info!("External change: {} {}", event.kind, event.path);
+                    if let Err(e) = app_handle.emit("bindings-file-changed", &event) {
+                        warn!("Failed to emit file change event: {}", e);
+                    }
+                }
+
+                previous = current;
+            }
+
+            info!("File watcher stopped");
+        });
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// How to take an external change on board
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExternalChangeResolution {
+    /// Drop in-memory edits and use the file as it is on disk
+    Reload,
+    /// Keep in-memory edits, take the file's changes everywhere else
+    Merge,
+}
+
+/// A binding changed both in memory and on disk; the in-memory one was kept
+#[derive(Debug, Serialize, Clone)]
+pub struct MergeConflict {
+    pub action_map: String,
+    pub action: String,
+    pub device_type: String,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct MergeReport {
+    /// Bindings taken from the file on disk
+    pub taken: usize,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+type BindingKey = (String, String, String);
+
+fn binding_table(bindings: &ActionMaps) -> HashMap<BindingKey, &Rebind> {
+    let mut table = HashMap::new();
+    for action_map in &bindings.action_maps {
+        for action in &action_map.actions {
+            for rebind in &action.rebinds {
+                let device = format!("{:?}", rebind.get_device_type());
+                table.insert(
+                    (action_map.name.clone(), action.name.clone(), device),
+                    rebind,
+                );
+            }
+        }
+    }
+    table
+}
+
+fn same(a: Option<&&Rebind>, b: Option<&&Rebind>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => {
+            a.input == b.input
+                && a.activation_mode == b.activation_mode
+                && a.multi_tap == b.multi_tap
+        }
+        _ => false,
+    }
+}
+
+/// Three-way merge of bindings, per action and device type
+/// `base` is the version last read from or written to disk, `ours` the in-memory
+/// bindings, `theirs` the file as changed by someone else
+pub fn merge_bindings(
+    base: &ActionMaps,
+    ours: &ActionMaps,
+    theirs: &ActionMaps,
+) -> (ActionMaps, MergeReport) {
+    let base_table = binding_table(base);
+    let our_table = binding_table(ours);
+    let their_table = binding_table(theirs);
+
+    let keys: BTreeSet<&BindingKey> = base_table
+        .keys()
+        .chain(our_table.keys())
+        .chain(their_table.keys())
+        .collect();
+
+    let mut merged = ours.clone();
+    let mut report = MergeReport::default();
+
+    for key in keys {
+        let b = base_table.get(key);
+        let o = our_table.get(key);
+        let t = their_table.get(key);
+
+        if same(o, t) || same(b, t) {
+            // Nothing new on disk, or both sides agree
+            continue;
+        }
+
+        if !same(o, b) {
+            // Changed on both sides - keep ours and report it
+            report.conflicts.push(MergeConflict {
+                action_map: key.0.clone(),
+                action: key.1.clone(),
+                device_type: key.2.clone(),
+                ours: o.map(|r| r.input.clone()),
+                theirs: t.map(|r| r.input.clone()),
+            });
+            continue;
+        }
+
+        // Only changed on disk - take theirs
+        report.taken += 1;
+        match t {
+            Some(rebind) => {
+                crate::transplant::set_rebind(&mut merged, &key.0, &key.1, (*rebind).clone())
+            }
+            None => {
+                if let Some(action) = merged
+                    .action_maps
+                    .iter_mut()
+                    .find(|am| am.name == key.0)
+                    .and_then(|am| am.actions.iter_mut().find(|a| a.name == key.1))
+                {
+                    action
+                        .rebinds
+                        .retain(|r| format!("{:?}", r.get_device_type()) != key.2);
+                }
+            }
+        }
+    }
+
+    (merged, report)
+}
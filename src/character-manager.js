@@ -2,7 +2,6 @@
 // Manages SC character appearance backups and deployments
 
 const { invoke } = window.__TAURI__.core;
-const { open } = window.__TAURI__.dialog;
 
 class CharacterManager
 {
@@ -13,6 +12,7 @@ class CharacterManager
         this.activeInstallation = null;
         this.masterCharacters = [];
         this.installationCharacters = {};
+        this.syncReport = {}; // character name -> content comparison from the backend
 
         this.init();
     }
@@ -47,6 +47,12 @@ class CharacterManager
 
     setupEventListeners()
     {
+        // Sync to All button
+        document.getElementById('sync-characters-btn')?.addEventListener('click', async () =>
+        {
+            await this.syncLibraryToAll();
+        });
+
         // Set Library Path button
         document.getElementById('set-library-path-btn')?.addEventListener('click', async () =>
         {
@@ -105,11 +111,8 @@ class CharacterManager
     {
         try
         {
-            const selectedPath = await open({
-                directory: true,
-                multiple: false,
-                title: 'Select Character Library Directory'
-            });
+            // The backend opens the dialog and allows file access to the picked folder
+            const selectedPath = await invoke('choose_folder', { role: 'character_library' });
 
             if (selectedPath)
             {
@@ -167,6 +170,7 @@ class CharacterManager
             });
 
             this.masterCharacters = characters;
+            await this.loadSyncReport();
             this.renderMasterCharacters();
         } catch (error)
         {
@@ -200,6 +204,8 @@ class CharacterManager
                     await this.loadInstallationCharacters(install);
                 }
 
+                await this.loadSyncReport();
+                this.renderMasterCharacters();
                 this.renderInstallationTabs();
 
                 // Activate first installation by default
@@ -218,6 +224,140 @@ class CharacterManager
         }
     }
 
+    async loadSyncReport()
+    {
+        const scDirectory = localStorage.getItem('scInstallDirectory');
+        if (!scDirectory)
+        {
+            this.syncReport = {};
+            return;
+        }
+
+        try
+        {
+            const report = await invoke('compare_character_files', {
+                libraryPath: this.libraryPath,
+                basePath: scDirectory
+            });
+
+            this.syncReport = {};
+            for (const entry of report)
+            {
+                this.syncReport[entry.name] = entry;
+            }
+        } catch (error)
+        {
+            console.error('Error comparing character files:', error);
+            this.syncReport = {};
+        }
+    }
+
+    // 'identical', 'different', 'missing', or null when unknown
+    getCopyStatus(characterName, installName)
+    {
+        const entry = this.syncReport[characterName];
+        const copy = entry?.copies.find(c => c.installation === installName);
+        return copy ? copy.status : null;
+    }
+
+    // Run a copy command; if the target holds a different file, ask before overwriting it
+    async copyCharacter(command, args, targetLabel)
+    {
+        const result = await invoke(command, { ...args, overwrite: false });
+        if (result.status !== 'conflict')
+        {
+            return result;
+        }
+
+        const confirmed = await window.showConfirmation(
+            `"${args.characterName}" in ${targetLabel} is different from the file being copied.\n\nOverwrite it? The current file is backed up first.`,
+            'Character Differs',
+            'Overwrite',
+            'Cancel',
+            'btn-danger'
+        );
+
+        if (!confirmed)
+        {
+            return result;
+        }
+
+        return await invoke(command, { ...args, overwrite: true });
+    }
+
+    // Ask once about a batch of conflicting targets
+    async confirmOverwrite(conflicts)
+    {
+        const list = conflicts.map(c => `• ${c.target_path}`).join('\n');
+        return await window.showConfirmation(
+            `${conflicts.length} file(s) differ from the library version:\n\n${list}\n\nOverwrite them? The current files are backed up first.`,
+            'Characters Differ',
+            'Overwrite',
+            'Skip These',
+            'btn-danger'
+        );
+    }
+
+    describeResults(results)
+    {
+        const count = status => results.filter(r => r.status === status).length;
+        const parts = [`${count('copied')} copied`, `${count('unchanged')} already up to date`];
+        if (count('conflict') > 0) parts.push(`${count('conflict')} skipped`);
+        if (count('failed') > 0) parts.push(`${count('failed')} failed`);
+        return parts.join(', ');
+    }
+
+    async syncLibraryToAll()
+    {
+        const scDirectory = localStorage.getItem('scInstallDirectory');
+        if (!this.libraryPath || !scDirectory)
+        {
+            this.showError('Library path and SC directory must be configured');
+            return;
+        }
+
+        try
+        {
+            const args = { libraryPath: this.libraryPath, basePath: scDirectory };
+            let results = await invoke('sync_character_library', { ...args, overwrite: false });
+
+            const conflicts = results.filter(r => r.status === 'conflict');
+            if (conflicts.length > 0 && await this.confirmOverwrite(conflicts))
+            {
+                // Everything else is already in place, so the second pass only touches the conflicts
+                const overwritten = await invoke('sync_character_library', { ...args, overwrite: true });
+                const byTarget = new Map(overwritten.map(r => [r.target_path, r]));
+                results = results.map(r => r.status === 'conflict' ? (byTarget.get(r.target_path) || r) : r);
+            }
+
+            for (const failed of results.filter(r => r.status === 'failed'))
+            {
+                console.error(`Failed to sync ${failed.name} to ${failed.target_path}:`, failed.error);
+            }
+
+            this.showSuccess(`Synced library to all installations: ${this.describeResults(results)}`);
+            await this.refreshAfterCopy();
+        } catch (error)
+        {
+            console.error('Error syncing character library:', error);
+            this.showError('Failed to sync character library: ' + error);
+        }
+    }
+
+    async refreshAfterCopy()
+    {
+        for (const installation of this.installations)
+        {
+            await this.loadInstallationCharacters(installation);
+        }
+        await this.loadMasterCharacters();
+
+        if (this.activeInstallation)
+        {
+            this.renderInstallationContent(this.activeInstallation);
+        }
+    }
+
     async loadInstallationCharacters(installation)
     {
         try
@@ -293,6 +433,10 @@ class CharacterManager
         for (const [installName, characters] of Object.entries(this.installationCharacters))
         {
             const installChar = characters.find(c => c.name === masterChar.name);
+            if (this.getCopyStatus(masterChar.name, installName) === 'identical')
+            {
+                continue;
+            }
             if (installChar && installChar.modified > masterChar.modified)
             {
                 return true;
@@ -337,7 +481,8 @@ class CharacterManager
             await invoke('import_character_to_library', {
                 characterName,
                 installationPath: newestInstallation.path,
-                libraryPath: this.libraryPath
+                libraryPath: this.libraryPath,
+                overwrite: true
             });
 
             this.showSuccess(`Updated ${characterName} in library from ${newestInstallation.name} (${this.formatDate(newestTimestamp)})`);
@@ -377,6 +522,9 @@ class CharacterManager
             if (!installChar)
             {
                 hasMissing = true;
+            } else if (this.getCopyStatus(masterChar.name, installName) === 'identical')
+            {
+                continue;
             } else if (installChar.modified > masterChar.modified)
             {
                 hasNewer = true;
@@ -620,15 +768,22 @@ class CharacterManager
 
         try
         {
-            await invoke('deploy_character_to_installation', {
+            const result = await this.copyCharacter('deploy_character_to_installation', {
                 characterName,
                 libraryPath: this.libraryPath,
                 installationPath: installation.path
-            });
+            }, installName);
 
-            this.showSuccess(`Deployed ${characterName} to ${installName}`);
-            await this.loadInstallationCharacters(installation);
-            this.renderInstallationContent(installName);
+            if (result.status === 'conflict')
+            {
+                this.showNotification(`Kept the existing ${characterName} in ${installName}`, 'info');
+                return;
+            }
+
+            this.showSuccess(result.status === 'unchanged'
+                ? `${characterName} in ${installName} is already up to date`
+                : `Deployed ${characterName} to ${installName}`);
+            await this.refreshAfterCopy();
         } catch (error)
         {
             console.error('Error deploying character:', error);
@@ -653,18 +808,12 @@ class CharacterManager
 
         try
         {
-            for (const char of this.masterCharacters)
-            {
-                await invoke('deploy_character_to_installation', {
-                    characterName: char.name,
-                    libraryPath: this.libraryPath,
-                    installationPath: installation.path
-                });
-            }
+            const results = await this.deployBatch(
+                this.masterCharacters.map(char => ({ characterName: char.name, installation }))
+            );
 
-            this.showSuccess(`Deployed all characters to ${installName}`);
-            await this.loadInstallationCharacters(installation);
-            this.renderInstallationContent(installName);
+            this.showSuccess(`Deployed characters to ${installName}: ${this.describeResults(results)}`);
+            await this.refreshAfterCopy();
         } catch (error)
         {
             console.error('Error deploying all characters:', error);
@@ -672,6 +821,39 @@ class CharacterManager
         }
     }
 
+    // Deploy several characters, then ask once about any that would replace a different file
+    async deployBatch(items)
+    {
+        const results = [];
+        for (const { characterName, installation } of items)
+        {
+            const result = await invoke('deploy_character_to_installation', {
+                characterName,
+                libraryPath: this.libraryPath,
+                installationPath: installation.path,
+                overwrite: false
+            });
+            results.push({ ...result, installation });
+        }
+
+        const conflicts = results.filter(r => r.status === 'conflict');
+        if (conflicts.length > 0 && await this.confirmOverwrite(conflicts))
+        {
+            for (const conflict of conflicts)
+            {
+                const result = await invoke('deploy_character_to_installation', {
+                    characterName: conflict.name,
+                    libraryPath: this.libraryPath,
+                    installationPath: conflict.installation.path,
+                    overwrite: true
+                });
+                Object.assign(conflict, result);
+            }
+        }
+
+        return results;
+    }
+
     async importToLibrary(characterName, installName)
     {
         if (!this.libraryPath)
@@ -689,16 +871,22 @@ class CharacterManager
 
         try
         {
-            await invoke('import_character_to_library', {
+            const result = await this.copyCharacter('import_character_to_library', {
                 characterName,
                 installationPath: installation.path,
                 libraryPath: this.libraryPath
-            });
+            }, 'the library');
 
-            this.showSuccess(`Imported ${characterName} to library`);
-            await this.loadMasterCharacters();
-            await this.loadInstallationCharacters(installation);
-            this.renderInstallationContent(installName);
+            if (result.status === 'conflict')
+            {
+                this.showNotification(`Kept the existing ${characterName} in the library`, 'info');
+                return;
+            }
+
+            this.showSuccess(result.status === 'unchanged'
+                ? `${characterName} in the library is already up to date`
+                : `Imported ${characterName} to library`);
+            await this.refreshAfterCopy();
         } catch (error)
         {
             console.error('Error importing character:', error);
@@ -722,30 +910,14 @@ class CharacterManager
 
         try
         {
-            for (const installation of this.installations)
-            {
-                await invoke('deploy_character_to_installation', {
-                    characterName,
-                    libraryPath: this.libraryPath,
-                    installationPath: installation.path
-                });
-            }
+            const results = await this.deployBatch(
+                this.installations.map(installation => ({ characterName, installation }))
+            );
 
-            this.showSuccess(`Deployed ${characterName} to all installations`);
+            this.showSuccess(`Deployed ${characterName} to all installations: ${this.describeResults(results)}`);
 
-            // Reload all installation characters
-            for (const installation of this.installations)
-            {
-                await this.loadInstallationCharacters(installation);
-            }
-
-            // Re-render master characters to update sync status
-            this.renderMasterCharacters();
-
-            if (this.activeInstallation)
-            {
-                this.renderInstallationContent(this.activeInstallation);
-            }
+            // Reload all installation characters and the sync status
+            await this.refreshAfterCopy();
         } catch (error)
         {
             console.error('Error exporting character:', error);
@@ -0,0 +1,45 @@
+// Command Errors
+// Backend commands reject with {code, message, details}. Wrap invoke so callers get an
+// Error whose string form is the message (as before) and which still carries code/details.
+
+(function ()
+{
+    const core = window.__TAURI__?.core;
+    if (!core || core.invoke.__wrapsCommandErrors) return;
+
+    class CommandError extends Error
+    {
+        constructor(payload)
+        {
+            super(payload.message);
+            this.name = 'CommandError';
+            this.code = payload.code;
+            this.details = payload.details ?? null;
+        }
+
+        toString()
+        {
+            return this.message;
+        }
+    }
+
+    const invoke = core.invoke;
+    const wrapped = async (...args) =>
+    {
+        try
+        {
+            return await invoke(...args);
+        } catch (error)
+        {
+            if (error && typeof error === 'object' && typeof error.code === 'string' && 'message' in error)
+            {
+                throw new CommandError(error);
+            }
+            throw error;
+        }
+    };
+    wrapped.__wrapsCommandErrors = true;
+
+    core.invoke = wrapped;
+    window.CommandError = CommandError;
+})();
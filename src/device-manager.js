@@ -71,6 +71,18 @@ function setupEventListeners()
     document.getElementById('dm-clear-debug-btn')?.addEventListener('click', clearDebuggerLog);
     document.getElementById('dm-refresh-devices-btn')?.addEventListener('click', refreshDeviceList);
 
+    // The backend HID service reports controllers being plugged in or out
+    listen('device-connected', (event) =>
+    {
+        console.log('[DEVICE-MANAGER] Device connected:', event.payload);
+        refreshDeviceList();
+    });
+    listen('device-disconnected', (event) =>
+    {
+        console.log('[DEVICE-MANAGER] Device disconnected:', event.payload);
+        refreshDeviceList();
+    });
+
     // Keyboard event listener for debugging
     document.addEventListener('keydown', handleKeyboardInput, true);
 }
@@ -309,7 +309,7 @@ function convertKeyCodeToSC(code, key, location)
         'Home': 'home',
         'End': 'end',
         'PageUp': 'pgup',
-        'PageDown': 'pgdown',
+        'PageDown': 'pgdn',
         'ArrowUp': 'up',
         'ArrowDown': 'down',
         'ArrowLeft': 'left',
@@ -1,5 +1,5 @@
 const { invoke } = window.__TAURI__.core;
-const { open, save } = window.__TAURI__.dialog;
+const { listen } = window.__TAURI__.event;
 
 // Import shared rendering utilities
 import
@@ -73,6 +73,12 @@ let currentPageIndex = 0; // Currently viewing page index
 let hideDefaultBindings = false; // Filter to hide default bindings
 let modifierFilter = 'all'; // Current modifier filter: 'all', 'lalt', 'lctrl', etc.
 
+// Live input highlighting (fed by the backend "input-state" stream)
+const LIVE_INPUT_RATE_HZ = 30;
+const LIVE_AXIS_THRESHOLD = 0.5; // Axes count as active past this deflection
+let liveInputs = new Set(); // SC input names currently active, e.g. "js1_button3"
+let liveInputUnlisten = null;
+
 // Export bounds tracking
 let drawBounds = { minX: Infinity, minY: Infinity, maxX: -Infinity, maxY: -Infinity };
 
@@ -442,6 +448,12 @@ function initializeEventListeners()
     const selectTemplateBtn = document.getElementById('select-template-btn');
     if (selectTemplateBtn) selectTemplateBtn.addEventListener('click', openTemplateModal);
 
+    const autoTemplateBtn = document.getElementById('auto-template-btn');
+    if (autoTemplateBtn) autoTemplateBtn.addEventListener('click', autoSelectTemplates);
+
+    const liveInputBtn = document.getElementById('live-input-btn');
+    if (liveInputBtn) liveInputBtn.addEventListener('click', toggleLiveInput);
+
     const welcomeSelectBtn = document.getElementById('welcome-select-btn');
     if (welcomeSelectBtn) welcomeSelectBtn.addEventListener('click', openTemplateModal);
 
@@ -519,6 +531,153 @@ async function openTemplateModal()
     document.getElementById('template-file-input').click();
 }
 
+// Below this the match is only a loose name similarity
+const AUTO_TEMPLATE_MIN_CONFIDENCE = 0.5;
+
+// Load the best bundled/library template for each connected device
+async function autoSelectTemplates()
+{
+    try
+    {
+        const devices = await invoke('detect_joysticks');
+        const matches = await invoke('match_device_templates', { devices });
+
+        const confident = matches.filter(m => m.best && m.best.confidence >= AUTO_TEMPLATE_MIN_CONFIDENCE);
+        if (confident.length === 0)
+        {
+            await window.showAlert('No matching templates found for the connected devices.', 'Auto Select');
+            return;
+        }
+
+        // Several devices can share one template (e.g. a dual-stick template)
+        const paths = [...new Set(confident.map(m => m.best.path))];
+        for (const path of paths)
+        {
+            const { template } = await invoke('load_template', { filePath: path });
+            const fileName = path.split(/[\\/]/).pop();
+            const existingIndex = loadedTemplates.findIndex(t => t.fileName === fileName);
+            if (existingIndex !== -1)
+            {
+                loadedTemplates[existingIndex].template = normalizeTemplateData(template);
+            }
+            else
+            {
+                loadedTemplates.push({ template: normalizeTemplateData(template), fileName });
+            }
+        }
+
+        // Show the page of the most confident match
+        const first = confident.reduce((a, b) => (b.best.confidence > a.best.confidence ? b : a));
+        currentTemplateIndex = loadedTemplates.findIndex(t => t.fileName === first.best.path.split(/[\\/]/).pop());
+        const pages = loadedTemplates[currentTemplateIndex].template.pages || [];
+        currentPageIndex = Math.max(0, pages.findIndex(p => p.id === first.best.page_id));
+
+        confident.forEach(m => console.log(
+            `[VIEWER] js${m.instance} ${m.device_name} -> ${m.best.template_name} / ${m.best.page_name} (${Math.round(m.best.confidence * 100)}%: ${m.best.reason})`));
+
+        saveLoadedTemplates();
+        updateTemplateTabs();
+        displayTemplate();
+    } catch (error)
+    {
+        console.error('Error auto-selecting templates:', error);
+        await window.showAlert(`Failed to auto-select templates: ${error}`, 'Error');
+    }
+}
+
+// Start or stop highlighting controls as they are pressed
+async function toggleLiveInput()
+{
+    const btn = document.getElementById('live-input-btn');
+
+    if (liveInputUnlisten)
+    {
+        liveInputUnlisten();
+        liveInputUnlisten = null;
+        liveInputs = new Set();
+        btn?.classList.remove('active');
+        try
+        {
+            await invoke('stop_input_stream');
+        } catch (error)
+        {
+            console.error('[VIEWER] Failed to stop input stream:', error);
+        }
+        if (getCurrentTemplate()) resizeViewerCanvas();
+        return;
+    }
+
+    try
+    {
+        liveInputUnlisten = await listen('input-state', (event) => onInputState(event.payload));
+        await invoke('start_input_stream', { rateHz: LIVE_INPUT_RATE_HZ });
+        btn?.classList.add('active');
+    } catch (error)
+    {
+        console.error('[VIEWER] Failed to start input stream:', error);
+        if (liveInputUnlisten)
+        {
+            liveInputUnlisten();
+            liveInputUnlisten = null;
+        }
+    }
+}
+
+// The stream reads the same devices as input detection, so it only runs while the viewer is shown
+window.stopLiveInput = async function ()
+{
+    if (liveInputUnlisten) await toggleLiveInput();
+};
+
+function onInputState(payload)
+{
+    const next = new Set();
+    (payload.devices || []).forEach(device =>
+    {
+        device.active.forEach(input => next.add(input));
+        Object.entries(device.axes).forEach(([input, value]) =>
+        {
+            if (Math.abs(value) > LIVE_AXIS_THRESHOLD) next.add(input);
+        });
+    });
+
+    // Only redraw when something was pressed or released
+    const changed = next.size !== liveInputs.size || [...next].some(input => !liveInputs.has(input));
+    liveInputs = next;
+    if (changed && getCurrentTemplate())
+    {
+        resizeViewerCanvas();
+    }
+}
+
+// Whether the physical control (or one of the hat's directions) is active right now
+function isButtonLive(button, directions = null)
+{
+    if (liveInputs.size === 0) return false;
+
+    const identifiers = directions
+        ? directions.filter(dir => button.inputs && button.inputs[dir]).map(dir => extractButtonIdentifier(button, dir))
+        : [extractButtonIdentifier(button)];
+
+    return identifiers.some(({ buttonNum, inputString, jsPrefix }) =>
+    {
+        if (inputString && liveInputs.has(inputString)) return true;
+        return buttonNum !== null && liveInputs.has(`${jsPrefix}button${buttonNum}`);
+    });
+}
+
+function drawLiveHighlight(pos)
+{
+    const accentPrimary = getComputedStyle(document.documentElement).getPropertyValue('--accent-primary').trim();
+    ctx.save();
+    ctx.strokeStyle = accentPrimary;
+    ctx.lineWidth = 3;
+    ctx.beginPath();
+    ctx.arc(pos.x, pos.y, 14, 0, Math.PI * 2);
+    ctx.stroke();
+    ctx.restore();
+}
+
 function closeTemplateModal()
 {
     document.getElementById('template-modal').style.display = 'none';
@@ -1343,6 +1502,10 @@ function drawSingleButton(button, mode = DrawMode.NORMAL)
 
     // Draw button position marker
     drawButtonMarker(ctx, button.buttonPos, 1, bindings.length > 0, false);
+    if (mode === DrawMode.NORMAL && isButtonLive(button))
+    {
+        drawLiveHighlight(button.buttonPos);
+    }
 
     // Draw label box with binding info
     if (button.labelPos)
@@ -1390,6 +1553,10 @@ function drawHat4Way(hat, mode = DrawMode.NORMAL)
 
     // Draw center point marker
     drawButtonMarker(ctx, hat.buttonPos, 1, false, true);
+    if (mode === DrawMode.NORMAL && isButtonLive(hat, directions))
+    {
+        drawLiveHighlight(hat.buttonPos);
+    }
 
     // Note: Hat connecting line is now drawn in drawConnectingLineForButton (first pass)
     // This ensures button frames are always drawn on top of lines
@@ -1500,6 +1667,10 @@ function drawHat2WayVertical(hat, mode = DrawMode.NORMAL)
 
     // Draw center point marker
     drawButtonMarker(ctx, hat.buttonPos, 1, false, true);
+    if (mode === DrawMode.NORMAL && isButtonLive(hat, directions))
+    {
+        drawLiveHighlight(hat.buttonPos);
+    }
 
     // Callback to register clickable boxes
     const onClickableBox = (box) =>
@@ -1596,6 +1767,10 @@ function drawHat2WayHorizontal(hat, mode = DrawMode.NORMAL)
 
     // Draw center point marker
     drawButtonMarker(ctx, hat.buttonPos, 1, false, true);
+    if (mode === DrawMode.NORMAL && isButtonLive(hat, directions))
+    {
+        drawLiveHighlight(hat.buttonPos);
+    }
 
     // Callback to register clickable boxes
     const onClickableBox = (box) =>
@@ -2689,14 +2864,10 @@ async function exportToImage()
                     console.warn('Could not get resource directory:', e);
                 }
 
-                const filePath = await save({
-                    defaultPath: resourceDir ? `${resourceDir}/${fileName}` : fileName,
-                    filters: [
-                        {
-                            name: 'PNG Image',
-                            extensions: ['png']
-                        }
-                    ]
+                const filePath = await invoke('choose_file', {
+                    save: true,
+                    filter: { name: 'PNG Image', extensions: ['png'] },
+                    defaultPath: resourceDir ? `${resourceDir}/${fileName}` : fileName
                 });
 
                 if (!filePath)
@@ -2711,7 +2882,7 @@ async function exportToImage()
                 const arrayBuffer = await blob.arrayBuffer();
                 const uint8Array = new Uint8Array(arrayBuffer);
 
-                // Call Tauri command to save file
+                // Call Tauri command to save file (only allowed inside the picked folder)
                 await invoke('write_binary_file', {
                     path: filePath,
                     contents: Array.from(uint8Array)
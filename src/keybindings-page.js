@@ -287,7 +287,7 @@ function convertKeyCodeToSC(code, key) {
         'Home': 'home',
         'End': 'end',
         'PageUp': 'pgup',
-        'PageDown': 'pgdown',
+        'PageDown': 'pgdn',
         'ArrowUp': 'up',
         'ArrowDown': 'down',
         'ArrowLeft': 'left',
@@ -469,13 +469,12 @@ async function handleDetectedInput(processedInput) {
     // Scenario 2: Second Input (Potential Conflict or Modifier Combo)
     else if (allDetectedInputs.size === 2) {
         // Check for Auto-Combo (Modifier + Key)
+        // The backend flags keyboard modifiers and the profile's declared modifiers (e.g. js1_button5)
         const firstInput = Array.from(allDetectedInputs.values())[0];
-        const isFirstModifier = ['lshift', 'rshift', 'lctrl', 'rctrl', 'lalt', 'ralt', 'lwin'].some(mod =>
-            firstInput.scFormattedInput.includes(mod)
-        );
+        const isFirstModifier = firstInput.originalResult?.is_modifier === true;
 
         if (isFirstModifier) {
-            // It's a combo (e.g., LCTRL was held, then A was pressed)
+            // It's a combo (e.g., LCTRL or js1_button5 was held, then another input was pressed)
             // The `processedInput` (the A key) already contains the modifier state due to `processInput` logic
             stopDetection('modifier-auto-combo');
             clearPrimaryCountdown();
@@ -578,8 +577,7 @@ async function loadKeybindingsFile() {
         // Persist file path so we know where to save
         localStorage.setItem('keybindingsFilePath', filePath);
 
-        // Cache only the user customizations (delta), not the full merged view
-        // This keeps the cache small and prevents stale data issues
+        // Drop any legacy localStorage delta - the backend session tracks edits now
         await cacheUserCustomizations();
 
         // Reset unsaved changes flag
@@ -590,6 +588,7 @@ async function loadKeybindingsFile() {
         // Update UI
         displayKeybindings();
         updateFileIndicator(filePath);
+        await watchLoadedFile();
 
         // Refresh the visual view if it's loaded and visible
         if (window.refreshVisualView) {
@@ -604,6 +603,28 @@ async function loadKeybindingsFile() {
 
 export async function loadPersistedKeybindings() {
     try {
+        // The backend journals every edit and recovers the last session on startup
+        const session = await invoke('get_session_state');
+        if (session.has_bindings) {
+            console.log('Restoring session from backend:', session);
+
+            currentFilename = session.file_name;
+            currentKeybindings = await invoke('get_merged_bindings');
+            hasUnsavedChanges = session.has_unsaved_changes;
+            localStorage.setItem('hasUnsavedChanges', hasUnsavedChanges.toString());
+
+            displayKeybindings();
+            if (session.file_path) {
+                updateFileIndicator(session.file_path);
+                await watchLoadedFile();
+            } else {
+                showUnsavedFileIndicator();
+            }
+            updateUnsavedIndicator();
+            return;
+        }
+
+        // Fallback for sessions cached by older versions
         const savedPath = localStorage.getItem('keybindingsFilePath');
         const cachedUnsavedState = localStorage.getItem('hasUnsavedChanges');
         const cachedDelta = localStorage.getItem('userCustomizationsDelta');
@@ -669,6 +690,7 @@ export async function loadPersistedKeybindings() {
 
                 displayKeybindings();
                 updateFileIndicator(savedPath);
+                await watchLoadedFile();
                 return;
             } catch (error) {
                 console.error('Error loading persisted file:', error);
@@ -744,26 +766,11 @@ function showWelcomeScreen() {
 }
 
 /**
- * Cache only the user's customizations (delta) to localStorage.
- * This is much smaller than caching the full merged view and prevents stale data issues.
+ * The backend persists its own session (snapshot + journal in app data), so the
+ * localStorage delta older versions cached is only cleared here.
  */
 async function cacheUserCustomizations() {
-    try {
-        // Get the user's customizations from backend (just the delta, not merged with AllBinds)
-        const userCustomizations = await invoke('get_user_customizations');
-
-        // Cache the delta - this is typically < 100 KB vs 25+ MB for full merged view
-        localStorage.setItem('userCustomizationsDelta', JSON.stringify(userCustomizations));
-
-        console.log('Cached user customizations delta:', {
-            hasData: !!userCustomizations,
-            actionMapsCount: userCustomizations?.action_maps?.length || 0,
-            profileName: userCustomizations?.profile_name
-        });
-    } catch (error) {
-        console.error('Failed to cache user customizations:', error);
-        // Non-critical error - we can always reload from file
-    }
+    localStorage.removeItem('userCustomizationsDelta');
 }
 
 async function newKeybinding() {
@@ -1110,6 +1117,7 @@ async function saveKeybindingsAs() {
 
         updateFileIndicator(filePath);
         updateCopyCommandButtonVisibility();
+        await watchLoadedFile();
 
         // Clear unsaved changes flag
         hasUnsavedChanges = false;
@@ -1670,10 +1678,9 @@ async function refreshBindings() {
 
         // Update working copy with latest changes
         if (currentKeybindings) {
-            // Cache only the user customizations (delta), not the full merged view
+            // Edits are journaled by the backend; only the unsaved flag lives here
             await cacheUserCustomizations();
             localStorage.setItem('hasUnsavedChanges', hasUnsavedChanges.toString());
-            console.log('Updated user customizations delta in localStorage');
         }
 
         renderKeybindings();
@@ -1682,6 +1689,87 @@ async function refreshBindings() {
     }
 }
 
+// Watch the loaded layout (and the installations' mapping folders) for changes made
+// outside the app, e.g. by Star Citizen rewriting its mapping file
+async function watchLoadedFile() {
+    try {
+        const scInstallDirectory = localStorage.getItem('scInstallDirectory');
+        const installations = scInstallDirectory
+            ? await invoke('scan_sc_installations', {basePath: scInstallDirectory}).catch(() => [])
+            : [];
+        await invoke('start_file_watch', {installationPaths: installations.map(i => i.path)});
+    } catch (error) {
+        console.error('Error starting file watch:', error);
+    }
+}
+
+let externalChangePrompt = null;
+
+async function handleExternalFileChange(event) {
+    const change = event.payload;
+    console.log('External file change:', change);
+
+    // Only the loaded layout needs a decision; other mapping files aren't in the editor
+    if (!change.is_loaded_file || externalChangePrompt) return;
+
+    externalChangePrompt = (async () => {
+        if (change.kind === 'removed') {
+            await window.showAlert(
+                `The loaded keybindings file was removed:\n${change.path}\n\nSaving will recreate it.`,
+                'File Removed'
+            );
+            return;
+        }
+
+        // Without edits to lose, just pick up the new version
+        let resolution = 'reload';
+        if (hasUnsavedChanges) {
+            const merge = await window.showConfirmation(
+                `The loaded keybindings file was changed outside the app:\n${change.path}\n\n` +
+                'Merge keeps your unsaved edits and takes the other changes. Reload discards your edits.',
+                'File Changed',
+                'Merge',
+                'Reload'
+            );
+            resolution = merge ? 'merge' : 'reload';
+        }
+
+        const result = await invoke('resolve_external_change', {filePath: change.path, resolution});
+        currentKeybindings = await invoke('get_merged_bindings');
+
+        const session = await invoke('get_session_state');
+        hasUnsavedChanges = session.has_unsaved_changes;
+        localStorage.setItem('hasUnsavedChanges', hasUnsavedChanges.toString());
+        updateUnsavedIndicator();
+        displayKeybindings();
+
+        if (window.refreshVisualView) {
+            await window.refreshVisualView();
+        }
+
+        const conflicts = result.merge_report?.conflicts || [];
+        if (conflicts.length > 0) {
+            const list = conflicts
+                .map(c => `${c.action} (${c.device_type}): kept ${c.ours || 'unbound'}, file had ${c.theirs || 'unbound'}`)
+                .join('\n');
+            await window.showAlert(`Your edits were kept for ${conflicts.length} binding(s):\n\n${list}`, 'Merge Conflicts');
+        } else {
+            window.showSuccessMessage(resolution === 'merge' ? 'Merged external changes' : 'Reloaded from disk');
+        }
+    })();
+
+    try {
+        await externalChangePrompt;
+    } catch (error) {
+        console.error('Error handling external file change:', error);
+        await window.showAlert(`Failed to take the external change on board: ${error}`, 'Error');
+    } finally {
+        externalChangePrompt = null;
+    }
+}
+
+listen('bindings-file-changed', handleExternalFileChange);
+
 // Helper function to process a raw input result
 const processInput = (result) => {
     console.log('INPUT DETECTED (raw):', result.display_name, result.input_string);
@@ -1845,7 +1933,7 @@ async function startBinding(actionMapName, actionName, actionDisplayName = null)
             const scButton = buttonMap[event.button] || `mouse${event.button + 1}`;
 
             const synthetic = {
-                input_string: scButton,
+                input_string: `mouse1_${scButton}`,
                 display_name: displayNameMap[scButton] || `Mouse Button ${event.button}`,
                 device_type: 'Mouse',
                 modifiers: []
@@ -1876,28 +1964,46 @@ async function startBinding(actionMapName, actionName, actionDisplayName = null)
         window.mouseDetectionActive = true;
 
         // 3. Keyboard Listeners
+        // Keys are detected by the backend like every other device. When it can't read any
+        // keyboard (on Linux that needs membership of the "input" group), the modal's own key
+        // events are used instead. Either way keys mustn't also press the modal's buttons
+        const backendKeyboard = await invoke('keyboard_available').catch(() => false);
+        if (!backendKeyboard) {
+            const note = document.createElement('div');
+            note.className = 'input-confirm-note';
+            note.textContent = 'Keys are only detected while this window has focus. On Linux, add your user to the "input" group to detect keys through the input devices.';
+            statusEl.appendChild(note);
+        }
+
         keyboardDetectionActive = true;
         keyboardDetectionHandler = (event) => {
             if (!isDetectionActive || !keyboardDetectionActive) return;
             event.preventDefault();
             event.stopPropagation();
+            if (backendKeyboard || event.repeat) return;
 
-            // Modifier Tracking
+            // Remember which side each modifier was pressed on
             if (event.code === 'AltLeft') window._lastAltKeyPressed = 'LALT';
             if (event.code === 'AltRight') window._lastAltKeyPressed = 'RALT';
-            // ... (keep rest of modifier tracking from original code)
+            if (event.code === 'ShiftLeft') window._lastShiftKeyPressed = 'LSHIFT';
+            if (event.code === 'ShiftRight') window._lastShiftKeyPressed = 'RSHIFT';
+            if (event.code === 'ControlLeft') window._lastCtrlKeyPressed = 'LCTRL';
+            if (event.code === 'ControlRight') window._lastCtrlKeyPressed = 'RCTRL';
 
             const scKey = convertKeyCodeToSC(event.code, event.key);
+            const isModifier = ['lalt', 'ralt', 'lctrl', 'rctrl', 'lshift', 'rshift'].includes(scKey);
+            // A modifier key isn't its own modifier
             const modifiers = [];
-            if (event.shiftKey && scKey !== 'lshift' && scKey !== 'rshift') modifiers.push('LSHIFT'); // Simplified logic for brevity
-            if (event.ctrlKey && scKey !== 'lctrl' && scKey !== 'rctrl') modifiers.push('LCTRL');
             if (event.altKey && scKey !== 'lalt' && scKey !== 'ralt') modifiers.push(window._lastAltKeyPressed || 'LALT');
+            if (event.ctrlKey && scKey !== 'lctrl' && scKey !== 'rctrl') modifiers.push(window._lastCtrlKeyPressed || 'LCTRL');
+            if (event.shiftKey && scKey !== 'lshift' && scKey !== 'rshift') modifiers.push(window._lastShiftKeyPressed || 'LSHIFT');
 
             const synthetic = {
                 input_string: `kb1_${scKey}`,
                 display_name: `Keyboard - ${event.code}`,
                 device_type: 'Keyboard',
-                modifiers: modifiers
+                modifiers: modifiers,
+                is_modifier: isModifier
             };
 
             handleDetectedInput(processInput(synthetic));
@@ -2075,7 +2181,8 @@ function applyJoystickMapping(inputString, deviceUuid) {
         // console.log(`[KEYBINDINGS] Applied local override: ${autoDetectedPrefix} -> ${finalPrefix}`);
     }
 
-    return inputString.replace(/^(js|gp)\d+_/, `${finalPrefix}_`);
+    // Chords repeat the device prefix, e.g. "js1_button5+js1_button3"
+    return inputString.replace(new RegExp(`(^|\\+)${match[1]}${match[2]}_`, 'g'), `$1${finalPrefix}_`);
 }
 
 
@@ -1,5 +1,4 @@
 ﻿const { invoke } = window.__TAURI__.core;
-const { open, save } = window.__TAURI__.dialog;
 import { loadPersistedKeybindings } from './keybindings-page.js';
 import { toStarCitizenFormat } from './input-utils.js';
 import { initializeUpdateChecker } from './update-checker.js';
@@ -555,6 +554,11 @@ function switchBindingsView(viewName)
   // Save to localStorage
   localStorage.setItem('bindingsView', viewName);
 
+  if (viewName !== 'visual' && window.stopLiveInput)
+  {
+    window.stopLiveInput();
+  }
+
   // If switching to visual view, initialize and refresh it
   if (viewName === 'visual')
   {
@@ -612,11 +616,8 @@ function initializeSettingsPage()
     {
       try
       {
-        const selectedPath = await open({
-          directory: true,
-          multiple: false,
-          title: 'Select Star Citizen Installation Directory'
-        });
+        // The backend opens the dialog so only a folder the user picked becomes accessible
+        const selectedPath = await invoke('choose_folder', { role: 'sc_base' });
 
         if (selectedPath)
         {
@@ -754,6 +755,12 @@ function switchTab(tabName)
 
   currentTab = tabName;
 
+  // Live input only shows in the visual bindings view
+  if (tabName !== 'bindings' && window.stopLiveInput)
+  {
+    window.stopLiveInput();
+  }
+
   // Update active tab button
   document.querySelectorAll('.tab-btn').forEach(btn =>
   {
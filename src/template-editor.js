@@ -1,10 +1,9 @@
 // Safely access TAURI APIs
-let invoke, open, save;
+let invoke;
 
 if (window.__TAURI__)
 {
     invoke = window.__TAURI__.core.invoke;
-    ({ open, save } = window.__TAURI__.dialog);
 }
 
 // Import shared rendering utilities
@@ -532,6 +531,12 @@ function initializeEventListeners()
 
 
 
+    document.getElementById('template-joystick-model').addEventListener('input', (e) =>
+    {
+        templateData.joystickModel = e.target.value;
+        markAsChanged();
+    });
+
     // Legacy image controls removed - per-page images now handled in template page modal
     document.getElementById('new-template-btn').addEventListener('click', newTemplate);
     document.getElementById('add-button-btn').addEventListener('click', startAddButton);
@@ -1124,6 +1129,8 @@ async function newTemplate()
         leftStick: { joystickNumber: 1, buttons: [] },
         rightStick: { joystickNumber: 2, buttons: [] },
         version: '1.0',
+        joystickModel: '',
+        hardwareIds: [],
         pages: []
     };
 
@@ -1132,6 +1139,7 @@ async function newTemplate()
 
     // Reset UI
     document.getElementById('template-name').value = '';
+    document.getElementById('template-joystick-model').value = '';
 
     // Reset canvas
     loadedImage = null;
@@ -3139,7 +3147,9 @@ function prepareSaveData()
     // Prepare data for saving - only pages array is used now
     return {
         name: templateData.name,
-        version: templateData.version || '1.0',
+        version: '2.0',
+        joystickModel: templateData.joystickModel || undefined,
+        hardwareIds: templateData.hardwareIds || [],
         imageWidth: loadedImage ? loadedImage.width : 0,
         imageHeight: loadedImage ? loadedImage.height : 0,
         pages: Array.isArray(templateData.pages) ? templateData.pages.map(page => ({
@@ -3212,10 +3222,12 @@ async function performSave(filePath, showNotification = true)
     {
         const saveData = prepareSaveData();
 
-        await invoke('save_template', {
+        // The backend validates the template and rejects it with the blocking issues
+        const issues = await invoke('save_template', {
             filePath,
-            templateJson: JSON.stringify(saveData, null, 2)
+            template: saveData
         });
+        issues.forEach(issue => console.warn('[TEMPLATE]', issue.page || '', issue.button || '', issue.message));
 
         // Update current file path for future saves
         currentTemplateFilePath = filePath;
@@ -3332,11 +3344,9 @@ async function saveTemplate()
             resourceDir = undefined;
         }
 
-        const filePath = await save({
-            filters: [{
-                name: 'Joystick Template',
-                extensions: ['json']
-            }],
+        const filePath = await invoke('choose_file', {
+            save: true,
+            filter: { name: 'Joystick Template', extensions: ['json'] },
             defaultPath: resourceDir
         });
 
@@ -3405,11 +3415,9 @@ async function saveTemplateAs()
         }
 
         // Always show file picker for Save As
-        const filePath = await save({
-            filters: [{
-                name: 'Joystick Template',
-                extensions: ['json']
-            }],
+        const filePath = await invoke('choose_file', {
+            save: true,
+            filter: { name: 'Joystick Template', extensions: ['json'] },
             defaultPath: resourceDir
         });
 
@@ -3437,25 +3445,21 @@ async function loadTemplate()
             console.warn('Could not get resource directory:', e);
         }
 
-        const filePath = await open({
-            filters: [{
-                name: 'Joystick Template',
-                extensions: ['json']
-            }],
-            multiple: false,
+        const filePath = await invoke('choose_file', {
+            save: false,
+            filter: { name: 'Joystick Template', extensions: ['json'] },
             defaultPath: defaultPath
         });
 
         if (!filePath) return; // User cancelled
 
-        const templateJson = await invoke('load_template', { filePath });
-        const data = JSON.parse(templateJson);
-
-        // Migrate template if needed (v1.0 -> v1.1)
-        if (data.version === '1.0' || !data.version)
+        // Older left/right templates come back already upgraded to the paged format
+        const { template: data, migrated_from: migratedFrom, issues } = await invoke('load_template', { filePath });
+        if (migratedFrom)
         {
-            migrateTemplateToV11(data);
+            console.log(`[TEMPLATE] Upgraded from version ${migratedFrom} to ${data.version}`);
         }
+        issues.forEach(issue => console.warn('[TEMPLATE]', issue.page || '', issue.button || '', issue.message));
 
         // Load the data - handle both old and new formats
         templateData.name = data.name || '';
@@ -3494,6 +3498,8 @@ async function loadTemplate()
             templateData.leftStick = { joystickNumber: 1, buttons: [] };
             templateData.rightStick = { joystickNumber: 2, buttons: [] };
         }
+        templateData.joystickModel = data.joystickModel || '';
+        templateData.hardwareIds = data.hardwareIds || [];
         templateData.version = data.version || '1.0';
         templateData.pages = Array.isArray(data.pages) ? data.pages : [];
         ensureTemplatePages();
@@ -3508,8 +3514,8 @@ async function loadTemplate()
         // Set current file path for auto-save
         currentTemplateFilePath = filePath;
 
-        // Reset unsaved changes
-        hasUnsavedChanges = false;
+        // Reset unsaved changes; an upgraded file still needs saving in the new format
+        hasUnsavedChanges = !!migratedFrom;
         updateTemplateUnsavedIndicator();
 
         // Update file indicator with path
@@ -3530,6 +3536,7 @@ async function loadTemplate()
 
         // Update UI
         document.getElementById('template-name').value = templateData.name;
+        document.getElementById('template-joystick-model').value = templateData.joystickModel;
 
         // Load the first page's image if we have pages
         if (currentPageId && templateData.pages.length > 0)
@@ -3768,6 +3775,8 @@ function loadPersistedTemplate()
                 templateData.rightStick = { joystickNumber: 2, buttons: [] };
             }
 
+            templateData.joystickModel = data.joystickModel || '';
+            templateData.hardwareIds = data.hardwareIds || [];
             templateData.version = data.version || '1.0';
             templateData.pages = Array.isArray(data.pages) ? data.pages : [];
             ensureTemplatePages();
@@ -3775,6 +3784,7 @@ function loadPersistedTemplate()
 
             // Update UI
             document.getElementById('template-name').value = templateData.name;
+            document.getElementById('template-joystick-model').value = templateData.joystickModel;
 
             // Update file indicator based on whether we have a saved file path
             if (currentTemplateFilePath)